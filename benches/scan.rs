@@ -0,0 +1,47 @@
+//! Throughput of parsing a listing buried in a large CI log, with and
+//! without the pre-scan. Run with `cargo bench`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use brune::{parse_branch_lines_with, ParseMode};
+
+
+/// A million lines of build output with a 100 branch listing in the middle.
+fn ci_log() -> String {
+    let mut log = String::new();
+    for i in 0..500_000 {
+        log.push_str(&format!("[info] Compiling module {} of 1000000\n", i));
+    }
+    for i in 0..100 {
+        log.push_str(&format!("  feature/{} {:08x} [origin/feature/{}: gone] Work on {}\n", i, i, i, i));
+    }
+    for i in 0..500_000 {
+        log.push_str(&format!("    at step {} (Runner.java:{})\n", i, i % 500));
+    }
+    log
+}
+
+/// The fastest of a few runs, so noise from other work on the machine
+/// doesn't count against either mode.
+fn fastest(input: &str, mode: ParseMode) -> Duration {
+    (0..5)
+        .map(|_| {
+            let start = Instant::now();
+            let branches = parse_branch_lines_with(black_box(input), mode).unwrap();
+            assert_eq!(branches.len(), 100);
+            start.elapsed()
+        })
+        .min()
+        .unwrap_or_default()
+}
+
+fn main() {
+    let log = ci_log();
+    let megabytes = log.len() as f64 / 1_000_000.0;
+
+    for (name, mode) in [("lenient", ParseMode::Lenient), ("scan", ParseMode::Scan)] {
+        let elapsed = fastest(&log, mode);
+        println!("{:<8} {:>8.1?} {:>8.1} MB/s", name, elapsed, megabytes / elapsed.as_secs_f64());
+    }
+}
@@ -0,0 +1,129 @@
+//! Backups of pruned branches, so a mistaken deletion can be recovered.
+//!
+//! Before deleting a branch prune points a ref at its tip under
+//! `refs/brune/backup/<time>/<branch>`, where `<time>` is when the prune
+//! started in seconds since the epoch. Every branch deleted by one prune
+//! shares a time. Being refs, backups keep their commits from being garbage
+//! collected and are seen by `git log --all`. `brune undo` restores the
+//! branches of the latest prune and drops their backups.
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use crate::error::BruneError;
+use crate::git::{self, Action};
+use crate::HexValue;
+
+
+/// Where backups are kept.
+pub const BACKUP_REFS: &str = "refs/brune/backup";
+
+
+/// The ref backing up `branch` for a prune started at `time`.
+pub fn backup_ref(time: i64, branch: &str) -> String {
+    format!("{}/{}/{}", BACKUP_REFS, time, branch)
+}
+
+/// A backed up branch.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Backup {
+    /// When the prune that deleted the branch started, in seconds since the epoch.
+    pub time: i64,
+    pub branch: String,
+    pub commit: HexValue
+}
+
+impl Backup {
+    pub fn backup_ref(&self) -> String {
+        backup_ref(self.time, &self.branch)
+    }
+
+    /// The actions that recreate the branch and then drop its backup.
+    pub fn restore_actions(&self) -> Vec<Action> {
+        vec![
+            Action::CreateBranch { name: self.branch.clone(), commit: self.commit.to_string() },
+            Action::DeleteRef(self.backup_ref())
+        ]
+    }
+}
+
+/// The `--format` used to list backups with `git for-each-ref`.
+const FORMAT: &str = "--format=%(refname)%09%(objectname)";
+
+/// Parses the backups listed by `git for-each-ref` with [`FORMAT`],
+/// ignoring any ref not laid out as prune leaves them.
+pub fn parse_backups(listing: &str) -> Vec<Backup> {
+    let prefix = format!("{}/", BACKUP_REFS);
+    listing
+        .lines()
+        .filter_map(|line| {
+            let (refname, commit) = line.split_once('\t')?;
+            let (time, branch) = refname.strip_prefix(&prefix)?.split_once('/')?;
+            Some(Backup { time: time.parse().ok()?, branch: branch.to_string(), commit: HexValue(commit.to_string()) })
+        })
+        .collect()
+}
+
+/// Every backup in the repository at `dir`, oldest prune first.
+pub fn list(dir: &std::path::Path) -> Result<Vec<Backup>, BruneError> {
+    let mut backups = parse_backups(&git::run_git(dir, &["for-each-ref", FORMAT, BACKUP_REFS])?);
+    backups.sort_by_key(|b| b.time);
+    Ok(backups)
+}
+
+/// The backups made by the latest prune.
+pub fn latest(backups: &[Backup]) -> Vec<&Backup> {
+    let time = backups.iter().map(|b| b.time).max();
+    backups.iter().filter(|b| Some(b.time) == time).collect()
+}
+
+/// The action that backs up `branch` before a prune started at `time`
+/// deletes it.
+pub fn backup_action(time: i64, branch: &str) -> Action {
+    Action::UpdateRef { name: backup_ref(time, branch), target: format!("refs/heads/{}", branch) }
+}
+
+
+#[test]
+fn backup_branch_ref() {
+    let action = backup_action(1_700_000_000, "feature/x");
+    assert_eq!(action.to_string(), "git update-ref refs/brune/backup/1700000000/feature/x refs/heads/feature/x");
+}
+
+/// 1. Branch names with slashes
+/// 2. Refs not made by prune are ignored
+/// 3. Only the latest prune is restored
+#[test]
+fn backup_listing() {
+    let listing = "refs/brune/backup/10/feature/x\teeee3333\nrefs/brune/backup/other\tffff1111\nrefs/brune/backup/20/y\tdddd3333\n";
+    let backups = parse_backups(listing);
+    assert_eq!(backups, vec![
+        Backup { time: 10, branch: "feature/x".to_string(), commit: HexValue("eeee3333".to_string()) },
+        Backup { time: 20, branch: "y".to_string(), commit: HexValue("dddd3333".to_string()) },
+    ]);
+    assert_eq!(latest(&backups), vec![&backups[1]]);
+}
+
+#[test]
+fn backup_restore() {
+    let dir = git::temp_repo("restore");
+    git::perform(&dir, &backup_action(1, "feature/a")).unwrap();
+    git::perform(&dir, &Action::DeleteBranch("feature/a".to_string())).unwrap();
+
+    let backups = list(&dir).unwrap();
+    for action in backups[0].restore_actions() {
+        git::perform(&dir, &action).unwrap()
+    }
+    assert!(git::run_git(&dir, &["rev-parse", "--verify", "-q", "refs/heads/feature/a"]).is_ok());
+    assert!(list(&dir).unwrap().is_empty());
+}
+
+#[test]
+fn backup_survives_deletion() {
+    let dir = git::temp_repo("backup");
+    git::perform(&dir, &backup_action(1, "feature/a")).unwrap();
+    git::perform(&dir, &Action::DeleteBranch("feature/a".to_string())).unwrap();
+
+    assert!(git::run_git(&dir, &["rev-parse", "--verify", "-q", &backup_ref(1, "feature/a")]).is_ok());
+}
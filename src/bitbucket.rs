@@ -0,0 +1,128 @@
+//! Looking up pull requests on Bitbucket Cloud and Bitbucket Server.
+//!
+//! Remotes on `bitbucket.org` use the Cloud API. Any other host with
+//! `bitbucket` in its name is treated as a Server (Data Center) instance.
+//! `BITBUCKET_API_URL` overrides the API root for either, and a
+//! `BITBUCKET_TOKEN` is sent when present.
+
+use std::env;
+use std::io;
+
+use serde::Deserialize;
+
+use crate::provider::{self, Provider};
+use crate::PrState;
+
+
+/// A repository on Bitbucket.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BitbucketRepo {
+    Cloud { workspace: String, repo: String },
+    Server { host: String, project: String, repo: String }
+}
+
+/// Finds the Bitbucket repository a remote URL points at.
+///
+/// Server HTTPS clone URLs have an `scm/` segment before the project,
+/// which is skipped.
+pub fn parse_remote_url(url: &str) -> Option<BitbucketRepo> {
+    let (host, path) = provider::split_remote_url(url)?;
+    if !host.contains("bitbucket") {
+        return None
+    }
+
+    let path = path.strip_prefix("scm/").unwrap_or(path);
+    let (owner, repo) = path.split_once('/')?;
+    if owner.is_empty() || repo.is_empty() || repo.contains('/') {
+        return None
+    }
+
+    Some(if host == "bitbucket.org" {
+        BitbucketRepo::Cloud { workspace: owner.to_string(), repo: repo.to_string() }
+    } else {
+        BitbucketRepo::Server { host: host.to_string(), project: owner.to_string(), repo: repo.to_string() }
+    })
+}
+
+#[derive(Debug, Deserialize)]
+struct PullRequest {
+    state: String
+}
+
+/// Both APIs return a page of pull requests under `values`.
+#[derive(Debug, Deserialize)]
+struct Page {
+    values: Vec<PullRequest>
+}
+
+/// The state of the first (most recent) pull request in a response.
+fn parse_pull_requests(body: &str) -> io::Result<Option<PrState>> {
+    let page: Page = serde_json::from_str(body).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    Ok(page.values.first().map(|pull| match pull.state.as_str() {
+        "MERGED" => PrState::Merged,
+        "OPEN" => PrState::Open,
+        _ => PrState::Closed
+    }))
+}
+
+impl Provider for BitbucketRepo {
+    fn pr_state(&self, branch: &str) -> io::Result<Option<PrState>> {
+        let api = env::var("BITBUCKET_API_URL").ok();
+        let mut request = match self {
+            BitbucketRepo::Cloud { workspace, repo } => {
+                let api = api.unwrap_or_else(|| "https://api.bitbucket.org/2.0".to_string());
+                let url = format!("{}/repositories/{}/{}/pullrequests", api.trim_end_matches('/'), workspace, repo);
+                ureq::get(&url)
+                    .query("q", &format!("source.branch.name=\"{}\"", branch))
+                    .query("sort", "-created_on")
+                    .query("state", "OPEN")
+                    .query("state", "MERGED")
+                    .query("state", "DECLINED")
+                    .query("state", "SUPERSEDED")
+            },
+            BitbucketRepo::Server { host, project, repo } => {
+                let api = api.unwrap_or_else(|| format!("https://{}/rest/api/1.0", host));
+                let url = format!("{}/projects/{}/repos/{}/pull-requests", api.trim_end_matches('/'), project, repo);
+                ureq::get(&url)
+                    .query("at", &format!("refs/heads/{}", branch))
+                    .query("direction", "OUTGOING")
+                    .query("state", "ALL")
+                    .query("order", "NEWEST")
+            }
+        };
+
+        if let Ok(token) = env::var("BITBUCKET_TOKEN") {
+            request = request.set("Authorization", &format!("Bearer {}", token))
+        }
+
+        parse_pull_requests(&provider::fetch(request, branch)?)
+    }
+}
+
+
+/// 1. Cloud over SSH
+/// 2. Server over SSH with a port
+/// 3. Server over HTTPS with the scm segment
+/// 4. Another host
+#[test]
+fn bitbucket_remote_urls() {
+    assert_eq!(parse_remote_url("git@bitbucket.org:team/brune.git"), Some(BitbucketRepo::Cloud { workspace: "team".to_string(), repo: "brune".to_string() }));
+
+    let server = Some(BitbucketRepo::Server { host: "bitbucket.example.com".to_string(), project: "TOOLS".to_string(), repo: "brune".to_string() });
+    assert_eq!(parse_remote_url("ssh://git@bitbucket.example.com:7999/TOOLS/brune.git"), server);
+    assert_eq!(parse_remote_url("https://bitbucket.example.com/scm/TOOLS/brune.git"), server);
+
+    assert_eq!(parse_remote_url("git@github.com:ssanj/brune.git"), None);
+}
+
+/// 1. Merged
+/// 2. Open
+/// 3. Declined
+/// 4. No pull requests
+#[test]
+fn bitbucket_pull_request_states() {
+    assert_eq!(parse_pull_requests(r#"{"values": [{"state": "MERGED"}]}"#).unwrap(), Some(PrState::Merged));
+    assert_eq!(parse_pull_requests(r#"{"values": [{"state": "OPEN"}, {"state": "MERGED"}]}"#).unwrap(), Some(PrState::Open));
+    assert_eq!(parse_pull_requests(r#"{"values": [{"state": "DECLINED"}]}"#).unwrap(), Some(PrState::Closed));
+    assert_eq!(parse_pull_requests(r#"{"values": []}"#).unwrap(), None);
+}
@@ -0,0 +1,629 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+use regex::Regex;
+
+use brune::dates::DateFormat;
+use brune::messages;
+use brune::shield;
+use brune::show;
+use brune::template::Template;
+
+
+/// Find and prune git branches whose upstream is gone.
+#[derive(Debug, Parser)]
+#[command(
+    name = "brune",
+    version,
+    after_help = "Exit status is 0 when no stale branches are listed, 1 when some are, for lint when too many branches break its rules, for policy check when a rule of error severity is broken or for reconcile when a branch isn't on both sides at the same commit, and 2 on errors."
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Report on this repository instead of the current one; can be repeated for one report tagged with each repository (list, gone and merged only)
+    #[arg(long, global = true, value_name = "PATH", conflicts_with_all = ["input_file", "input_command", "input_command_arg", "porcelain", "summary", "exec", "group_by", "current"])]
+    pub repo: Vec<PathBuf>,
+
+    /// Read `git branch -vv` output from this file, or from stdin for `-` even when it is a terminal, instead of stdin or git
+    #[arg(long, global = true, value_name = "PATH")]
+    pub input_file: Option<PathBuf>,
+
+    /// Read the listing from the stdout of this shell command instead, such as one that fetches it over ssh; not --exec, which runs a command for each branch
+    #[arg(long, global = true, value_name = "COMMAND", conflicts_with_all = ["input_file", "input_command_arg"])]
+    pub input_command: Option<String>,
+
+    /// Like --input-command, but runs a program without a shell: the first is the program and the rest its arguments, each given as they are
+    #[arg(long, global = true, value_name = "ARG", conflicts_with = "input_file", allow_hyphen_values = true)]
+    pub input_command_arg: Vec<String>,
+
+    /// Pass this on to the git branch -vv brune runs, such as --sort=-committerdate or a pattern of branches to list; can be repeated. Options that change what git prints, like --format or --color=always, are turned down
+    #[arg(long, global = true, value_name = "ARG", allow_hyphen_values = true, conflicts_with_all = ["input_file", "input_command", "input_command_arg"])]
+    pub git_args: Vec<String>,
+
+    /// Stop the input command when it runs for longer than this, e.g. 30s, 5m or 1h [default: 30s]
+    #[arg(long, global = true, value_name = "INTERVAL", value_parser = brune::watch::parse_interval)]
+    pub input_command_timeout: Option<Duration>,
+
+    /// Only consider branches whose name matches this regex
+    #[arg(long, global = true, value_name = "REGEX")]
+    pub filter: Option<Regex>,
+
+    /// Only consider branches matching this glob; can be repeated
+    #[arg(long, global = true, value_name = "GLOB")]
+    pub include: Vec<String>,
+
+    /// Ignore branches matching this glob; can be repeated
+    #[arg(long, global = true, value_name = "GLOB")]
+    pub exclude: Vec<String>,
+
+    /// Order the branches by this key instead of input order
+    #[arg(long, global = true, value_enum, value_name = "KEY")]
+    pub sort: Option<SortBy>,
+
+    /// Only branches whose upstream is gone, as the gone subcommand does
+    #[arg(long, global = true)]
+    pub gone_only: bool,
+
+    /// Only the checked out branch, printed with its tracking status on one line for shell prompts
+    #[arg(long, global = true, conflicts_with_all = ["group_by", "summary"])]
+    pub current: bool,
+
+    /// Look up the date of each branch's last commit and show it as a column
+    #[arg(long, global = true)]
+    pub dates: bool,
+
+    /// Show dates as they are, e.g. 2024-03-01T09:30:00Z, rather than how long ago they were, e.g. 3 weeks ago
+    #[arg(long, global = true)]
+    pub absolute_dates: bool,
+
+    /// How exact dates are written by the json, ndjson, csv, tsv and html output, and by the human and markdown output when they show dates as they are: iso8601 (the default, in UTC), local (in the local timezone), unix or a strftime pattern in UTC such as "%d %b %Y". Giving it implies --absolute-dates
+    #[arg(long, global = true, value_name = "FORMAT", value_parser = DateFormat::parse)]
+    pub date_format: Option<DateFormat>,
+
+    /// Reverse the order given by --sort
+    #[arg(long, global = true, requires = "sort")]
+    pub reverse: bool,
+
+    /// Only branches whose last commit is older than this, e.g. 30d, 6w or 1y
+    #[arg(long, global = true, value_name = "DURATION", value_parser = brune::filter::parse_duration)]
+    pub older_than: Option<Duration>,
+
+    /// Look up each branch's pull or merge request on the remote's host (uses GITHUB_TOKEN, GITLAB_TOKEN or BITBUCKET_TOKEN when set)
+    #[cfg(feature = "provider")]
+    #[arg(long, global = true)]
+    pub prs: bool,
+
+    /// Send a desktop notification saying how many branches are gone and can be pruned (list and gone only); watch sends one whenever branches newly go gone, and scan and reports on several repositories one when they took longer than --notify-after
+    #[cfg(feature = "notify")]
+    #[arg(long, global = true)]
+    pub notify: bool,
+
+    /// How long scan or a report on several repositories runs before --notify says when it is done, e.g. 30s or 2m [default: 30s]
+    #[cfg(feature = "notify")]
+    #[arg(long, global = true, value_name = "INTERVAL", value_parser = brune::watch::parse_interval, requires = "notify")]
+    pub notify_after: Option<Duration>,
+
+    /// Show branches under a heading per group, with counts (human output only)
+    #[arg(long, global = true, value_enum, value_name = "GROUP", conflicts_with = "porcelain")]
+    pub group_by: Option<GroupBy>,
+
+    /// Print totals of active, gone, ahead, behind, merged and protected branches after the listing, or only the totals for json, csv and tsv
+    #[arg(long, global = true, conflicts_with = "porcelain")]
+    pub summary: bool,
+
+    /// Run this shell command for each branch listed by list, gone, merged or stale instead of printing it, with BRUNE_BRANCH, BRUNE_SHA, BRUNE_STATUS and BRUNE_UPSTREAM set
+    #[arg(long, global = true, value_name = "COMMAND", conflicts_with_all = ["summary", "porcelain", "group_by"])]
+    pub exec: Option<String>,
+
+    /// Log more about what brune is doing: -v for progress, -vv for every git command and skipped line
+    #[arg(short, long, global = true, action = clap::ArgAction::Count, conflicts_with = "quiet")]
+    pub verbose: u8,
+
+    /// Only log errors
+    #[arg(short, long, global = true)]
+    pub quiet: bool,
+
+    /// Parse the listing on this many threads, for repositories with a huge number of branches
+    #[cfg(feature = "parallel")]
+    #[arg(short, long, global = true, value_name = "N", value_parser = clap::value_parser!(u16).range(1..))]
+    pub jobs: Option<u16>,
+
+    /// Fail on the first line that can't be parsed, instead of skipping it with a warning
+    #[arg(long, global = true)]
+    pub strict: bool,
+
+    /// Pick the branch lines, with a hash and an annotation or subject, out of other output such as a CI log, silently ignoring every other line; -v says how many candidate lines were accepted
+    #[arg(long, global = true, conflicts_with = "strict")]
+    pub scan: bool,
+
+    /// Remove this text from the start of each line before parsing, such as `[info] `; can be repeated
+    #[arg(long, global = true, value_name = "PREFIX")]
+    pub strip_prefix: Vec<String>,
+
+    /// Shorten commit hashes to this many digits, failing on any that are shorter, e.g. 40 for full hashes
+    #[arg(long, global = true, value_name = "N", value_parser = clap::value_parser!(u8).range(4..=40))]
+    pub abbrev: Option<u8>,
+
+    /// Don't strip ANSI colour codes from the listing before parsing it
+    #[arg(long, global = true)]
+    pub keep_ansi: bool,
+
+    /// The layout of the branch listing being read
+    #[arg(long, global = true, value_enum, value_name = "LAYOUT", default_value_t = InputFormat::BranchVv)]
+    pub input: InputFormat,
+
+    /// Read the branches of the current repository through libgit2 instead of parsing git's output
+    #[cfg(feature = "git2")]
+    #[arg(long, global = true, conflicts_with_all = ["input_file", "input_command", "input_command_arg", "git_args", "input"])]
+    pub libgit2: bool,
+
+    /// Read defaults from this config file instead of ~/.config/brune/config.toml
+    #[arg(long, global = true, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// Show what would be deleted without changing anything
+    #[arg(long, global = true)]
+    pub dry_run: bool,
+
+    /// Print branch, status, commit and upstream in a format that is stable across versions
+    #[arg(long, global = true, conflicts_with = "format")]
+    pub porcelain: bool,
+
+    /// Terminate porcelain fields with NUL instead of separating them with tabs and newlines
+    #[arg(short = 'z', global = true, requires = "porcelain")]
+    pub nul_terminated: bool,
+
+    /// Never page output that doesn't fit on the terminal through $PAGER or less
+    #[arg(long, global = true)]
+    pub no_pager: bool,
+
+    /// The label of a shield badge
+    #[arg(long, global = true, value_name = "LABEL", default_value = shield::DEFAULT_LABEL)]
+    pub shield_label: String,
+
+    /// How many stale branches a shield badge shows in yellow, with any more turning it red, and how many --count allows before exiting with 1
+    #[arg(long, global = true, value_name = "N", default_value_t = shield::DEFAULT_MAX_GONE)]
+    pub max_gone: usize,
+
+    /// Print only how many branches gone, merged or stale would list, exiting with 1 when there are more than --max-gone
+    #[arg(long, global = true, conflicts_with_all = ["summary", "porcelain", "exec", "group_by", "current", "format", "repo"])]
+    pub count: bool,
+
+    /// The language of brune's messages, such as de, rather than the one LC_ALL, LC_MESSAGES or LANG names; listings and machine formats are the same in every language
+    #[arg(long, global = true, value_name = "LANG", value_parser = messages::parse_lang)]
+    pub lang: Option<String>,
+
+    /// When to colour human output
+    #[arg(long, global = true, value_enum, value_name = "WHEN", default_value_t = ColorChoice::Auto)]
+    pub color: ColorChoice,
+
+    /// How to render the branch list: human, json, ndjson, csv, tsv, markdown, dot, shield, or a template such as '{name}\t{hash}\t{status}' using any of {name}, {hash}, {status}, {type}, {ahead}, {behind}, {upstream}, {remote}, {comment}, {date}, {last_commit_date}, {current}, {worktree}, {merged}, {pr_state} and {provider}
+    #[arg(long, global = true, value_name = "FORMAT", value_parser = parse_format)]
+    pub format: Option<Format>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// List all branches (the default)
+    List,
+    /// List branches whose upstream is gone, the same as list --gone-only
+    Gone,
+    /// List branches already merged into the base branch, safe to delete even if their upstream still exists
+    Merged {
+        /// The branch to check against, by default the remote's default branch or HEAD when that isn't known
+        #[arg(long, value_name = "BRANCH")]
+        base: Option<String>,
+    },
+    /// List branches that are gone, merged or untouched for longer than --older-than (90d by default), most clearly stale first
+    Stale {
+        /// The branch to check for merges against, by default the remote's default branch or HEAD when that isn't known
+        #[arg(long, value_name = "BRANCH")]
+        base: Option<String>,
+    },
+    /// Find every git repository under a directory and list the stale branches of each, as stale does
+    Scan {
+        /// The directory to look in
+        #[arg(value_name = "DIR", default_value = ".")]
+        dir: PathBuf,
+
+        /// Look at most this many directories deep, where DIR itself is depth 0
+        #[arg(long, value_name = "N")]
+        max_depth: Option<usize>,
+
+        /// Don't look in directories whose name or path relative to DIR matches this glob, e.g. node_modules; can be repeated
+        #[arg(long, value_name = "GLOB")]
+        ignore: Vec<String>,
+
+        /// The branch to check for merges against in each repository, by default its remote's default branch or HEAD when that isn't known
+        #[arg(long, value_name = "BRANCH")]
+        base: Option<String>,
+    },
+    /// Write the whole analysis, with totals, the age of the branches and a sortable table of them, to a self-contained HTML file
+    Report {
+        /// The file to write
+        #[arg(long, value_name = "PATH")]
+        html: PathBuf,
+    },
+    /// Check branch names against the naming rules under [lint] in the config, exiting with 1 when more break them than --max-violations allows
+    Lint {
+        /// How many violations are allowed before exiting with 1, 0 unless the config says otherwise
+        #[arg(long, value_name = "N")]
+        max_violations: Option<usize>,
+
+        /// Check protected branches and the default branch too, which are exempt otherwise
+        #[arg(long)]
+        include_protected: bool,
+
+        /// Print GitHub Actions annotations, as errors when lint fails and warnings otherwise
+        #[arg(long)]
+        github: bool,
+    },
+    /// Check the branches against the hygiene rules under [policy] in the config
+    Policy {
+        #[command(subcommand)]
+        command: PolicyCommand,
+    },
+    /// Look at the branches every so often and print only what changed: branches newly gone and branches deleted
+    Watch {
+        /// How long to wait between looks, e.g. 30s, 5m or 1h
+        #[arg(long, value_name = "INTERVAL", default_value = "60s", value_parser = brune::watch::parse_interval)]
+        interval: Duration,
+    },
+    /// List remote branches that have no local counterpart
+    Remote {
+        /// Read `git branch -r -vv` output from this file instead of running git
+        #[arg(long, value_name = "PATH")]
+        remote_file: Option<PathBuf>,
+    },
+    /// Line the local branches up with a remote's: those only local, to push or delete, those only on the remote, and those on both, at the same commit or not
+    Reconcile {
+        /// Read the local branches from this `git branch -vv` listing instead, along with REMOTE
+        #[arg(value_name = "LOCAL", requires = "remote_file")]
+        local_file: Option<PathBuf>,
+
+        /// Read the remote's branches from this `git ls-remote` or `git branch -r -vv` listing
+        #[arg(value_name = "REMOTE")]
+        remote_file: Option<PathBuf>,
+
+        /// Ask the remote for its branches with git ls-remote, rather than going by the remote-tracking branches of the last fetch
+        #[arg(long, conflicts_with = "local_file")]
+        check_remote: bool,
+
+        /// The remote to line up with, rather than the configured one or origin
+        #[arg(long, value_name = "NAME")]
+        remote: Option<String>,
+    },
+    /// List the commits a branch has that the default branch hasn't, latest first, with their hash, date, author and subject
+    Show {
+        /// The branch whose commits to list
+        #[arg(value_name = "BRANCH")]
+        branch: String,
+
+        /// Also show the files the branch changed since it left the base, as git diff --stat does
+        #[arg(long)]
+        stat: bool,
+
+        /// Show at most this many of the commits
+        #[arg(long, value_name = "N", default_value_t = show::DEFAULT_MAX_COMMITS)]
+        max_commits: usize,
+
+        /// The branch to compare with, by default the remote's default branch or HEAD when that isn't known
+        #[arg(long, value_name = "BRANCH")]
+        base: Option<String>,
+    },
+    /// Choose branches to delete from an interactive list
+    #[cfg(feature = "tui")]
+    Ui {
+        /// Never delete this branch, in addition to the configured protected branches and the current branch
+        #[arg(long, value_name = "BRANCH")]
+        protect: Vec<String>,
+    },
+    /// Parse one line of `git branch -vv` output and print the branch, to check how brune reads it
+    Parse {
+        /// Show what each step of the parser consumed and where it stopped, to report why a line fails
+        #[arg(long)]
+        trace: bool,
+
+        /// The line, quoted
+        line: String,
+    },
+    /// Print a completion script for your shell, which for bash, zsh and fish completes branch names by running brune
+    Completions {
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+    /// Add a reminder about gone branches to a git hook of the repository, after anything already in it
+    InstallHook {
+        #[arg(value_enum)]
+        hook: HookName,
+
+        /// Fail the hook when there are more gone branches than --max-gone, rather than only printing the reminder
+        #[arg(long)]
+        blocking: bool,
+    },
+    /// Take brune's reminder out of a git hook, leaving the rest of the hook as it was
+    UninstallHook {
+        #[arg(value_enum)]
+        hook: HookName,
+    },
+    /// Print the names a completion script offers, one a line, without looking anything up for them
+    #[command(name = "__complete", hide = true)]
+    Complete {
+        #[command(subcommand)]
+        names: CompleteCommand,
+    },
+    /// Delete the branches whose upstream is gone
+    Prune {
+        /// Don't ask for confirmation before deleting
+        #[arg(short, long)]
+        yes: bool,
+
+        /// Ask about each branch in turn: y(es), n(o), a(ll remaining) or q(uit)
+        #[arg(short, long, conflicts_with = "yes")]
+        interactive: bool,
+
+        /// Never delete this branch, in addition to the configured protected branches and the current branch
+        #[arg(long, value_name = "BRANCH")]
+        protect: Vec<String>,
+
+        /// Allow protected branches to be deleted, saying so for each one, as --force does for protection alone
+        #[arg(long)]
+        include_protected: bool,
+
+        /// Also delete branches merged into the default branch, and delete the upstream of every deleted branch whose upstream still exists with `git push <remote> --delete`
+        #[arg(long)]
+        push_delete: bool,
+
+        /// Don't keep a ref under refs/brune/backup to each deleted branch
+        #[arg(long)]
+        no_backup: bool,
+
+        /// Delete protected branches, branches that stashes were made on, and gone branches with commits on no remote or other branch, which are otherwise skipped, saying which checks each deletion overrides
+        #[arg(long)]
+        force: bool,
+
+        /// Also delete branches whose pull or merge request was merged, for squash merges that leave the upstream behind
+        #[cfg(feature = "provider")]
+        #[arg(long)]
+        merged_prs: bool,
+    },
+    /// Restore the branches deleted by the latest prune from their backups under refs/brune/backup
+    Undo {
+        /// List every backup instead of restoring any
+        #[arg(short, long)]
+        list: bool,
+
+        /// Ask about each branch in turn: y(es), n(o), a(ll remaining) or q(uit)
+        #[arg(short, long, conflicts_with = "list")]
+        interactive: bool,
+
+        /// Only restore these branches, each from its latest backup even if an earlier prune made it
+        #[arg(value_name = "BRANCH", conflicts_with = "list")]
+        branches: Vec<String>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum CompleteCommand {
+    /// The local branches
+    Branches {
+        /// Only the branches with this status
+        #[arg(long, value_enum)]
+        status: Option<BranchState>,
+    },
+    /// The branches with backups, for undo
+    Backups,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum BranchState {
+    /// The upstream is gone
+    Gone,
+    /// The upstream isn't gone, or there is none
+    Active,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum HookName {
+    /// Run after switching branches, where the reminder can't stop anything
+    PostCheckout,
+    /// Run before pushing, which --blocking stops
+    PrePush,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum PolicyCommand {
+    /// Report the rules the branches break, grouped by rule, exiting with 1 when a rule of error severity is broken
+    Check {
+        /// Check protected branches and the default branch too, which rules about single branches pass over otherwise
+        #[arg(long)]
+        include_protected: bool,
+
+        /// Print GitHub Actions annotations, as warnings or errors as the rules are
+        #[arg(long)]
+        github: bool,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum InputFormat {
+    /// `git branch -vv` output
+    BranchVv,
+    /// `git for-each-ref --format='%(HEAD)%09%(refname:short)%09%(upstream:short)%09%(upstream:track)%09%(objectname:short)%09%(subject)' refs/heads` output, or the same without the %(HEAD) and %(upstream:short) fields
+    ForEachRef,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum OutputFormat {
+    Human,
+    Json,
+    /// One JSON object per line, written as each branch is parsed when nothing needs the whole listing
+    Ndjson,
+    Csv,
+    Tsv,
+    /// A GitHub flavored markdown table, to paste into an issue or pull request
+    Markdown,
+    /// A Graphviz digraph of the branches and their upstreams, to render with `dot -Tsvg`
+    Dot,
+    /// A shields.io endpoint badge counting the stale branches, coloured by --max-gone
+    Shield,
+}
+
+/// A built in output format, or a template of the user's.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Format {
+    Builtin(OutputFormat),
+    Template(Template),
+}
+
+/// Anything with a placeholder is a template, so a mistyped format name
+/// is still reported as one.
+pub fn parse_format(input: &str) -> Result<Format, String> {
+    if input.contains('{') {
+        Template::parse(input).map(Format::Template)
+    } else {
+        OutputFormat::from_str(input, true)
+            .map(Format::Builtin)
+            .map_err(|_| format!("unknown format {:?}, expected human, json, ndjson, csv, tsv, markdown, dot, shield or a template with {{placeholders}}", input))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum SortBy {
+    Name,
+    Hash,
+    /// Gone branches first
+    Status,
+    /// Oldest last commit first, implies --dates
+    Age,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum GroupBy {
+    /// The part of the name before the first `/`
+    Prefix,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum ColorChoice {
+    /// Colour when stdout is a terminal and NO_COLOR isn't set
+    Auto,
+    Always,
+    Never,
+}
+
+/// Offers branch names for `--protect`, `--base` and show, and backed up
+/// ones for undo, by asking `brune __complete`.
+const BASH_BRANCHES: &str = r#"
+_brune_with_branches() {
+    local cur="${COMP_WORDS[COMP_CWORD]}" prev="${COMP_WORDS[COMP_CWORD-1]}" word subcommand=""
+    for word in "${COMP_WORDS[@]:1:COMP_CWORD-1}"; do
+        case "${word}" in
+            undo|prune|ui|merged|stale|scan|show) subcommand="${word}"; break ;;
+        esac
+    done
+    if [[ "${prev}" == --protect || "${prev}" == --base || ( "${subcommand}" == show && "${cur}" != -* && "${prev}" != --max-commits ) ]]; then
+        COMPREPLY=( $(compgen -W "$(brune __complete branches 2>/dev/null)" -- "${cur}") )
+    elif [[ "${subcommand}" == undo && "${cur}" != -* && "${prev}" != -* ]]; then
+        COMPREPLY=( $(compgen -W "$(brune __complete backups 2>/dev/null)" -- "${cur}") )
+    else
+        _brune "$@"
+    fi
+}
+
+complete -F _brune_with_branches -o bashdefault -o default brune
+"#;
+
+/// What the BRANCH arguments complete with in zsh.
+const ZSH_BRANCHES: &str = r#"
+_brune_branches() {
+    local -a names
+    names=(${(f)"$(brune __complete branches 2>/dev/null)"})
+    compadd -a names
+}
+
+_brune_backups() {
+    local -a names
+    names=(${(f)"$(brune __complete backups 2>/dev/null)"})
+    compadd -a names
+}
+"#;
+
+const FISH_BRANCHES: &str = r#"
+complete -c brune -n "__fish_brune_using_subcommand undo" -f -a "(brune __complete backups 2>/dev/null)"
+complete -c brune -n "__fish_brune_using_subcommand prune; or __fish_brune_using_subcommand ui" -l protect -f -a "(brune __complete branches 2>/dev/null)"
+complete -c brune -n "__fish_brune_using_subcommand merged; or __fish_brune_using_subcommand stale; or __fish_brune_using_subcommand scan; or __fish_brune_using_subcommand show" -l base -f -a "(brune __complete branches 2>/dev/null)"
+complete -c brune -n "__fish_brune_using_subcommand show" -f -a "(brune __complete branches 2>/dev/null)"
+"#;
+
+/// The completion script for `shell`. For bash, zsh and fish, the branch
+/// arguments complete with the names `brune __complete` prints, where
+/// clap alone would offer files.
+pub fn completion_script(shell: Shell) -> String {
+    let mut script = Vec::new();
+    clap_complete::generate(shell, &mut Cli::command(), "brune", &mut script);
+    let script = String::from_utf8_lossy(&script);
+    match shell {
+        Shell::Bash => format!("{}{}", script, BASH_BRANCHES),
+        Shell::Zsh => {
+            let script = script
+                .lines()
+                .map(|line| match line {
+                    _ if line.starts_with("'*::branches -- ") => line.replace(":_default'", ":_brune_backups'"),
+                    _ if line.starts_with("':branch -- ") => line.replace(":_default'", ":_brune_branches'"),
+                    _ => line.replace(":BRANCH:_default'", ":BRANCH:_brune_branches'")
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            // After the #compdef line, which has to stay first.
+            match script.split_once('\n') {
+                Some((compdef, rest)) => format!("{}\n{}{}\n", compdef, ZSH_BRANCHES, rest),
+                None => script
+            }
+        },
+        Shell::Fish => format!("{}{}", script, FISH_BRANCHES),
+        _ => script.into_owned()
+    }
+}
+
+
+#[test]
+fn cli_definition_is_valid() {
+    Cli::command().debug_assert()
+}
+
+#[test]
+fn completions_include_subcommands_and_flags() {
+    let mut script = Vec::new();
+    clap_complete::generate(Shell::Bash, &mut Cli::command(), "brune", &mut script);
+    let script = String::from_utf8(script).unwrap();
+    assert!(script.contains("prune"));
+    assert!(script.contains("--older-than"));
+}
+
+/// 1. Branch arguments ask brune for names in bash, zsh and fish
+/// 2. The other shells keep clap's script as it is
+#[test]
+fn completions_complete_branches() {
+    let bash = completion_script(Shell::Bash);
+    assert!(bash.contains(r#"compgen -W "$(brune __complete backups 2>/dev/null)""#));
+    assert!(bash.trim_end().ends_with("complete -F _brune_with_branches -o bashdefault -o default brune"));
+
+    let zsh = completion_script(Shell::Zsh);
+    assert!(zsh.starts_with("#compdef brune\n\n_brune_branches() {"));
+    assert!(zsh.contains(":BRANCH:_brune_branches' \\"));
+    assert!(zsh.contains("'*::branches -- Only restore these branches, each from its latest backup even if an earlier prune made it:_brune_backups' \\"));
+    assert!(zsh.contains("':branch -- The branch whose commits to list:_brune_branches' \\"));
+    assert!(!zsh.contains(":BRANCH:_default"));
+
+    let fish = completion_script(Shell::Fish);
+    assert!(fish.contains("-l protect -f -a \"(brune __complete branches 2>/dev/null)\""));
+    assert!(fish.contains("__fish_brune_using_subcommand show\" -f -a \"(brune __complete branches 2>/dev/null)\""));
+
+    let mut elvish = Vec::new();
+    clap_complete::generate(Shell::Elvish, &mut Cli::command(), "brune", &mut elvish);
+    assert_eq!(completion_script(Shell::Elvish).into_bytes(), elvish);
+}
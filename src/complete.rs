@@ -0,0 +1,95 @@
+//! Names for the shell completion scripts to offer, which they get from the
+//! hidden `brune __complete` as the user presses tab.
+
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::backup::Backup;
+use crate::error::BruneError;
+use crate::{for_each_ref, git, BranchLine};
+
+
+/// The branches to offer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Status {
+    /// Those whose upstream is gone.
+    Gone,
+    /// Those whose upstream isn't gone, or that have none.
+    Active
+}
+
+/// The local branches of the repository at `dir`, as git for-each-ref lists
+/// them, without dates, providers or anything else looked up for them, so
+/// completing is quick and never waits on the network.
+pub fn local_branches(dir: &Path) -> Result<Vec<BranchLine>, BruneError> {
+    Ok(for_each_ref::parse_for_each_ref_lines(&git::for_each_ref_listing(dir)?))
+}
+
+/// The names of the `branches` with `status`, or of all of them.
+pub fn branch_names(branches: &[BranchLine], status: Option<Status>) -> Vec<&str> {
+    branches
+        .iter()
+        .filter(|b| match status {
+            Some(Status::Gone) => b.status.is_gone(),
+            Some(Status::Active) => !b.status.is_gone(),
+            None => true
+        })
+        .map(|b| b.branch_name.as_str())
+        .collect()
+}
+
+/// The names of the branches with backups, for undo, each once and the most
+/// recently deleted first.
+pub fn backup_names(backups: &[Backup]) -> Vec<&str> {
+    let mut names: Vec<&str> = Vec::new();
+    for b in backups.iter().rev() {
+        if !names.contains(&b.branch.as_str()) {
+            names.push(&b.branch)
+        }
+    }
+    names
+}
+
+/// One name a line, as they are, for the shell to split.
+pub fn write_names<W: Write>(out: &mut W, names: &[&str]) -> io::Result<()> {
+    names.iter().try_for_each(|name| writeln!(out, "{}", name))
+}
+
+
+/// 1. Every branch, or those with the status
+/// 2. Names are written raw, one a line
+#[test]
+fn complete_branch_names() {
+    let branches = crate::parse_branch_lines("* main 0000aaaa [origin/main] A\n  feature/x 1111bbbb [origin/feature/x: gone] B\n  spike 2222cccc C\n");
+    assert_eq!(branch_names(&branches, None), vec!["main", "feature/x", "spike"]);
+    assert_eq!(branch_names(&branches, Some(Status::Active)), vec!["main", "spike"]);
+
+    let mut out = Vec::new();
+    write_names(&mut out, &branch_names(&branches, Some(Status::Gone))).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), "feature/x\n");
+}
+
+/// 1. Each branch once, latest deleted first
+#[test]
+fn complete_backup_names() {
+    let backups = crate::backup::parse_backups("refs/brune/backup/100/old\t1111bbbb\nrefs/brune/backup/200/feature/x\t2222cccc\nrefs/brune/backup/300/old\t3333dddd\n");
+    assert_eq!(backup_names(&backups), vec!["old", "feature/x"]);
+}
+
+/// 1. Straight from git, with nothing looked up, though the remote is on
+///    GitHub and the commits have dates
+#[test]
+fn complete_local_branches() {
+    let dir = git::temp_repo("complete");
+    for args in [
+        &["remote", "add", "origin", "git@github.com:ssanj/brune.git"][..],
+        &["config", "branch.feature/a.remote", "origin"][..],
+        &["config", "branch.feature/a.merge", "refs/heads/feature/a"][..],
+    ] {
+        git::run_git(&dir, args).unwrap();
+    }
+
+    let branches = local_branches(&dir).unwrap();
+    assert_eq!(branch_names(&branches, Some(Status::Gone)), vec!["feature/a"]);
+    assert!(branches.iter().all(|b| b.last_commit_date.is_none() && b.provider.is_none() && !b.merged));
+}
@@ -0,0 +1,237 @@
+//! Defaults loaded from `~/.config/brune/config.toml`.
+//!
+//! ```toml
+//! protected = ["main", "release"]
+//! format = "human"
+//! remote = "origin"
+//! older_than = "90d"
+//! abbrev = 7
+//! repos = ["~/src/api", "~/src/web"]
+//! notify_after = "30s"
+//!
+//! [lint]
+//! max_violations = 0
+//!
+//! [[lint.rules]]
+//! name = "naming"
+//! structure = "<type>/<ticket>-<slug>"
+//! types = ["feature", "fix", "chore"]
+//!
+//! [[lint.rules]]
+//! name = "short"
+//! pattern = "^.{1,40}$"
+//!
+//! [[policy.rules]]
+//! max_age = "180d"
+//!
+//! [[policy.rules]]
+//! name = "few gone branches"
+//! max_gone = 9
+//! severity = "warn"
+//! ```
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer};
+
+use crate::error::BruneError;
+use crate::policy::Severity;
+use crate::{FULL_HASH_LEN, MIN_HASH_LEN};
+
+
+/// Settings read from the config file. Anything missing falls back to the
+/// built-in default, and command line flags override everything here.
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Replaces the default protected branches.
+    pub protected: Option<Vec<String>>,
+    /// The output format used when `--format` isn't given.
+    pub format: Option<String>,
+    /// The remote branches are expected to track.
+    pub remote: Option<String>,
+    /// How long a branch can go untouched before it counts as old, e.g. `90d`.
+    pub older_than: Option<String>,
+    /// The number of digits commit hashes are shortened to, see `--abbrev`.
+    #[serde(deserialize_with = "deserialize_abbrev")]
+    pub abbrev: Option<usize>,
+    /// Repositories list, gone and merged report on together when no
+    /// `--repo` or `--input-file` is given. A leading `~/` is the home
+    /// directory.
+    pub repos: Option<Vec<String>>,
+    /// How long a run over several repositories takes before `--notify`
+    /// says when it is done, e.g. `30s` or `2m`.
+    pub notify_after: Option<String>,
+    /// The naming rules `brune lint` checks branches against.
+    pub lint: Option<LintConfig>,
+    /// The hygiene rules `brune policy check` checks branches against.
+    pub policy: Option<PolicyConfig>
+}
+
+/// The `[lint]` section.
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct LintConfig {
+    pub rules: Vec<LintRule>,
+    /// How many violations there can be before lint exits with 1.
+    pub max_violations: Option<usize>,
+    /// Check protected branches and the default branch too.
+    pub include_protected: bool
+}
+
+/// A naming rule, given either as a regex `pattern` or as a `structure`
+/// such as `<type>/<ticket>-<slug>` with the `types` allowed.
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct LintRule {
+    pub name: String,
+    pub pattern: Option<String>,
+    pub structure: Option<String>,
+    #[serde(default)]
+    pub types: Vec<String>
+}
+
+/// The `[policy]` section.
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct PolicyConfig {
+    pub rules: Vec<PolicyRule>,
+    /// Check protected branches and the default branch against the rules
+    /// about single branches too.
+    pub include_protected: bool
+}
+
+/// A policy rule, with exactly one of `max_age`, `tracks_upstream`,
+/// `max_gone` and `pattern`. It is named for what it checks unless given a
+/// `name`, and is an error unless its `severity` is `warn`.
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct PolicyRule {
+    pub name: Option<String>,
+    pub severity: Severity,
+    /// How long a branch can go without commits, e.g. `180d`.
+    pub max_age: Option<String>,
+    pub tracks_upstream: bool,
+    pub max_gone: Option<usize>,
+    pub pattern: Option<String>
+}
+
+impl Config {
+    pub fn parse(contents: &str) -> Result<Config, toml::de::Error> {
+        toml::from_str(contents)
+    }
+
+    /// Loads the config at `path`. A missing file is an empty config.
+    pub fn load(path: &Path) -> Result<Config, BruneError> {
+        match fs::read_to_string(path) {
+            Ok(contents) =>
+                Config::parse(&contents)
+                    .map_err(|source| BruneError::Config { path: path.to_path_buf(), source }),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Config::default()),
+            Err(e) => Err(e.into())
+        }
+    }
+}
+
+/// An `abbrev` in the same range as `--abbrev` takes.
+fn deserialize_abbrev<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<usize>, D::Error> {
+    let abbrev = usize::deserialize(deserializer)?;
+    if (MIN_HASH_LEN..=FULL_HASH_LEN).contains(&abbrev) {
+        Ok(Some(abbrev))
+    } else {
+        Err(D::Error::custom(format!("abbrev must be between {} and {}, not {}", MIN_HASH_LEN, FULL_HASH_LEN, abbrev)))
+    }
+}
+
+/// The path, with a leading `~/` expanded to the home directory.
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path)
+    }
+}
+
+/// The default config location, honouring `XDG_CONFIG_HOME`.
+pub fn default_path() -> Option<PathBuf> {
+    let base =
+        env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+
+    Some(base.join("brune").join("config.toml"))
+}
+
+
+#[test]
+fn config_parse() {
+    let config = Config::parse("protected = [\"main\", \"release\"]\nformat = \"human\"\nremote = \"upstream\"\nolder_than = \"90d\"\nabbrev = 12\nrepos = [\"~/src/api\"]\nnotify_after = \"1m\"\n").unwrap();
+    let expected = Config {
+        protected: Some(vec!["main".to_string(), "release".to_string()]),
+        format: Some("human".to_string()),
+        remote: Some("upstream".to_string()),
+        older_than: Some("90d".to_string()),
+        abbrev: Some(12),
+        repos: Some(vec!["~/src/api".to_string()]),
+        notify_after: Some("1m".to_string()),
+        lint: None,
+        policy: None
+    };
+    assert_eq!(config, expected);
+}
+
+/// 1. Rules in both forms
+/// 2. Unknown keys in a rule are rejected
+#[test]
+fn config_parse_lint() {
+    let config = Config::parse("[lint]\nmax_violations = 3\n\n[[lint.rules]]\nname = \"naming\"\nstructure = \"<type>/<slug>\"\ntypes = [\"fix\"]\n\n[[lint.rules]]\nname = \"short\"\npattern = \"^.{1,40}$\"\n").unwrap();
+    let expected = LintConfig {
+        rules: vec![
+            LintRule { name: "naming".to_string(), pattern: None, structure: Some("<type>/<slug>".to_string()), types: vec!["fix".to_string()] },
+            LintRule { name: "short".to_string(), pattern: Some("^.{1,40}$".to_string()), structure: None, types: Vec::new() }
+        ],
+        max_violations: Some(3),
+        include_protected: false
+    };
+    assert_eq!(config.lint, Some(expected));
+    assert!(Config::parse("[[lint.rules]]\nname = \"x\"\nregex = \"y\"\n").is_err());
+}
+
+/// 1. Empty config
+/// 2. Unknown keys are rejected
+/// 3. Hash lengths `--abbrev` wouldn't take are rejected
+#[test]
+fn config_parse_edge_cases() {
+    assert_eq!(Config::parse("").unwrap(), Config::default());
+    assert!(Config::parse("protect = []").is_err());
+    assert!(Config::parse("abbrev = 0").is_err());
+    assert!(Config::parse("abbrev = 2").unwrap_err().to_string().contains("abbrev must be between 4 and 40, not 2"));
+    assert!(Config::parse("abbrev = 41").is_err());
+    assert_eq!(Config::parse("abbrev = 4").unwrap().abbrev, Some(4));
+}
+
+/// 1. Rules default to errors named for their check
+/// 2. Unknown severities are rejected
+#[test]
+fn config_parse_policy() {
+    let config = Config::parse("[[policy.rules]]\nmax_age = \"180d\"\n\n[[policy.rules]]\nname = \"upstreams\"\ntracks_upstream = true\nseverity = \"warn\"\n").unwrap();
+    let expected = PolicyConfig {
+        rules: vec![
+            PolicyRule { max_age: Some("180d".to_string()), ..PolicyRule::default() },
+            PolicyRule { name: Some("upstreams".to_string()), severity: Severity::Warn, tracks_upstream: true, ..PolicyRule::default() }
+        ],
+        include_protected: false
+    };
+    assert_eq!(config.policy, Some(expected));
+    assert!(Config::parse("[[policy.rules]]\nmax_gone = 3\nseverity = \"fatal\"\n").is_err());
+}
+
+#[test]
+fn config_load_missing_file() {
+    let config = Config::load(Path::new("/definitely/not/here/config.toml")).unwrap();
+    assert_eq!(config, Config::default());
+}
@@ -0,0 +1,424 @@
+//! Showing dates: how long ago they were, such as `3 weeks ago`, or exactly,
+//! in the format `--date-format` gives.
+
+#[cfg(feature = "serde")]
+use std::cell::RefCell;
+use std::fmt::Write as _;
+
+#[cfg(feature = "serde")]
+use serde::Serializer;
+
+const MINUTE: i64 = 60;
+const HOUR: i64 = 60 * MINUTE;
+const DAY: i64 = 24 * HOUR;
+const WEEK: i64 = 7 * DAY;
+const MONTH: i64 = 30 * DAY;
+const YEAR: i64 = 365 * DAY;
+
+const WEEKDAYS: [&str; 7] = ["Sunday", "Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday"];
+const MONTHS: [&str; 12] = ["January", "February", "March", "April", "May", "June", "July", "August", "September", "October", "November", "December"];
+
+
+/// How exact dates are written.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum DateFormat {
+    /// ISO 8601 in UTC, e.g. `2023-11-14T22:13:20Z`.
+    #[default]
+    Iso8601,
+    /// ISO 8601 in the local timezone, e.g. `2023-11-15T09:13:20+11:00`.
+    Local,
+    /// Seconds since the Unix epoch, e.g. `1700000000`.
+    Unix,
+    /// A strftime pattern such as `%d %b %Y`, in UTC.
+    Custom(String)
+}
+
+impl DateFormat {
+    /// `iso8601`, `local`, `unix` or a strftime pattern, which has to have
+    /// at least one conversion and only those [`DateFormat::format`]
+    /// knows.
+    pub fn parse(input: &str) -> Result<DateFormat, String> {
+        match input {
+            "iso8601" => Ok(DateFormat::Iso8601),
+            "local" => Ok(DateFormat::Local),
+            "unix" => Ok(DateFormat::Unix),
+            pattern if !pattern.contains('%') =>
+                Err(format!("{:?} is neither iso8601, local nor unix, nor a strftime pattern such as %Y-%m-%d", pattern)),
+            pattern => strftime(pattern, &Civil::of(0, 0)).map(|_| DateFormat::Custom(pattern.to_string()))
+        }
+    }
+
+    /// `secs` since the Unix epoch in this format, in the local timezone
+    /// for [`DateFormat::Local`]. The local timezone is only known on Unix,
+    /// and is UTC elsewhere.
+    pub fn format(&self, secs: i64) -> String {
+        self.format_with_offset(secs, local_offset(secs))
+    }
+
+    /// Like [`DateFormat::format`], with `local` as the local timezone's
+    /// offset from UTC in seconds.
+    pub fn format_with_offset(&self, secs: i64, local: i32) -> String {
+        match self {
+            DateFormat::Iso8601 => iso8601(&Civil::of(secs, 0)),
+            DateFormat::Local => iso8601(&Civil::of(secs, local)),
+            DateFormat::Unix => secs.to_string(),
+            // Checked by parse, so this fails only when built directly.
+            DateFormat::Custom(pattern) => strftime(pattern, &Civil::of(secs, 0)).unwrap_or_else(|_| secs.to_string())
+        }
+    }
+}
+
+
+/// A moment broken down into its calendar date and time of day in a
+/// timezone.
+struct Civil {
+    secs: i64,
+    /// The timezone's offset from UTC in seconds.
+    offset: i32,
+    year: i64,
+    month: i64,
+    day: i64,
+    /// Of the year, from 1.
+    ordinal: i64,
+    /// From 0 for Sunday.
+    weekday: i64,
+    hour: i64,
+    minute: i64,
+    second: i64
+}
+
+impl Civil {
+    fn of(secs: i64, offset: i32) -> Civil {
+        let local = secs.saturating_add(i64::from(offset));
+        let days = local.div_euclid(DAY);
+        let (year, month, day) = civil_from_days(days);
+        let time = local.rem_euclid(DAY);
+        Civil {
+            secs,
+            offset,
+            year,
+            month,
+            day,
+            ordinal: days - days_from_civil(year, 1, 1) + 1,
+            // The epoch was a Thursday.
+            weekday: (days + 4).rem_euclid(7),
+            hour: time / HOUR,
+            minute: time % HOUR / MINUTE,
+            second: time % MINUTE
+        }
+    }
+}
+
+/// Days since the epoch to a `(year, month, day)` date, from Howard
+/// Hinnant's `civil_from_days`.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    (yoe + era * 400 + if month <= 2 { 1 } else { 0 }, month, day)
+}
+
+/// The other way round, from Hinnant's `days_from_civil`.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+fn iso8601(c: &Civil) -> String {
+    let zone = if c.offset == 0 { "Z".to_string() } else { offset(c.offset, ":") };
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}{}", c.year, c.month, c.day, c.hour, c.minute, c.second, zone)
+}
+
+/// An offset from UTC such as `+11:00` or `-0330`.
+fn offset(secs: i32, separator: &str) -> String {
+    let sign = if secs < 0 { '-' } else { '+' };
+    let minutes = secs.unsigned_abs() / 60;
+    format!("{}{:02}{}{:02}", sign, minutes / 60, separator, minutes % 60)
+}
+
+/// `c` written as `pattern` says, with the conversions of C's strftime in
+/// its C locale, other than those for week numbers and eras: `%Y`, `%y`,
+/// `%C`, `%m`, `%d`, `%e`, `%j`, `%H`, `%I`, `%M`, `%S`, `%p`, `%a`, `%A`,
+/// `%b`, `%h`, `%B`, `%u`, `%w`, `%z`, `%Z`, `%s`, `%F`, `%T`, `%R`, `%D`,
+/// `%c`, `%n`, `%t` and `%%`.
+fn strftime(pattern: &str, c: &Civil) -> Result<String, String> {
+    let mut out = String::with_capacity(pattern.len() * 2);
+    let mut chars = pattern.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '%' {
+            out.push(ch);
+            continue
+        }
+        let written = match chars.next() {
+            Some('Y') => write!(out, "{}", c.year),
+            Some('y') => write!(out, "{:02}", c.year.rem_euclid(100)),
+            Some('C') => write!(out, "{:02}", c.year.div_euclid(100)),
+            Some('m') => write!(out, "{:02}", c.month),
+            Some('d') => write!(out, "{:02}", c.day),
+            Some('e') => write!(out, "{:2}", c.day),
+            Some('j') => write!(out, "{:03}", c.ordinal),
+            Some('H') => write!(out, "{:02}", c.hour),
+            Some('I') => write!(out, "{:02}", (c.hour + 11) % 12 + 1),
+            Some('M') => write!(out, "{:02}", c.minute),
+            Some('S') => write!(out, "{:02}", c.second),
+            Some('p') => write!(out, "{}", if c.hour < 12 { "AM" } else { "PM" }),
+            Some('a') => write!(out, "{}", &WEEKDAYS[c.weekday as usize][..3]),
+            Some('A') => write!(out, "{}", WEEKDAYS[c.weekday as usize]),
+            Some('b' | 'h') => write!(out, "{}", &MONTHS[c.month as usize - 1][..3]),
+            Some('B') => write!(out, "{}", MONTHS[c.month as usize - 1]),
+            Some('u') => write!(out, "{}", if c.weekday == 0 { 7 } else { c.weekday }),
+            Some('w') => write!(out, "{}", c.weekday),
+            Some('z') => write!(out, "{}", offset(c.offset, "")),
+            Some('Z') => write!(out, "{}", if c.offset == 0 { "UTC".to_string() } else { offset(c.offset, ":") }),
+            Some('s') => write!(out, "{}", c.secs),
+            Some('F') => write!(out, "{}-{:02}-{:02}", c.year, c.month, c.day),
+            Some('T') => write!(out, "{:02}:{:02}:{:02}", c.hour, c.minute, c.second),
+            Some('R') => write!(out, "{:02}:{:02}", c.hour, c.minute),
+            Some('D') => write!(out, "{:02}/{:02}/{:02}", c.month, c.day, c.year.rem_euclid(100)),
+            Some('c') => write!(out, "{} {} {:2} {:02}:{:02}:{:02} {}", &WEEKDAYS[c.weekday as usize][..3], &MONTHS[c.month as usize - 1][..3], c.day, c.hour, c.minute, c.second, c.year),
+            Some(ch @ ('n' | 't' | '%')) => {
+                out.push(match ch { 'n' => '\n', 't' => '\t', _ => '%' });
+                Ok(())
+            },
+            Some(other) => return Err(format!("%{} in {:?} isn't a conversion brune knows", other, pattern)),
+            None => return Err(format!("{:?} ends in a % with no conversion after it", pattern))
+        };
+        written.map_err(|e| e.to_string())?
+    }
+    Ok(out)
+}
+
+/// The local timezone's offset from UTC at `secs`, with daylight saving
+/// time as it was then.
+#[cfg(unix)]
+fn local_offset(secs: i64) -> i32 {
+    // Truncated where time_t is 32 bits, which is only wrong past 2038.
+    let time = secs as libc::time_t;
+    // SAFETY: an all zero tm is a valid value for localtime_r to overwrite.
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    // SAFETY: both pointers are to live locals, which localtime_r doesn't
+    // keep hold of.
+    let converted = unsafe { libc::localtime_r(&time, &mut tm) };
+    if converted.is_null() { 0 } else { tm.tm_gmtoff as i32 }
+}
+
+#[cfg(not(unix))]
+fn local_offset(_secs: i64) -> i32 {
+    0
+}
+
+
+#[cfg(feature = "serde")]
+thread_local! {
+    static SERIALIZED_FORMAT: RefCell<DateFormat> = const { RefCell::new(DateFormat::Unix) };
+}
+
+/// Runs `serialize` with dates serialized in `format`, which is otherwise
+/// [`DateFormat::Unix`] as numbers.
+#[cfg(feature = "serde")]
+pub fn serializing_as<R>(format: &DateFormat, serialize: impl FnOnce() -> R) -> R {
+    let previous = SERIALIZED_FORMAT.with(|f| f.replace(format.clone()));
+    let result = serialize();
+    SERIALIZED_FORMAT.with(|f| f.replace(previous));
+    result
+}
+
+/// Serializes a date as the innermost [`serializing_as`] says.
+#[cfg(feature = "serde")]
+pub(crate) fn serialize_date<S: Serializer>(date: &Option<i64>, serializer: S) -> Result<S::Ok, S::Error> {
+    SERIALIZED_FORMAT.with(|format| match (date, &*format.borrow()) {
+        (None, _) => serializer.serialize_none(),
+        (Some(date), DateFormat::Unix) => serializer.serialize_some(date),
+        (Some(date), format) => serializer.serialize_some(&format.format(*date))
+    })
+}
+
+
+/// How the human formats show dates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DateStyle {
+    /// When given, dates are shown as how long before or after this, in
+    /// seconds since the Unix epoch, they were.
+    pub relative_to: Option<i64>,
+    /// How dates are shown exactly, otherwise or when there's room.
+    pub format: DateFormat
+}
+
+impl DateStyle {
+    /// Dates as how long before `now` they were.
+    pub fn relative(now: i64) -> DateStyle {
+        DateStyle { relative_to: Some(now), format: DateFormat::default() }
+    }
+
+    /// Dates exactly, as `format` writes them.
+    pub fn absolute(format: DateFormat) -> DateStyle {
+        DateStyle { relative_to: None, format }
+    }
+
+    pub fn render(&self, secs: i64) -> String {
+        match self.relative_to {
+            Some(now) => relative(secs, now),
+            None => self.format.format(secs)
+        }
+    }
+}
+
+
+/// How long before `now` the time `secs` was, in the largest unit it has a
+/// whole one of: seconds, minutes, hours, days, weeks, months of 30 days or
+/// years of 365, e.g. `3 weeks ago`, or `yesterday` for a day. Times after
+/// `now`, as a skewed clock makes them, are `in 2 days` or `tomorrow`.
+///
+/// It counts elapsed time rather than calendar days, so the same dates read
+/// the same in any timezone, and always in English.
+pub fn relative(secs: i64, now: i64) -> String {
+    let elapsed = now.saturating_sub(secs);
+    let (count, unit) = match elapsed.saturating_abs() {
+        0 => return "just now".to_string(),
+        span if span < MINUTE => (span, "second"),
+        span if span < HOUR => (span / MINUTE, "minute"),
+        span if span < DAY => (span / HOUR, "hour"),
+        span if span < WEEK => (span / DAY, "day"),
+        span if span < MONTH => (span / WEEK, "week"),
+        span if span < YEAR => (span / MONTH, "month"),
+        span => (span / YEAR, "year")
+    };
+    let plural = if count == 1 { "" } else { "s" };
+    match (count, unit, elapsed > 0) {
+        (1, "day", true) => "yesterday".to_string(),
+        (1, "day", false) => "tomorrow".to_string(),
+        (_, _, true) => format!("{} {}{} ago", count, unit, plural),
+        (_, _, false) => format!("in {} {}{}", count, unit, plural)
+    }
+}
+
+/// Formats seconds since the Unix epoch as a UTC `YYYY-MM-DD` date.
+pub fn format_date(secs: i64) -> String {
+    let (year, month, day) = civil_from_days(secs.div_euclid(DAY));
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+
+#[cfg(test)]
+const NOW: i64 = 1_700_000_000;
+
+/// 1. Each unit starts at exactly one of it
+/// 2. Just short of the next unit stays in the smaller one
+#[test]
+fn dates_relative_boundaries() {
+    let ago = |secs| relative(NOW - secs, NOW);
+    assert_eq!(ago(0), "just now");
+    assert_eq!(ago(1), "1 second ago");
+    assert_eq!(ago(59), "59 seconds ago");
+    assert_eq!(ago(MINUTE), "1 minute ago");
+    assert_eq!(ago(HOUR - 1), "59 minutes ago");
+    assert_eq!(ago(HOUR), "1 hour ago");
+    assert_eq!(ago(DAY - 1), "23 hours ago");
+    assert_eq!(ago(DAY), "yesterday");
+    assert_eq!(ago(2 * DAY - 1), "yesterday");
+    assert_eq!(ago(2 * DAY), "2 days ago");
+    assert_eq!(ago(WEEK - 1), "6 days ago");
+    assert_eq!(ago(WEEK), "1 week ago");
+    assert_eq!(ago(3 * WEEK), "3 weeks ago");
+    assert_eq!(ago(MONTH - 1), "4 weeks ago");
+    assert_eq!(ago(MONTH), "1 month ago");
+    assert_eq!(ago(YEAR - 1), "12 months ago");
+    assert_eq!(ago(YEAR), "1 year ago");
+    assert_eq!(ago(5 * YEAR + 100 * DAY), "5 years ago");
+}
+
+/// 1. Dates after now, as clock skew makes them
+#[test]
+fn dates_relative_future() {
+    let ahead = |secs| relative(NOW + secs, NOW);
+    assert_eq!(ahead(30), "in 30 seconds");
+    assert_eq!(ahead(HOUR), "in 1 hour");
+    assert_eq!(ahead(DAY), "tomorrow");
+    assert_eq!(ahead(2 * DAY), "in 2 days");
+    assert_eq!(ahead(2 * YEAR), "in 2 years");
+}
+
+/// 1. Far away dates don't overflow
+#[test]
+fn dates_relative_extremes() {
+    assert_eq!(relative(i64::MIN, NOW), format!("{} years ago", i64::MAX / YEAR));
+    assert!(relative(i64::MAX, i64::MIN).starts_with("in "));
+}
+
+/// 1. The epoch
+/// 2. A leap day
+/// 3. Before the epoch
+#[test]
+fn dates_format_date() {
+    assert_eq!(format_date(0), "1970-01-01");
+    assert_eq!(format_date(951_782_400), "2000-02-29");
+    assert_eq!(format_date(-86_400), "1969-12-31");
+}
+
+/// 1. UTC, and the same moment in a timezone 11 hours ahead, the next day
+/// 2. And one behind UTC by a part of an hour
+/// 3. Unix time whatever the timezone
+#[test]
+fn dates_formats() {
+    let sydney = 11 * 3600;
+    assert_eq!(DateFormat::Iso8601.format_with_offset(NOW, sydney), "2023-11-14T22:13:20Z");
+    assert_eq!(DateFormat::Local.format_with_offset(NOW, sydney), "2023-11-15T09:13:20+11:00");
+    assert_eq!(DateFormat::Local.format_with_offset(NOW, -(3 * 3600 + 1800)), "2023-11-14T18:43:20-03:30");
+    assert_eq!(DateFormat::Local.format_with_offset(NOW, 0), "2023-11-14T22:13:20Z");
+    assert_eq!(DateFormat::Unix.format_with_offset(NOW, sydney), "1700000000");
+}
+
+/// 1. Custom patterns, in UTC however the local timezone is set
+/// 2. The other conversions, on a leap day before noon
+#[test]
+fn dates_custom_patterns() {
+    let custom = DateFormat::parse("%d %b %Y, %H:%M %Z").unwrap();
+    assert_eq!(custom.format_with_offset(NOW, 11 * 3600), "14 Nov 2023, 22:13 UTC");
+
+    let leap_day = 951_782_400 + 9 * HOUR + 5 * MINUTE + 7;
+    let all = DateFormat::parse("%Y %y %C %m %d %e %j %H %I %M %S %p %a %A %b %h %B %u %w %z %s %F %T %R %D%n%c%t%%").unwrap();
+    assert_eq!(all.format_with_offset(leap_day, 0), "2000 00 20 02 29 29 060 09 09 05 07 AM Tue Tuesday Feb Feb February 2 2 +0000 951815107 2000-02-29 09:05:07 09:05 02/29/00\nTue Feb 29 09:05:07 2000\t%");
+    assert_eq!(DateFormat::parse("%I%p %e %u").unwrap().format_with_offset(NOW - 22 * HOUR + 4 * DAY, 0), "12AM 18 6");
+}
+
+/// 1. The named formats
+/// 2. Unknown conversions and a dangling % are rejected
+/// 3. So is text without a conversion, most likely a misspelt name
+#[test]
+fn dates_parse_formats() {
+    assert_eq!(DateFormat::parse("iso8601"), Ok(DateFormat::Iso8601));
+    assert_eq!(DateFormat::parse("local"), Ok(DateFormat::Local));
+    assert_eq!(DateFormat::parse("unix"), Ok(DateFormat::Unix));
+    assert_eq!(DateFormat::parse("%Y-%q"), Err("%q in \"%Y-%q\" isn't a conversion brune knows".to_string()));
+    assert_eq!(DateFormat::parse("%Y %"), Err("\"%Y %\" ends in a % with no conversion after it".to_string()));
+    assert!(DateFormat::parse("iso-8601").unwrap_err().contains("neither iso8601, local nor unix"));
+}
+
+/// 1. Dates serialize as numbers unless a format is given
+#[cfg(feature = "serde")]
+#[test]
+fn dates_serialized() {
+    let mut branches = crate::parse_branch_lines("  FeatureC dddd3333 [gone] X\n");
+    branches[0].last_commit_date = Some(NOW);
+    assert!(serde_json::to_string(&branches[0]).unwrap().contains("\"last_commit_date\":1700000000"));
+    let iso = serializing_as(&DateFormat::Iso8601, || serde_json::to_string(&branches[0]).unwrap());
+    assert!(iso.contains("\"last_commit_date\":\"2023-11-14T22:13:20Z\""));
+    assert!(serde_json::to_string(&branches[0]).unwrap().contains("\"last_commit_date\":1700000000"));
+}
+
+/// 1. The style picks the rendering
+#[test]
+fn dates_style() {
+    assert_eq!(DateStyle::relative(NOW).render(NOW - 3 * WEEK), "3 weeks ago");
+    assert_eq!(DateStyle::absolute(DateFormat::Custom("%F".to_string())).render(NOW), "2023-11-14");
+    assert_eq!(DateStyle::absolute(DateFormat::Unix).render(NOW), "1700000000");
+}
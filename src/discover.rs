@@ -0,0 +1,99 @@
+//! Finding the git repositories under a directory.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use globset::GlobSet;
+
+
+/// Limits on how far [`find_repos`] looks.
+#[derive(Debug, Clone, Default)]
+pub struct Discovery {
+    /// How many directories deep to look below the root, which is depth 0.
+    /// Unlimited when not given.
+    pub max_depth: Option<usize>,
+    /// Directories not to look in, matched against their name and their
+    /// path relative to the root, e.g. `node_modules` or `archive/*`.
+    pub ignore: Option<GlobSet>
+}
+
+/// Whether `dir` is the top of a git repository or worktree, where `.git`
+/// is a directory or, for worktrees and submodules, a file.
+fn is_repo(dir: &Path) -> bool {
+    dir.join(".git").exists()
+}
+
+impl Discovery {
+    fn ignores(&self, root: &Path, dir: &Path) -> bool {
+        let ignore = match &self.ignore {
+            Some(ignore) => ignore,
+            None => return false
+        };
+        dir.file_name().is_some_and(|name| ignore.is_match(name)) ||
+            dir.strip_prefix(root).is_ok_and(|relative| ignore.is_match(relative))
+    }
+
+    /// Every repository at or below `root`, sorted by path. Repositories
+    /// aren't looked inside for more, and symlinks aren't followed, so
+    /// links back up the tree can't loop. Directories that can't be read
+    /// are skipped with a warning.
+    pub fn find_repos(&self, root: &Path) -> io::Result<Vec<PathBuf>> {
+        let mut repos = Vec::new();
+        // Checked up front, so a mistyped root is an error rather than a warning.
+        fs::read_dir(root)?;
+
+        let mut pending = vec![(root.to_path_buf(), 0)];
+        while let Some((dir, depth)) = pending.pop() {
+            if is_repo(&dir) {
+                repos.push(dir);
+                continue
+            }
+            if self.max_depth.is_some_and(|max| depth >= max) {
+                continue
+            }
+
+            let entries = match fs::read_dir(&dir) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    tracing::warn!("skipping {}: {}", dir.display(), e);
+                    continue
+                }
+            };
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if entry.file_type().is_ok_and(|t| t.is_dir()) && !self.ignores(root, &path) {
+                    pending.push((path, depth + 1))
+                }
+            }
+        }
+
+        repos.sort();
+        Ok(repos)
+    }
+}
+
+
+/// 1. Repositories at several depths, including a worktree's `.git` file
+/// 2. Not looking inside repositories
+/// 3. Ignored directories, by name and by relative path
+/// 4. The depth limit
+#[test]
+fn discover_repos() {
+    let root = std::env::temp_dir().join(format!("brune-discover-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    for dir in ["api/.git", "api/vendor/lib/.git", "team/web/.git", "node_modules/pkg/.git", "archive/old/.git", "deep/a/b/c/.git"] {
+        fs::create_dir_all(root.join(dir)).unwrap()
+    }
+    fs::create_dir_all(root.join("team/wt")).unwrap();
+    fs::write(root.join("team/wt/.git"), "gitdir: ../web/.git/worktrees/wt\n").unwrap();
+
+    let ignore = crate::filter::glob_set(&["node_modules", "archive/*"]).unwrap();
+    let found = |discovery: Discovery| -> Vec<String> {
+        discovery.find_repos(&root).unwrap().iter().map(|r| r.strip_prefix(&root).unwrap().display().to_string()).collect()
+    };
+
+    assert_eq!(found(Discovery { max_depth: None, ignore: ignore.clone() }), vec!["api", "deep/a/b/c", "team/web", "team/wt"]);
+    assert_eq!(found(Discovery { max_depth: Some(2), ignore }), vec!["api", "team/web", "team/wt"]);
+    assert!(Discovery::default().find_repos(&root.join("missing")).is_err());
+}
@@ -0,0 +1,194 @@
+//! A Graphviz digraph of the local branches and the upstreams they track,
+//! for a visual overview with `dot -Tsvg`.
+//!
+//! Local branches are boxes coloured by their status, and each remote is a
+//! cluster of the upstreams tracked on it, with an edge from every branch
+//! to its upstream, dashed and red when the upstream is gone.
+
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+use crate::remote::RemoteBranchLine;
+use crate::{BranchLine, BranchStatus};
+
+
+const GRAPH_ATTRIBUTES: &str = "rankdir=LR;\n  node [shape=box, style=\"rounded,filled\", fontname=\"Helvetica\"];";
+
+/// `text` as a double quoted DOT identifier. Backslashes are escaped too,
+/// as DOT would read them as the start of an escape such as `\n`.
+pub fn quote(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        if matches!(c, '"' | '\\') {
+            quoted.push('\\')
+        }
+        quoted.push(c)
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// The fill colour of a branch: red when gone, yellow when out of sync,
+/// green when in sync and grey when tracking nothing.
+fn fill_colour(status: &BranchStatus) -> &'static str {
+    match status {
+        BranchStatus::Gone => "#f4cccc",
+        BranchStatus::Ahead(_) | BranchStatus::Behind(_) | BranchStatus::Diverged { .. } => "#fff2cc",
+        BranchStatus::UpToDate => "#d9ead3",
+        BranchStatus::NoUpstream => "#eeeeee"
+    }
+}
+
+/// Writes the nodes and edges of `branches`, with `prefix` in front of
+/// every identifier so several repositories can share a graph.
+fn write_branches<W: Write>(out: &mut W, branches: &[&BranchLine], prefix: &str, indent: &str) -> io::Result<()> {
+    let branch_id = |name: &str| quote(&format!("{}branch:{}", prefix, name));
+    let upstream_id = |name: &str| quote(&format!("{}upstream:{}", prefix, name));
+
+    for b in branches {
+        write!(out, "{}{} [label={}, fillcolor=\"{}\"", indent, branch_id(&b.branch_name), quote(&b.branch_name), fill_colour(&b.status))?;
+        if b.is_current {
+            write!(out, ", penwidth=2")?
+        }
+        writeln!(out, "];")?
+    }
+
+    // Upstreams on each remote, in name order; a `.` remote is another
+    // local branch, which already has a node.
+    let mut remotes: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for upstream in branches.iter().filter_map(|b| b.upstream.as_ref()).filter(|u| u.remote != ".") {
+        let tracked = remotes.entry(&upstream.remote).or_default();
+        if !tracked.contains(&upstream.to_string()) {
+            tracked.push(upstream.to_string())
+        }
+    }
+    for (remote, tracked) in &remotes {
+        writeln!(out, "{}subgraph {} {{\n{}  label={};\n{}  node [shape=ellipse, style=filled, fillcolor=\"#ffffff\"];", indent, quote(&format!("cluster_{}remote:{}", prefix, remote)), indent, quote(remote), indent)?;
+        for name in tracked {
+            writeln!(out, "{}  {} [label={}];", indent, upstream_id(name), quote(name))?
+        }
+        writeln!(out, "{}}}", indent)?
+    }
+
+    for b in branches {
+        let upstream = match &b.upstream {
+            Some(upstream) => upstream,
+            None => continue
+        };
+        let target = if upstream.remote == "." { branch_id(&upstream.branch) } else { upstream_id(&upstream.to_string()) };
+        write!(out, "{}{} -> {}", indent, branch_id(&b.branch_name), target)?;
+        if b.status.is_gone() {
+            write!(out, " [style=dashed, color=\"#cc0000\"]")?
+        }
+        writeln!(out, ";")?
+    }
+    Ok(())
+}
+
+/// Writes the branches and their upstreams as one digraph.
+pub fn write_dot<W: Write>(out: &mut W, branches: &[&BranchLine]) -> io::Result<()> {
+    writeln!(out, "digraph branches {{\n  {}", GRAPH_ATTRIBUTES)?;
+    write_branches(out, branches, "", "  ")?;
+    writeln!(out, "}}")
+}
+
+/// Like [`write_dot`], with the branches of each repository in a cluster
+/// labelled with the repository.
+pub fn write_repos_dot<W: Write>(out: &mut W, repos: &[(&str, Vec<&BranchLine>)]) -> io::Result<()> {
+    writeln!(out, "digraph branches {{\n  {}", GRAPH_ATTRIBUTES)?;
+    for (repo, branches) in repos {
+        writeln!(out, "  subgraph {} {{\n    label={};", quote(&format!("cluster_repo:{}", repo)), quote(repo))?;
+        write_branches(out, branches, &format!("{}:", repo), "    ")?;
+        writeln!(out, "  }}")?
+    }
+    writeln!(out, "}}")
+}
+
+/// Writes remote branches as a cluster per remote, without edges as they
+/// have no local branches.
+pub fn write_remote_dot<W: Write>(out: &mut W, branches: &[&RemoteBranchLine]) -> io::Result<()> {
+    writeln!(out, "digraph branches {{\n  {}", GRAPH_ATTRIBUTES)?;
+    let mut remotes: BTreeMap<&str, Vec<&RemoteBranchLine>> = BTreeMap::new();
+    for r in branches {
+        remotes.entry(&r.remote).or_default().push(r)
+    }
+    for (remote, branches) in remotes {
+        writeln!(out, "  subgraph {} {{\n    label={};\n    node [shape=ellipse, style=filled, fillcolor=\"#ffffff\"];", quote(&format!("cluster_remote:{}", remote)), quote(remote))?;
+        for r in branches {
+            writeln!(out, "    {} [label={}];", quote(&format!("upstream:{}", r.full_name())), quote(&r.full_name()))?
+        }
+        writeln!(out, "  }}")?
+    }
+    writeln!(out, "}}")
+}
+
+
+/// 1. Branches coloured by status, the current one outlined
+/// 2. Upstreams clustered by remote, each only once
+/// 3. Gone upstreams dashed and red, local upstreams pointing at the branch
+#[test]
+fn dot_graph() {
+    let branches = crate::parse_branch_lines(
+        "* main 0000bbbb [origin/main] Blah\n  \
+           feature/x dddd3333 [origin/feature/x: gone] X\n  \
+           topic eeee4444 [main: ahead 1] Y\n  \
+           fork ffff5555 [upstream/main: behind 2] W\n  \
+           local aaaa1111 Z\n"
+    );
+    let mut out = Vec::new();
+    write_dot(&mut out, &branches.iter().collect::<Vec<_>>()).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), "\
+digraph branches {
+  rankdir=LR;
+  node [shape=box, style=\"rounded,filled\", fontname=\"Helvetica\"];
+  \"branch:main\" [label=\"main\", fillcolor=\"#d9ead3\", penwidth=2];
+  \"branch:feature/x\" [label=\"feature/x\", fillcolor=\"#f4cccc\"];
+  \"branch:topic\" [label=\"topic\", fillcolor=\"#fff2cc\"];
+  \"branch:fork\" [label=\"fork\", fillcolor=\"#fff2cc\"];
+  \"branch:local\" [label=\"local\", fillcolor=\"#eeeeee\"];
+  subgraph \"cluster_remote:origin\" {
+    label=\"origin\";
+    node [shape=ellipse, style=filled, fillcolor=\"#ffffff\"];
+    \"upstream:origin/main\" [label=\"origin/main\"];
+    \"upstream:origin/feature/x\" [label=\"origin/feature/x\"];
+  }
+  subgraph \"cluster_remote:upstream\" {
+    label=\"upstream\";
+    node [shape=ellipse, style=filled, fillcolor=\"#ffffff\"];
+    \"upstream:upstream/main\" [label=\"upstream/main\"];
+  }
+  \"branch:main\" -> \"upstream:origin/main\";
+  \"branch:feature/x\" -> \"upstream:origin/feature/x\" [style=dashed, color=\"#cc0000\"];
+  \"branch:topic\" -> \"branch:main\";
+  \"branch:fork\" -> \"upstream:upstream/main\";
+}
+");
+}
+
+/// 1. Quotes and backslashes are escaped
+/// 2. Slashes and unicode are kept as they are
+#[test]
+fn dot_quoting() {
+    assert_eq!(quote("say \"hi\""), "\"say \\\"hi\\\"\"");
+    assert_eq!(quote("a\\b"), "\"a\\\\b\"");
+    assert_eq!(quote("功能/登录"), "\"功能/登录\"");
+
+    let branches = crate::parse_branch_lines("  fix/\"quoted\" dddd3333 X\n");
+    let mut out = Vec::new();
+    write_dot(&mut out, &branches.iter().collect::<Vec<_>>()).unwrap();
+    assert!(String::from_utf8(out).unwrap().contains("  \"branch:fix/\\\"quoted\\\"\" [label=\"fix/\\\"quoted\\\"\", fillcolor=\"#eeeeee\"];\n"));
+}
+
+/// 1. Each repository is a cluster, with identifiers kept apart
+#[test]
+fn dot_repos() {
+    let api = crate::parse_branch_lines("  main 0000bbbb [origin/main] A\n");
+    let web = crate::parse_branch_lines("  main 1111cccc [origin/main: gone] B\n");
+    let repos = vec![("api", api.iter().collect()), ("web", web.iter().collect())];
+    let mut out = Vec::new();
+    write_repos_dot(&mut out, &repos).unwrap();
+    let dot = String::from_utf8(out).unwrap();
+    assert!(dot.contains("  subgraph \"cluster_repo:api\" {\n    label=\"api\";\n    \"api:branch:main\" [label=\"main\", fillcolor=\"#d9ead3\"];\n"));
+    assert!(dot.contains("    \"web:branch:main\" -> \"web:upstream:origin/main\" [style=dashed, color=\"#cc0000\"];\n"));
+}
@@ -0,0 +1,63 @@
+//! The error type shared by the library and the binary.
+
+use std::io;
+#[cfg(all(feature = "serde", feature = "system"))]
+use std::path::PathBuf;
+#[cfg(feature = "system")]
+use std::process::ExitStatus;
+#[cfg(feature = "system")]
+use std::time::Duration;
+
+use thiserror::Error;
+
+use crate::stream::ParseError;
+
+
+/// Anything that can go wrong while gathering, parsing or acting on branches.
+#[derive(Debug, Error)]
+pub enum BruneError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+
+    /// git ran but exited unsuccessfully.
+    #[cfg(feature = "system")]
+    #[error("git {args} failed: {stderr}")]
+    Git { args: String, stderr: String },
+
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+
+    #[cfg(all(feature = "serde", feature = "system"))]
+    #[error("invalid config {}: {source}", path.display())]
+    Config { path: PathBuf, source: toml::de::Error },
+
+    /// The command given to `--exec` failed.
+    #[cfg(feature = "system")]
+    #[error("{command} failed for {branch}: {status}")]
+    Hook { command: String, branch: String, status: ExitStatus },
+
+    /// The command given to `--input-command` failed.
+    #[cfg(feature = "system")]
+    #[error("{command} failed with {status}: {stderr}")]
+    InputCommand { command: String, status: ExitStatus, stderr: String },
+
+    /// The command given to `--input-command` ran for too long.
+    #[cfg(feature = "system")]
+    #[error("{command} was stopped after running for {timeout:?}")]
+    InputCommandTimeout { command: String, timeout: Duration },
+
+    #[error(transparent)]
+    Glob(#[from] globset::Error),
+
+    #[cfg(feature = "git2")]
+    #[error(transparent)]
+    Libgit2(#[from] git2::Error),
+
+    #[cfg(feature = "notify")]
+    #[error("couldn't send a notification: {0}")]
+    Notify(#[from] notify_rust::error::Error),
+
+    /// The arguments or settings can't be used together or as given.
+    #[error("{0}")]
+    Usage(String)
+}
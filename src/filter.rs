@@ -0,0 +1,207 @@
+//! Choosing which branches a command applies to.
+
+use std::time::Duration;
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use nom::character::complete::{digit1, one_of};
+use nom::combinator::{all_consuming, map_res};
+use nom::sequence::pair;
+use nom::IResult;
+use regex::Regex;
+
+use crate::BranchLine;
+
+
+/// Limits branches by name, age and status. An empty filter matches everything.
+///
+/// A name matches when it matches the regex, at least one include glob
+/// (if there are any) and none of the exclude globs. In globs `*` also
+/// matches `/`, as with `git branch --list`.
+#[derive(Debug, Clone, Default)]
+pub struct BranchFilter {
+    pattern: Option<Regex>,
+    include: Option<GlobSet>,
+    exclude: Option<GlobSet>,
+    /// Only branches with a last commit before this, in seconds since the epoch.
+    committed_before: Option<i64>,
+    gone_only: bool
+}
+
+fn take_duration(input: &str) -> IResult<&str, (u64, char)> {
+    all_consuming(pair(map_res(digit1, str::parse), one_of("hdwy")))(input)
+}
+
+/// Parses a duration like `12h`, `30d`, `6w` or `1y`. A year is 365 days.
+pub fn parse_duration(input: &str) -> Result<Duration, String> {
+    let (_, (count, unit)) = take_duration(input).map_err(|_| format!("invalid duration {:?}, expected a number followed by h, d, w or y", input))?;
+    let hours = match unit {
+        'h' => 1,
+        'd' => 24,
+        'w' => 24 * 7,
+        _ => 24 * 365
+    };
+    count.checked_mul(hours * 60 * 60).map(Duration::from_secs).ok_or_else(|| format!("duration {:?} is too long", input))
+}
+
+/// The globs as one set, or nothing when there are none.
+pub fn glob_set<S: AsRef<str>>(patterns: &[S]) -> Result<Option<GlobSet>, globset::Error> {
+    if patterns.is_empty() {
+        return Ok(None)
+    }
+
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(Glob::new(pattern.as_ref())?);
+    }
+    builder.build().map(Some)
+}
+
+impl BranchFilter {
+    pub fn new() -> Self {
+        BranchFilter::default()
+    }
+
+    /// Only match branch names the regex finds a match in.
+    pub fn with_regex(mut self, pattern: Regex) -> Self {
+        self.pattern = Some(pattern);
+        self
+    }
+
+    /// Only match branch names matching at least one of these globs.
+    pub fn with_include<S: AsRef<str>>(mut self, patterns: &[S]) -> Result<Self, globset::Error> {
+        self.include = glob_set(patterns)?;
+        Ok(self)
+    }
+
+    /// Never match branch names matching any of these globs.
+    pub fn with_exclude<S: AsRef<str>>(mut self, patterns: &[S]) -> Result<Self, globset::Error> {
+        self.exclude = glob_set(patterns)?;
+        Ok(self)
+    }
+
+    /// Only match branches whose last commit is more than `age` before `now`,
+    /// both in seconds since the epoch. Branches without a
+    /// [`last_commit_date`](BranchLine::last_commit_date) never match.
+    pub fn with_older_than(mut self, now: i64, age: Duration) -> Self {
+        self.committed_before = Some(now.saturating_sub(age.as_secs() as i64));
+        self
+    }
+
+    /// Only match branches whose upstream is gone.
+    pub fn with_gone_only(mut self) -> Self {
+        self.gone_only = true;
+        self
+    }
+
+    /// Whether the name is matched, ignoring the age and status limits.
+    pub fn matches(&self, name: &str) -> bool {
+        self.pattern.as_ref().is_none_or(|pattern| pattern.is_match(name)) &&
+            self.include.as_ref().is_none_or(|include| include.is_match(name)) &&
+            !self.exclude.as_ref().is_some_and(|exclude| exclude.is_match(name))
+    }
+
+    fn is_old_enough(&self, branch: &BranchLine) -> bool {
+        match self.committed_before {
+            Some(before) => branch.last_commit_date.is_some_and(|date| date < before),
+            None => true
+        }
+    }
+
+    /// Whether the branch is matched by name, age and status.
+    pub fn accepts(&self, branch: &BranchLine) -> bool {
+        self.matches(&branch.branch_name) && self.is_old_enough(branch) && (!self.gone_only || branch.status.is_gone())
+    }
+
+    /// The matching branches, in their original order.
+    pub fn apply<'a>(&self, branches: &'a [BranchLine]) -> Vec<&'a BranchLine> {
+        branches.iter().filter(|b| self.accepts(b)).collect()
+    }
+}
+
+
+#[test]
+fn filter_empty_matches_everything() {
+    let filter = BranchFilter::new();
+    assert!(filter.matches("feature/a"));
+    assert!(filter.matches(""));
+}
+
+/// 1. Anchored prefix
+/// 2. Ticket number anywhere in the name
+#[test]
+fn filter_regex() {
+    let branches = crate::parse_branch_lines("  feature/ABC-1-x dddd3333 X\n  fix/ABC-2 eeee3333 Y\n  my-feature/z ffff1111 Z\n");
+
+    let filter = BranchFilter::new().with_regex(Regex::new("^feature/").unwrap());
+    let names: Vec<&str> = filter.apply(&branches).iter().map(|b| b.branch_name.as_str()).collect();
+    assert_eq!(names, vec!["feature/ABC-1-x"]);
+
+    let filter = BranchFilter::new().with_regex(Regex::new(r"ABC-\d+").unwrap());
+    let names: Vec<&str> = filter.apply(&branches).iter().map(|b| b.branch_name.as_str()).collect();
+    assert_eq!(names, vec!["feature/ABC-1-x", "fix/ABC-2"]);
+}
+
+/// 1. Multiple includes
+/// 2. Exclude wins over include
+/// 3. Star matches across slashes
+#[test]
+fn filter_globs() {
+    let filter = BranchFilter::new().with_include(&["release/*", "hotfix-*"]).unwrap().with_exclude(&["release/old*"]).unwrap();
+    assert!(filter.matches("release/1.2"));
+    assert!(filter.matches("release/team/1.2"));
+    assert!(filter.matches("hotfix-3"));
+    assert!(!filter.matches("release/old-1.0"));
+    assert!(!filter.matches("feature/a"));
+}
+
+/// 1. Exclude only
+/// 2. Combined with a regex
+#[test]
+fn filter_exclude_with_regex() {
+    let filter = BranchFilter::new().with_regex(Regex::new("^PERSON1/").unwrap()).with_exclude(&["*/wip*"]).unwrap();
+    assert!(filter.matches("PERSON1/FeatureD"));
+    assert!(!filter.matches("PERSON1/wip-thing"));
+    assert!(!filter.matches("PERSON2/FeatureD"));
+}
+
+#[test]
+fn filter_invalid_glob() {
+    assert!(BranchFilter::new().with_include(&["release/["]).is_err());
+}
+
+/// 1. Each unit
+/// 2. Missing unit, unknown unit, trailing text and overflow
+#[test]
+fn duration_parsing() {
+    assert_eq!(parse_duration("12h"), Ok(Duration::from_secs(12 * 60 * 60)));
+    assert_eq!(parse_duration("30d"), Ok(Duration::from_secs(30 * 24 * 60 * 60)));
+    assert_eq!(parse_duration("6w"), Ok(Duration::from_secs(6 * 7 * 24 * 60 * 60)));
+    assert_eq!(parse_duration("1y"), Ok(Duration::from_secs(365 * 24 * 60 * 60)));
+
+    assert!(parse_duration("30").is_err());
+    assert!(parse_duration("30m").is_err());
+    assert!(parse_duration("30d ago").is_err());
+    assert!(parse_duration("99999999999999999y").is_err());
+}
+
+/// 1. Older than the limit
+/// 2. Newer than the limit
+/// 3. No date
+#[test]
+fn filter_older_than() {
+    let mut branches = crate::parse_branch_lines("  old dddd3333 X\n  new eeee3333 Y\n  unknown ffff1111 Z\n");
+    branches[0].last_commit_date = Some(1_000);
+    branches[1].last_commit_date = Some(9_000);
+
+    let filter = BranchFilter::new().with_older_than(10_000, Duration::from_secs(5_000));
+    let names: Vec<&str> = filter.apply(&branches).iter().map(|b| b.branch_name.as_str()).collect();
+    assert_eq!(names, vec!["old"]);
+}
+
+#[test]
+fn filter_gone_only() {
+    let branches = crate::parse_branch_lines("  feature/a dddd3333 [gone] X\n  feature/b eeee3333 Y\n  fix/c ffff1111 [origin/fix/c: gone] Z\n");
+    let filter = BranchFilter::new().with_include(&["feature/*"]).unwrap().with_gone_only();
+    let names: Vec<&str> = filter.apply(&branches).iter().map(|b| b.branch_name.as_str()).collect();
+    assert_eq!(names, ["feature/a"]);
+}
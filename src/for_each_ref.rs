@@ -0,0 +1,205 @@
+//! Branches listed by `git for-each-ref`, which is steadier than the human
+//! oriented `git branch -vv` output.
+
+use nom::{IResult, bytes::complete::{tag, take_till}, combinator::{all_consuming, rest, verify}, sequence::{terminated, tuple}};
+
+use crate::stream::ParseError;
+use crate::{is_valid_branch_name, numbered_lines, report_lines, take_annotation, take_comment, take_hex, BranchStatus, BranchLine, ParseMode, ParseReport, Upstream};
+
+
+/// The `--format` passed to `git for-each-ref`: tab separated `*` for the
+/// checked out branch, short name, upstream, upstream tracking status,
+/// short commit hash and subject.
+pub const FORMAT: &str = "%(HEAD)%09%(refname:short)%09%(upstream:short)%09%(upstream:track)%09%(objectname:short)%09%(subject)";
+
+/// The `--format` brune used before [`FORMAT`], without the HEAD and
+/// upstream fields. Listings captured with it still parse.
+pub const OLD_FORMAT: &str = "%(refname:short)%09%(upstream:track)%09%(objectname:short)%09%(subject)";
+
+
+fn take_field(input: &str) -> IResult<&str, &str> {
+    terminated(take_till(|c| c == '\t'), tag("\t"))(input)
+}
+
+/// Parses a single line of `git for-each-ref --format=FORMAT` output, or
+/// of [`OLD_FORMAT`] output when the first field isn't a HEAD marker.
+///
+/// Possible variations:
+/// " \tFeatureA\t\t\tdddeeee\tRandom weird comments"
+/// " \tFeatureD\torigin/FeatureD\t[ahead 1]\tffff1111\tRandom weird comments"
+/// " \tFeatureB\torigin/FeatureB\t[ahead 1, behind 3]\teeee3333\tRandom weird comments"
+/// " \tFeatureC\torigin/FeatureC\t[gone]\tdddd3333\tRandom weird comments"
+/// "*\tmaster\torigin/master\t\t0000bbbb\tRandom weird comments"
+/// "FeatureC\t[gone]\tdddd3333\tRandom weird comments"
+///
+pub fn parse_for_each_ref_line(input: &str) -> IResult<&str, BranchLine> {
+    match take_field(input) {
+        Ok((_, "*" | " " | "")) => parse_line(input),
+        _ => parse_old_line(input)
+    }
+}
+
+fn parse_line(input: &str) -> IResult<&str, BranchLine> {
+    let (_, (head, branch_name, upstream, track, commit, comment)) =
+        tuple((
+            verify(take_field, |head: &str| matches!(head, "*" | " " | "")),
+            verify(take_field, is_valid_branch_name),
+            take_field,
+            take_field,
+            terminated(take_hex, tag("\t")),
+            rest
+        ))(input)?;
+
+    // An empty track is in sync when there is an upstream.
+    let status =
+        match (track.is_empty(), upstream.is_empty()) {
+            (true, true) => BranchStatus::NoUpstream,
+            (true, false) => BranchStatus::UpToDate,
+            (false, _) => all_consuming(take_annotation)(track)?.1.status()
+        };
+
+    let branch_line = BranchLine {
+        branch_name: branch_name.to_string(),
+        is_current: head == "*",
+        checked_out_in_worktree: false,
+        status,
+        commit,
+        upstream: if upstream.is_empty() { None } else { Some(Upstream::parse(upstream)) },
+        comment: take_comment(comment).to_string(),
+        last_commit_date: None,
+        merged: false,
+        pr_state: None,
+        provider: None
+    };
+
+    Ok(("", branch_line))
+}
+
+/// A line of [`OLD_FORMAT`] output, which says nothing of the upstream
+/// or the checked out branch, so an empty track reads as no upstream.
+fn parse_old_line(input: &str) -> IResult<&str, BranchLine> {
+    let (_, (branch_name, track, commit, comment)) =
+        tuple((
+            verify(take_field, is_valid_branch_name),
+            take_field,
+            terminated(take_hex, tag("\t")),
+            rest
+        ))(input)?;
+
+    let status = if track.is_empty() { BranchStatus::NoUpstream } else { all_consuming(take_annotation)(track)?.1.status() };
+
+    let branch_line = BranchLine {
+        branch_name: branch_name.to_string(),
+        is_current: false,
+        checked_out_in_worktree: false,
+        status,
+        commit,
+        upstream: None,
+        comment: take_comment(comment).to_string(),
+        last_commit_date: None,
+        merged: false,
+        pr_state: None,
+        provider: None
+    };
+
+    Ok(("", branch_line))
+}
+
+/// Parses every non-blank line of `git for-each-ref --format=FORMAT` output.
+///
+/// Lines that don't parse are skipped.
+pub fn parse_for_each_ref_lines(input: &str) -> Vec<BranchLine> {
+    parse_for_each_ref_lines_with(input, ParseMode::Lenient).unwrap_or_default()
+}
+
+/// Parses every non-blank line of `git for-each-ref --format=FORMAT` output, treating lines
+/// that don't parse as `mode` says.
+pub fn parse_for_each_ref_lines_with(input: &str, mode: ParseMode) -> Result<Vec<BranchLine>, ParseError> {
+    parse_for_each_ref_lines_report(input).into_result(mode)
+}
+
+/// Parses every non-blank line of `git for-each-ref --format=FORMAT` output, reporting the
+/// branches along with the lines that were skipped.
+pub fn parse_for_each_ref_lines_report(input: &str) -> ParseReport<BranchLine> {
+    report_lines(numbered_lines(input).into_iter().map(|(n, line)| (n, line, parse_for_each_ref_line(line).map(|(_, parsed)| Some(parsed)))))
+}
+
+
+/// 1. Gone upstream
+#[test]
+fn parse_for_each_ref_gone() {
+    let (_, m) = parse_for_each_ref_line(" \tPERSON1/FeatureD\torigin/PERSON1/FeatureD\t[gone]\teeee4444\tRandom weird comments").unwrap();
+    assert_eq!(m.branch_name, "PERSON1/FeatureD");
+    assert_eq!(m.status, BranchStatus::Gone);
+    assert_eq!(m.commit, crate::HexValue("eeee4444".to_string()));
+    assert_eq!(m.comment, "Random weird comments");
+}
+
+/// 1. Diverged
+/// 2. Tabs in the subject are kept
+#[test]
+fn parse_for_each_ref_diverged() {
+    let (_, m) = parse_for_each_ref_line(" \tFeatureB\torigin/FeatureB\t[ahead 1, behind 3]\teeee3333\tRandom\tweird").unwrap();
+    assert_eq!(m.status, BranchStatus::Diverged { ahead: 1, behind: 3 });
+    assert_eq!(m.comment, "Random\tweird");
+}
+
+/// 1. No upstream
+/// 2. Empty subject
+#[test]
+fn parse_for_each_ref_no_tracking() {
+    let (_, m) = parse_for_each_ref_line(" \twt1\t\t\t8ef9921\t").unwrap();
+    assert_eq!(m.branch_name, "wt1");
+    assert_eq!(m.status, BranchStatus::NoUpstream);
+    assert_eq!(m.upstream, None);
+    assert!(!m.is_current);
+    assert_eq!(m.comment, "");
+}
+
+/// 1. The checked out branch, in sync with its upstream
+/// 2. A branch tracking a local branch
+#[test]
+fn parse_for_each_ref_current_upstream() {
+    let (_, m) = parse_for_each_ref_line("*\tmaster\torigin/master\t\t0000bbbb\tBlah").unwrap();
+    assert!(m.is_current);
+    assert_eq!(m.status, BranchStatus::UpToDate);
+    assert_eq!(m.upstream, Some(Upstream::new("origin", "master")));
+
+    let (_, m) = parse_for_each_ref_line(" \ttopic\tmaster\t[ahead 2]\t1111cccc\tBlah").unwrap();
+    assert_eq!(m.status, BranchStatus::Ahead(2));
+    assert_eq!(m.upstream, Some(Upstream::new(".", "master")));
+}
+
+/// 1. CRLF line endings
+/// 2. A single line parsed on its own
+#[test]
+fn parse_for_each_ref_crlf() {
+    let branches = parse_for_each_ref_lines(" \tFeatureC\torigin/FeatureC\t[gone]\tdddd3333\tGone\r\n \twt1\t\t\t8ef9921\t\r\n");
+    let comments: Vec<&str> = branches.iter().map(|b| b.comment.as_str()).collect();
+    assert_eq!(comments, vec!["Gone", ""]);
+    assert_eq!(parse_for_each_ref_line(" \twt1\t\t\t8ef9921\tinit\r").unwrap().1.comment, "init");
+}
+
+/// 1. Missing fields
+/// 2. Unknown tracking status
+#[test]
+fn parse_for_each_ref_invalid() {
+    assert!(parse_for_each_ref_line("FeatureA dddeeee Random weird comments").is_err());
+    assert!(parse_for_each_ref_line(" \tFeatureA\torigin/FeatureA\t[sideways 2]\tdddeeee\tRandom").is_err());
+    assert!(parse_for_each_ref_line("FeatureA\t[sideways 2]\tdddeeee\tRandom").is_err());
+}
+
+/// 1. The older layout without HEAD and upstream fields, tracked or not
+/// 2. Mixed with the current layout
+#[test]
+fn parse_for_each_ref_old_format() {
+    let (_, m) = parse_for_each_ref_line("FeatureC\t[gone]\tdddd3333\tRandom weird comments").unwrap();
+    assert_eq!((m.branch_name.as_str(), m.status, m.upstream, m.is_current), ("FeatureC", BranchStatus::Gone, None, false));
+    assert_eq!(m.comment, "Random weird comments");
+
+    let (_, m) = parse_for_each_ref_line("wt1\t\t8ef9921\tinit").unwrap();
+    assert_eq!(m.status, BranchStatus::NoUpstream);
+
+    let names: Vec<String> = parse_for_each_ref_lines("FeatureB\t[ahead 1, behind 3]\teeee3333\tX\n*\tmaster\torigin/master\t\t0000bbbb\tBlah\n").into_iter().map(|b| b.branch_name).collect();
+    assert_eq!(names, vec!["FeatureB", "master"]);
+}
@@ -0,0 +1,524 @@
+//! Running git to gather branch information.
+
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::process::Command;
+
+use crate::error::BruneError;
+use crate::host::CodeHost;
+use crate::{BranchLine, HexValue};
+
+
+/// Runs git with `args` inside `dir` and returns its stdout.
+///
+/// The C locale is forced so the output is stable enough to parse.
+pub fn run_git(dir: &Path, args: &[&str]) -> Result<String, BruneError> {
+    tracing::debug!("running git -C {} {}", dir.display(), args.join(" "));
+    let output =
+        Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(args)
+            .env("LC_ALL", "C")
+            .output()?;
+
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(BruneError::Git { args: args.join(" "), stderr: stderr.trim().to_string() })
+    }
+}
+
+/// Long options of `git branch` that change the listing into one the
+/// parser can't read, or have it do something other than list.
+const UNPARSEABLE_BRANCH_OPTIONS: &[&str] = &[
+    "--format", "--color", "--column", "--verbose", "--quiet", "--remotes", "--all", "--show-current",
+    "--delete", "--move", "--copy", "--force", "--track", "--no-track", "--set-upstream-to", "--unset-upstream",
+    "--edit-description", "--create-reflog", "--recurse-submodules"
+];
+
+/// The short forms of those, which can be bundled as in `-dv`.
+const UNPARSEABLE_BRANCH_FLAGS: &str = "vqradDmMcCfut";
+
+/// Options of `git branch` that take the next argument as their value,
+/// which may itself start with `-`, as in `--sort -committerdate`.
+const VALUE_BRANCH_OPTIONS: &[&str] = &["--sort", "--contains", "--no-contains", "--merged", "--no-merged", "--points-at"];
+
+/// The first of `args` that can't be passed on to `git branch -vv`, with
+/// the values of [`VALUE_BRANCH_OPTIONS`] let through as they are.
+fn unpassable_branch_arg<'a>(args: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let mut is_value = false;
+    for arg in args {
+        if is_value {
+            is_value = false
+        } else if !is_passable_branch_arg(arg) {
+            return Some(arg)
+        } else {
+            is_value = VALUE_BRANCH_OPTIONS.contains(&arg)
+        }
+    }
+    None
+}
+
+/// Whether `arg` can be passed on to `git branch -vv` without changing
+/// what it prints. git takes any unambiguous start of a long option for
+/// the option, so those are turned down too.
+fn is_passable_branch_arg(arg: &str) -> bool {
+    if arg == "--color=never" {
+        return true
+    }
+    match (arg.strip_prefix("--"), arg.strip_prefix('-')) {
+        (Some(""), _) => true,
+        (Some(_), _) => {
+            let name = arg.split('=').next().unwrap_or(arg);
+            !UNPARSEABLE_BRANCH_OPTIONS.iter().any(|option| option.starts_with(name))
+        },
+        (None, Some(flags)) => !flags.chars().any(|flag| UNPARSEABLE_BRANCH_FLAGS.contains(flag)),
+        (None, None) => true
+    }
+}
+
+/// Returns the `git branch -vv` listing of the repository at `dir`, with
+/// commits abbreviated to `abbrev` digits when given.
+pub fn branch_listing(dir: &Path, abbrev: Option<usize>) -> Result<String, BruneError> {
+    branch_listing_with(dir, abbrev, &[] as &[&str])
+}
+
+/// Like [`branch_listing`], with `extra` arguments such as `--sort` added
+/// to the git command. Arguments that would leave git printing something
+/// the parser can't read are turned down.
+pub fn branch_listing_with<S: AsRef<str>>(dir: &Path, abbrev: Option<usize>, extra: &[S]) -> Result<String, BruneError> {
+    if let Some(arg) = unpassable_branch_arg(extra.iter().map(AsRef::as_ref)) {
+        return Err(BruneError::Usage(format!("--git-args {} can't be passed to git branch -vv, as brune couldn't parse what it prints then", arg)))
+    }
+
+    // --list, so that anything else is a pattern to list rather than a
+    // branch to create.
+    let abbrev = abbrev.map(|len| format!("--abbrev={}", len));
+    let args: Vec<&str> =
+        ["branch", "--list", "-vv", "--no-color"]
+            .iter()
+            .copied()
+            .chain(abbrev.as_deref())
+            .chain(extra.iter().map(AsRef::as_ref))
+            .collect();
+    tracing::info!("listing branches with git {}", args.join(" "));
+    run_git(dir, &args)
+}
+
+/// Returns the local branches of the repository at `dir` in the
+/// [`for_each_ref::FORMAT`](crate::for_each_ref::FORMAT) layout.
+pub fn for_each_ref_listing(dir: &Path) -> Result<String, BruneError> {
+    let format = format!("--format={}", crate::for_each_ref::FORMAT);
+    run_git(dir, &["for-each-ref", &format, "refs/heads"])
+}
+
+/// Returns the `git branch -r -vv` listing of the repository at `dir`.
+pub fn remote_branch_listing(dir: &Path) -> Result<String, BruneError> {
+    run_git(dir, &["branch", "-r", "-vv", "--no-color"])
+}
+
+/// Asks `remote` itself for its branches, as `git ls-remote --heads` lists
+/// them, which needs the network and any credentials the remote wants.
+pub fn ls_remote_heads(dir: &Path, remote: &str) -> Result<String, BruneError> {
+    run_git(dir, &["ls-remote", "--heads", remote])
+}
+
+/// Returns the URL git fetches `remote` from.
+pub fn remote_url(dir: &Path, remote: &str) -> Result<String, BruneError> {
+    run_git(dir, &["remote", "get-url", remote]).map(|url| url.trim().to_string())
+}
+
+/// Returns the checked out branch, or `None` when HEAD is detached.
+pub fn current_branch(dir: &Path) -> Result<Option<String>, BruneError> {
+    let name = run_git(dir, &["branch", "--show-current"])?;
+    let name = name.trim();
+    Ok(if name.is_empty() { None } else { Some(name.to_string()) })
+}
+
+/// Returns the default branch of `remote`, such as `main`, going by the
+/// `refs/remotes/<remote>/HEAD` that `git clone` and `git remote set-head`
+/// leave, or `None` when there isn't one.
+pub fn default_branch(dir: &Path, remote: &str) -> Option<String> {
+    let head = run_git(dir, &["symbolic-ref", "--quiet", &format!("refs/remotes/{}/HEAD", remote)]).ok()?;
+    head.trim().strip_prefix(&format!("refs/remotes/{}/", remote)).map(str::to_string)
+}
+
+/// Whether the repository at `dir` has a local branch called `name`.
+pub fn has_branch(dir: &Path, name: &str) -> bool {
+    run_git(dir, &["rev-parse", "--verify", "--quiet", &format!("refs/heads/{}", name)]).is_ok()
+}
+
+/// Returns when `commit` was made, in seconds since the Unix epoch.
+pub fn commit_date(dir: &Path, commit: &HexValue) -> Result<i64, BruneError> {
+    let date = run_git(dir, &["log", "-1", "--format=%ct", commit.as_str()])?;
+    date.trim().parse().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("unexpected commit date {:?}: {}", date.trim(), e)).into())
+}
+
+/// Fills in [`BranchLine::last_commit_date`] for every branch that
+/// doesn't have one yet.
+pub fn add_commit_dates(dir: &Path, branches: &mut [BranchLine]) -> Result<(), BruneError> {
+    for b in branches.iter_mut().filter(|b| b.last_commit_date.is_none()) {
+        b.last_commit_date = Some(commit_date(dir, &b.commit)?)
+    }
+    Ok(())
+}
+
+/// Returns the local branches already merged into `base`.
+pub fn merged_branches(dir: &Path, base: &str) -> Result<Vec<String>, BruneError> {
+    let listing = run_git(dir, &["branch", "--format=%(refname:short)", "--merged", base])?;
+    Ok(listing.lines().map(str::to_string).collect())
+}
+
+/// Sets [`BranchLine::merged`] on every branch merged into `base`,
+/// except `base` itself (the current branch when `base` is `HEAD`).
+pub fn mark_merged(dir: &Path, base: &str, branches: &mut [BranchLine]) -> Result<(), BruneError> {
+    let merged = merged_branches(dir, base)?;
+    for b in branches.iter_mut() {
+        let is_base = if base == "HEAD" { b.is_current } else { b.branch_name == base };
+        b.merged = !is_base && merged.contains(&b.branch_name)
+    }
+    Ok(())
+}
+
+/// Returns the branches checked out in any worktree of the repository at
+/// `dir`, including its own.
+pub fn worktree_branches(dir: &Path) -> Result<Vec<String>, BruneError> {
+    Ok(parse_worktree_branches(&run_git(dir, &["worktree", "list", "--porcelain"])?))
+}
+
+/// The branches in a `git worktree list --porcelain` listing. Worktrees
+/// with a detached HEAD, and bare ones, have none.
+pub fn parse_worktree_branches(listing: &str) -> Vec<String> {
+    listing
+        .lines()
+        .filter_map(|line| line.strip_prefix("branch refs/heads/"))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Returns how many commits of `branch` aren't on any remote-tracking
+/// branch or other local branch, and so would only be kept by the backup
+/// once it is deleted.
+pub fn unpushed_commits(dir: &Path, branch: &str) -> Result<usize, BruneError> {
+    // --exclude patterns are relative to refs/heads for --branches, and
+    // branch names can't have glob characters in them.
+    let count = run_git(dir, &["rev-list", "--count", &format!("refs/heads/{}", branch), "--not", "--remotes", &format!("--exclude={}", branch), "--branches"])?;
+    count.trim().parse().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("unexpected commit count {:?}: {}", count.trim(), e)).into())
+}
+
+/// Returns the branch each stash was made on, newest stash first, with a
+/// branch repeated for every stash made on it.
+pub fn stash_branches(dir: &Path) -> Result<Vec<String>, BruneError> {
+    Ok(parse_stash_branches(&run_git(dir, &["stash", "list", "--format=%gs"])?))
+}
+
+/// The branches in the subjects of a `git stash list --format=%gs`
+/// listing, such as `WIP on main: 8ef9921 init` or `On main: message`.
+/// Stashes made with HEAD detached have none.
+pub fn parse_stash_branches(listing: &str) -> Vec<String> {
+    listing
+        .lines()
+        .filter_map(|line| line.strip_prefix("WIP on ").or_else(|| line.strip_prefix("On ")))
+        .filter_map(|rest| rest.split_once(": ").map(|(branch, _)| branch))
+        .filter(|branch| *branch != "(no branch)")
+        .map(str::to_string)
+        .collect()
+}
+
+/// Fills in [`BranchLine::provider`] from the URL of each upstream's
+/// remote. Remotes git doesn't know, e.g. when the listing came from
+/// another repository, are left without one.
+pub fn add_providers(dir: &Path, branches: &mut [BranchLine]) {
+    let mut providers = Providers::new(dir);
+    branches.iter_mut().for_each(|b| providers.add(b))
+}
+
+/// The code host of each remote, looked up once per remote, for filling
+/// in [`BranchLine::provider`] a branch at a time.
+#[derive(Debug)]
+pub struct Providers<'a> {
+    dir: &'a Path,
+    hosts: Vec<(String, Option<CodeHost>)>
+}
+
+impl<'a> Providers<'a> {
+    pub fn new(dir: &'a Path) -> Self {
+        Providers { dir, hosts: Vec::new() }
+    }
+
+    /// Sets the branch's provider, as [`add_providers`] does.
+    pub fn add(&mut self, b: &mut BranchLine) {
+        let remote = match &b.upstream {
+            Some(upstream) if upstream.remote != "." => &upstream.remote,
+            _ => return
+        };
+        b.provider = match self.hosts.iter().find(|(name, _)| name == remote) {
+            Some((_, host)) => *host,
+            None => {
+                let host = match remote_url(self.dir, remote) {
+                    Ok(url) => CodeHost::from_remote_url(&url),
+                    Err(e) => {
+                        tracing::debug!("no provider for {}: {}", remote, e);
+                        None
+                    }
+                };
+                self.hosts.push((remote.clone(), host));
+                host
+            }
+        }
+    }
+}
+
+/// A change to the repository.
+///
+/// Destructive operations are described as actions so they can be shown
+/// instead of performed during a dry run.
+#[derive(Debug, PartialEq)]
+pub enum Action {
+    DeleteBranch(String),
+    /// Creates the branch `name` at `commit`, failing if it exists.
+    CreateBranch { name: String, commit: String },
+    /// Points the ref `name` at `target`, creating it if need be.
+    UpdateRef { name: String, target: String },
+    DeleteRef(String),
+    /// Deletes `branch` from the `remote` with `git push --delete`.
+    DeleteRemoteBranch { remote: String, branch: String }
+}
+
+impl Action {
+    /// The arguments passed to git to perform this action.
+    pub fn args(&self) -> Vec<&str> {
+        match self {
+            Action::DeleteBranch(name) => vec!["branch", "-D", name],
+            Action::CreateBranch { name, commit } => vec!["branch", name, commit],
+            Action::UpdateRef { name, target } => vec!["update-ref", name, target],
+            Action::DeleteRef(name) => vec!["update-ref", "-d", name],
+            Action::DeleteRemoteBranch { remote, branch } => vec!["push", remote, "--delete", branch]
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "git {}", self.args().join(" "))
+    }
+}
+
+/// Performs `action` in the repository at `dir`.
+pub fn perform(dir: &Path, action: &Action) -> Result<(), BruneError> {
+    run_git(dir, &action.args()).map(|_| ())
+}
+
+
+#[cfg(test)]
+pub(crate) fn temp_repo(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("brune-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    for args in [
+        &["init", "-q", "-b", "main"][..],
+        &["-c", "user.name=brune", "-c", "user.email=brune@example.com", "commit", "-q", "--allow-empty", "-m", "Initial commit"][..],
+        &["branch", "feature/a"][..],
+    ] {
+        run_git(&dir, args).unwrap();
+    }
+    dir
+}
+
+#[test]
+fn git_branch_listing() {
+    let dir = temp_repo("listing");
+    let listing = branch_listing(&dir, None).unwrap();
+    let names: Vec<String> = crate::parse_branch_lines(&listing).into_iter().map(|b| b.branch_name).collect();
+    assert_eq!(names, vec!["feature/a", "main"]);
+}
+
+/// 1. A passed on --sort changes the order
+/// 2. The value of an option is passed on even when it starts with `-`
+/// 3. Other arguments are patterns to list
+/// 4. Options that change the listing are turned down, however they are spelled
+#[test]
+fn git_branch_listing_extra_args() {
+    let dir = temp_repo("extra-args");
+    let names = |extra: &[&str]| crate::parse_branch_lines(&branch_listing_with(&dir, None, extra).unwrap()).into_iter().map(|b| b.branch_name).collect::<Vec<_>>();
+    assert_eq!(names(&["--sort=-refname"]), vec!["main", "feature/a"]);
+    assert_eq!(names(&["--sort", "refname", "--color=never", "--", "feature/*"]), vec!["feature/a"]);
+    assert_eq!(names(&["--sort", "-refname"]), vec!["main", "feature/a"]);
+    assert_eq!(names(&["--sort", "-committerdate", "--sort", "refname"]), vec!["feature/a", "main"]);
+
+    let error = branch_listing_with(&dir, None, &["--sort=refname", "--format=%(refname)"]).unwrap_err();
+    assert_eq!(error.to_string(), "--git-args --format=%(refname) can't be passed to git branch -vv, as brune couldn't parse what it prints then");
+    for arg in ["--color=always", "--color", "--col", "--no-track", "-D", "-lr", "--all"] {
+        assert!(matches!(branch_listing_with(&dir, None, &[arg]), Err(BruneError::Usage(message)) if message.contains(arg)), "{}", arg)
+    }
+}
+
+#[test]
+fn git_branch_listing_full_hashes() {
+    let dir = temp_repo("abbrev");
+    let branches = crate::parse_branch_lines(&branch_listing(&dir, Some(crate::FULL_HASH_LEN)).unwrap());
+    assert!(branches.iter().all(|b| b.commit.is_full()));
+}
+
+#[test]
+fn git_for_each_ref_listing() {
+    let dir = temp_repo("for-each-ref");
+    run_git(&dir, &["branch", "-q", "--set-upstream-to", "main", "feature/a"]).unwrap();
+    let listing = for_each_ref_listing(&dir).unwrap();
+    let branches = crate::for_each_ref::parse_for_each_ref_lines(&listing);
+    let names: Vec<(&str, bool, Option<String>)> = branches.iter().map(|b| (b.branch_name.as_str(), b.is_current, b.upstream.as_ref().map(ToString::to_string))).collect();
+    assert_eq!(names, vec![("feature/a", false, Some("main".to_string())), ("main", true, None)]);
+}
+
+#[test]
+fn git_failure_reports_stderr() {
+    let dir = temp_repo("failure");
+    let error = run_git(&dir, &["branch", "--no-such-flag"]).unwrap_err();
+    assert!(error.to_string().contains("git branch --no-such-flag failed"));
+}
+
+#[test]
+fn git_delete_branch() {
+    let dir = temp_repo("delete");
+    perform(&dir, &Action::DeleteBranch("feature/a".to_string())).unwrap();
+    let listing = branch_listing(&dir, None).unwrap();
+    let names: Vec<String> = crate::parse_branch_lines(&listing).into_iter().map(|b| b.branch_name).collect();
+    assert_eq!(names, vec!["main"]);
+}
+
+#[test]
+fn git_current_branch() {
+    let dir = temp_repo("current");
+    assert_eq!(current_branch(&dir).unwrap(), Some("main".to_string()));
+}
+
+#[test]
+fn git_action_display() {
+    let action = Action::DeleteBranch("PERSON1/FeatureD".to_string());
+    assert_eq!(action.to_string(), "git branch -D PERSON1/FeatureD");
+    let action = Action::UpdateRef { name: "refs/brune/backup/1/x".to_string(), target: "refs/heads/x".to_string() };
+    assert_eq!(action.to_string(), "git update-ref refs/brune/backup/1/x refs/heads/x");
+    let action = Action::DeleteRemoteBranch { remote: "origin".to_string(), branch: "x".to_string() };
+    assert_eq!(action.to_string(), "git push origin --delete x");
+}
+
+#[test]
+fn git_add_commit_dates() {
+    let dir = temp_repo("dates");
+    let mut branches = crate::parse_branch_lines(&branch_listing(&dir, None).unwrap());
+    add_commit_dates(&dir, &mut branches).unwrap();
+
+    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs() as i64;
+    for b in &branches {
+        let date = b.last_commit_date.unwrap();
+        assert!((now - date).abs() < 60 * 60, "{} is not recent", date);
+    }
+}
+
+/// 1. A branch at the same commit as the base
+/// 2. A branch with commits of its own
+/// 3. The base itself
+#[test]
+fn git_mark_merged() {
+    let dir = temp_repo("merged");
+    run_git(&dir, &["checkout", "-q", "-b", "feature/b"]).unwrap();
+    run_git(&dir, &["-c", "user.name=brune", "-c", "user.email=brune@example.com", "commit", "-q", "--allow-empty", "-m", "Unmerged"]).unwrap();
+
+    let mut branches = crate::parse_branch_lines(&branch_listing(&dir, None).unwrap());
+    mark_merged(&dir, "main", &mut branches).unwrap();
+
+    let merged: Vec<(&str, bool)> = branches.iter().map(|b| (b.branch_name.as_str(), b.merged)).collect();
+    assert_eq!(merged, vec![("feature/a", true), ("feature/b", false), ("main", false)]);
+}
+
+/// 1. The main worktree and a linked one
+/// 2. Detached and bare worktrees have no branch
+#[test]
+fn git_worktree_listing() {
+    let listing = "worktree /src/app\nHEAD 8ef9921\nbranch refs/heads/main\n\n\
+                   worktree /src/app-wt\nHEAD 0000bbbb\nbranch refs/heads/feature/x\n\n\
+                   worktree /src/app-detached\nHEAD dddd3333\ndetached\n\n\
+                   worktree /src/app.git\nbare\n";
+    assert_eq!(parse_worktree_branches(listing), vec!["main", "feature/x"]);
+}
+
+#[test]
+fn git_worktree_branches() {
+    let dir = temp_repo("worktrees");
+    let linked = dir.with_file_name(format!("{}-linked", dir.file_name().unwrap().to_string_lossy()));
+    let _ = std::fs::remove_dir_all(&linked);
+    run_git(&dir, &["worktree", "add", "-q", linked.to_str().unwrap(), "feature/a"]).unwrap();
+
+    assert_eq!(worktree_branches(&dir).unwrap(), vec!["main", "feature/a"]);
+}
+
+/// 1. No remote HEAD
+/// 2. The branch a remote HEAD points to
+#[test]
+fn git_default_branch() {
+    let dir = temp_repo("default");
+    assert_eq!(default_branch(&dir, "origin"), None);
+
+    run_git(&dir, &["update-ref", "refs/remotes/origin/trunk", "main"]).unwrap();
+    run_git(&dir, &["symbolic-ref", "refs/remotes/origin/HEAD", "refs/remotes/origin/trunk"]).unwrap();
+    assert_eq!(default_branch(&dir, "origin"), Some("trunk".to_string()));
+    assert!(has_branch(&dir, "main"));
+    assert!(!has_branch(&dir, "trunk"));
+}
+
+/// 1. A branch at a commit a remote has
+/// 2. A branch with a commit of its own
+#[test]
+fn git_unpushed_commits() {
+    let dir = temp_repo("unpushed");
+    run_git(&dir, &["update-ref", "refs/remotes/origin/main", "main"]).unwrap();
+    run_git(&dir, &["checkout", "-q", "feature/a"]).unwrap();
+    run_git(&dir, &["-c", "user.name=brune", "-c", "user.email=brune@example.com", "commit", "-q", "--allow-empty", "-m", "Unpushed"]).unwrap();
+
+    assert_eq!(unpushed_commits(&dir, "main").unwrap(), 0);
+    assert_eq!(unpushed_commits(&dir, "feature/a").unwrap(), 1);
+
+    // Still reachable from another local branch.
+    run_git(&dir, &["branch", "feature/b"]).unwrap();
+    assert_eq!(unpushed_commits(&dir, "feature/a").unwrap(), 0);
+}
+
+/// 1. Stashes with and without a message
+/// 2. Stashes made with HEAD detached
+#[test]
+fn git_stash_listing() {
+    let listing = "WIP on feature/x: 8ef9921 init\nOn main: half done: really\nWIP on (no branch): dddd333 detached\nWIP on feature/x: 8ef9921 init\n";
+    assert_eq!(parse_stash_branches(listing), vec!["feature/x", "main", "feature/x"]);
+}
+
+#[test]
+fn git_stash_branches() {
+    let dir = temp_repo("stashes");
+    std::fs::write(dir.join("wip.txt"), "wip").unwrap();
+    run_git(&dir, &["add", "wip.txt"]).unwrap();
+    run_git(&dir, &["-c", "user.name=brune", "-c", "user.email=brune@example.com", "stash", "push", "-q", "-m", "wip"]).unwrap();
+
+    assert_eq!(stash_branches(&dir).unwrap(), vec!["main"]);
+}
+
+/// 1. Tracking a GitHub remote
+/// 2. No upstream
+#[test]
+fn git_add_providers() {
+    let dir = temp_repo("providers");
+    for args in [
+        &["remote", "add", "origin", "git@github.com:ssanj/brune.git"][..],
+        &["config", "branch.feature/a.remote", "origin"][..],
+        &["config", "branch.feature/a.merge", "refs/heads/feature/a"][..],
+    ] {
+        run_git(&dir, args).unwrap();
+    }
+
+    let mut branches = crate::parse_branch_lines(&branch_listing(&dir, None).unwrap());
+    add_providers(&dir, &mut branches);
+    let providers: Vec<Option<CodeHost>> = branches.iter().map(|b| b.provider).collect();
+    assert_eq!(providers, vec![Some(CodeHost::GitHub), None]);
+}
@@ -0,0 +1,277 @@
+//! A reminder in a git hook, such as pre-push, that says when there are too
+//! many gone branches. It goes into its own section of the hook, between
+//! markers, so a hook that is already there keeps doing what it did and
+//! uninstalling takes out only what brune put in.
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::BruneError;
+use crate::git;
+
+
+/// The line starting brune's section of a hook.
+pub const BEGIN_MARKER: &str = "# >>> brune >>>";
+
+/// The line ending it.
+pub const END_MARKER: &str = "# <<< brune <<<";
+
+/// The interpreter of a hook brune writes from scratch.
+const SHEBANG: &str = "#!/bin/sh";
+
+/// The shells brune's section can be run by, as the last part of a hook's
+/// interpreter.
+const SHELLS: [&str; 5] = ["sh", "bash", "dash", "ksh", "zsh"];
+
+
+/// The hooks the reminder can go into.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Hook {
+    /// Run after switching branches, where the reminder can't stop anything.
+    PostCheckout,
+    /// Run before pushing, which a blocking reminder stops.
+    PrePush
+}
+
+impl fmt::Display for Hook {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Hook::PostCheckout => write!(f, "post-checkout"),
+            Hook::PrePush => write!(f, "pre-push")
+        }
+    }
+}
+
+/// What the reminder does.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Reminder {
+    /// How many gone branches there can be before the reminder speaks up.
+    pub max_gone: usize,
+    /// Whether the hook fails as well, when there are more.
+    pub blocking: bool
+}
+
+impl Reminder {
+    /// brune's section of a hook, markers and all. Git gives some hooks
+    /// input of their own, so brune is told to ask git for the branches
+    /// rather than read it, and anything going wrong with brune, or brune
+    /// not being installed, passes quietly.
+    pub fn section(&self) -> String {
+        let fail = if self.blocking { "        exit 1\n" } else { "" };
+        format!(
+            "{begin}\n\
+             # Added by brune install-hook, and taken out by brune uninstall-hook.\n\
+             if command -v brune >/dev/null 2>&1; then\n\
+             \x20   brune_gone=$(brune gone --count --max-gone {max} --input-command 'git branch -vv --no-color' 2>/dev/null)\n\
+             \x20   if [ $? -eq 1 ]; then\n\
+             \x20       echo \"brune: $brune_gone branches have a gone upstream, more than {max}. brune prune deletes them.\" >&2\n\
+             {fail}\
+             \x20   fi\n\
+             fi\n\
+             {end}\n",
+            begin = BEGIN_MARKER, end = END_MARKER, max = self.max_gone, fail = fail
+        )
+    }
+}
+
+
+/// The section's place in `hook`, as the byte range from its begin marker
+/// to the end of its end marker's line.
+fn find_section(hook: &str) -> Option<(usize, usize)> {
+    let start = hook.find(&format!("{}\n", BEGIN_MARKER)).filter(|start| *start == 0 || hook[..*start].ends_with('\n'))?;
+    let end = hook[start..].find(&format!("{}\n", END_MARKER))? + start + END_MARKER.len() + 1;
+    Some((start, end))
+}
+
+/// `existing`, the hook as it is, if there is one, with `section` in it:
+/// in place of brune's section if it has one, otherwise right after its
+/// interpreter line so an `exit` later on doesn't skip it. A new hook is a
+/// shell script of the section alone. Hooks run by something other than a
+/// shell are turned down, as the section would be a syntax error in them.
+pub fn with_section(existing: Option<&str>, section: &str) -> Result<String, String> {
+    let existing = match existing {
+        Some(hook) if !hook.trim().is_empty() => hook,
+        _ => return Ok(format!("{}\n{}", SHEBANG, section))
+    };
+    if let Some((start, end)) = find_section(existing) {
+        return Ok(format!("{}{}{}", &existing[..start], section, &existing[end..]))
+    }
+
+    let (first, rest) = existing.split_once('\n').unwrap_or((existing, ""));
+    match first.strip_prefix("#!") {
+        Some(interpreter) => {
+            let mut words = interpreter.split_whitespace();
+            let program = match words.next() {
+                Some(env) if env.ends_with("/env") => words.find(|w| !w.starts_with('-')),
+                program => program
+            };
+            let shell = program.and_then(|p| p.rsplit('/').next()).filter(|name| SHELLS.contains(name));
+            match shell {
+                Some(_) => Ok(format!("{}\n{}{}", first, section, rest)),
+                None => Err(format!("it is run by {}, not a shell brune can add to", interpreter.trim()))
+            }
+        },
+        // Git runs a hook without an interpreter line with sh.
+        None => Ok(format!("{}\n{}{}", SHEBANG, section, existing))
+    }
+}
+
+/// `hook` without brune's section, or None when it hasn't one. What is left
+/// is None as well when it was only the interpreter line brune started it
+/// with, so the hook can go.
+pub fn without_section(hook: &str) -> Option<Option<String>> {
+    let (start, end) = find_section(hook)?;
+    let rest = format!("{}{}", &hook[..start], &hook[end..]);
+    Some(if rest.trim() == SHEBANG || rest.trim().is_empty() { None } else { Some(rest) })
+}
+
+
+/// Where the repository at `dir` keeps `hook`, following core.hooksPath
+/// and worktrees as git does.
+pub fn hook_path(dir: &Path, hook: Hook) -> Result<PathBuf, BruneError> {
+    let hooks = git::run_git(dir, &["rev-parse", "--git-path", "hooks"])?;
+    Ok(dir.join(hooks.trim()).join(hook.to_string()))
+}
+
+/// Puts the reminder into `hook` of the repository at `dir`, making the
+/// hook if there isn't one, and returns where it is.
+pub fn install(dir: &Path, hook: Hook, reminder: &Reminder) -> Result<PathBuf, BruneError> {
+    let path = hook_path(dir, hook)?;
+    let existing = match fs::read_to_string(&path) {
+        Ok(existing) => Some(existing),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e.into())
+    };
+    let installed = with_section(existing.as_deref(), &reminder.section())
+        .map_err(|reason| BruneError::Usage(format!("can't add to {}: {}", path.display(), reason)))?;
+
+    if let Some(hooks) = path.parent() {
+        fs::create_dir_all(hooks)?
+    }
+    fs::write(&path, installed)?;
+    make_executable(&path)?;
+    Ok(path)
+}
+
+/// Takes the reminder out of `hook` of the repository at `dir`, removing
+/// the hook when nothing else is left in it. Returns where the hook is, or
+/// None when the reminder wasn't in it.
+pub fn uninstall(dir: &Path, hook: Hook) -> Result<Option<PathBuf>, BruneError> {
+    let path = hook_path(dir, hook)?;
+    let existing = match fs::read_to_string(&path) {
+        Ok(existing) => existing,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into())
+    };
+    match without_section(&existing) {
+        None => return Ok(None),
+        Some(Some(rest)) => fs::write(&path, rest)?,
+        Some(None) => fs::remove_file(&path)?
+    }
+    Ok(Some(path))
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let mut permissions = fs::metadata(path)?.permissions();
+    permissions.set_mode(permissions.mode() | 0o111);
+    fs::set_permissions(path, permissions)
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> std::io::Result<()> {
+    Ok(())
+}
+
+
+#[cfg(test)]
+const REMINDER: Reminder = Reminder { max_gone: 3, blocking: false };
+
+/// 1. A new hook is the section alone
+/// 2. The section goes after the interpreter, before an exit
+/// 3. Installing again replaces the section
+/// 4. A hook with no interpreter line is run by sh
+#[test]
+fn git_hook_with_section() {
+    let section = REMINDER.section();
+    assert_eq!(with_section(None, &section).unwrap(), format!("#!/bin/sh\n{}", section));
+
+    let existing = "#!/usr/bin/env bash\nmake lint\nexit 0\n";
+    let installed = with_section(Some(existing), &section).unwrap();
+    assert_eq!(installed, format!("#!/usr/bin/env bash\n{}make lint\nexit 0\n", section));
+
+    let blocking = Reminder { blocking: true, ..REMINDER }.section();
+    assert_eq!(with_section(Some(&installed), &blocking).unwrap(), format!("#!/usr/bin/env bash\n{}make lint\nexit 0\n", blocking));
+
+    assert_eq!(with_section(Some("make lint\n"), &section).unwrap(), format!("#!/bin/sh\n{}make lint\n", section));
+}
+
+/// 1. Hooks run by something other than a shell are turned down
+#[test]
+fn git_hook_other_interpreters() {
+    let section = REMINDER.section();
+    assert_eq!(with_section(Some("#!/usr/bin/env python3\nprint()\n"), &section), Err("it is run by /usr/bin/env python3, not a shell brune can add to".to_string()));
+    assert!(with_section(Some("#!/usr/bin/env -S bash -e\ntrue\n"), &section).is_ok());
+    assert!(with_section(Some("#!/bin/zsh\ntrue\n"), &section).is_ok());
+}
+
+/// 1. Only brune's section is taken out
+/// 2. A hook that was only brune's goes
+/// 3. A hook without the section is left alone
+#[test]
+fn git_hook_without_section() {
+    let section = REMINDER.section();
+    let existing = "#!/bin/bash\nmake lint\n";
+    assert_eq!(without_section(&with_section(Some(existing), &section).unwrap()), Some(Some(existing.to_string())));
+    assert_eq!(without_section(&with_section(None, &section).unwrap()), Some(None));
+    assert_eq!(without_section(existing), None);
+}
+
+/// 1. The reminder runs along with the hook that was there, without
+///    failing it
+/// 2. A blocking one fails it
+/// 3. Uninstalling leaves the hook as it was
+#[cfg(unix)]
+#[test]
+fn git_hook_install_and_run() {
+    let dir = git::temp_repo("hook");
+    let bin = dir.join("bin");
+    fs::create_dir_all(&bin).unwrap();
+    // Stands in for brune, saying there are 7 gone branches.
+    fs::write(bin.join("brune"), "#!/bin/sh\necho \"$@\" > \"$(dirname \"$0\")/args\"\necho 7\nexit 1\n").unwrap();
+    make_executable(&bin.join("brune")).unwrap();
+
+    let path = hook_path(&dir, Hook::PrePush).unwrap();
+    let existing = "#!/bin/sh\necho existing hook ran\n";
+    fs::write(&path, existing).unwrap();
+    make_executable(&path).unwrap();
+
+    let run = || {
+        let search = format!("{}:{}", bin.display(), std::env::var("PATH").unwrap_or_default());
+        std::process::Command::new(&path).current_dir(&dir).env("PATH", search).output().unwrap()
+    };
+
+    assert_eq!(install(&dir, Hook::PrePush, &REMINDER).unwrap(), path);
+    let ran = run();
+    assert!(ran.status.success());
+    assert_eq!(String::from_utf8_lossy(&ran.stdout), "existing hook ran\n");
+    assert_eq!(String::from_utf8_lossy(&ran.stderr), "brune: 7 branches have a gone upstream, more than 3. brune prune deletes them.\n");
+    assert_eq!(fs::read_to_string(bin.join("args")).unwrap(), "gone --count --max-gone 3 --input-command git branch -vv --no-color\n");
+
+    install(&dir, Hook::PrePush, &Reminder { blocking: true, ..REMINDER }).unwrap();
+    let ran = run();
+    assert_eq!(ran.status.code(), Some(1));
+    assert!(ran.stdout.is_empty());
+
+    assert_eq!(uninstall(&dir, Hook::PrePush).unwrap(), Some(path.clone()));
+    assert_eq!(fs::read_to_string(&path).unwrap(), existing);
+    assert_eq!(uninstall(&dir, Hook::PrePush).unwrap(), None);
+
+    install(&dir, Hook::PostCheckout, &REMINDER).unwrap();
+    let post_checkout = hook_path(&dir, Hook::PostCheckout).unwrap();
+    assert!(post_checkout.exists());
+    uninstall(&dir, Hook::PostCheckout).unwrap();
+    assert!(!post_checkout.exists());
+}
@@ -0,0 +1,100 @@
+//! Looking up pull requests on GitHub.
+//!
+//! Requests go to the public API, or `GITHUB_API_URL` when it is set. A
+//! `GITHUB_TOKEN` is sent when present, which raises the rate limit and
+//! allows private repositories.
+
+use std::env;
+use std::io;
+
+use serde::Deserialize;
+
+use crate::provider::{self, Provider};
+use crate::PrState;
+
+
+/// A repository on GitHub.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GitHubRepo {
+    pub owner: String,
+    pub name: String
+}
+
+/// Finds the GitHub repository a remote URL points at, for both
+/// `git@github.com:owner/repo.git` and `https://github.com/owner/repo`.
+pub fn parse_remote_url(url: &str) -> Option<GitHubRepo> {
+    let (host, path) = provider::split_remote_url(url)?;
+    if host != "github.com" {
+        return None
+    }
+
+    match path.split_once('/') {
+        Some((owner, name)) if !owner.is_empty() && !name.is_empty() && !name.contains('/') =>
+            Some(GitHubRepo { owner: owner.to_string(), name: name.to_string() }),
+        _ => None
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Pull {
+    state: String,
+    merged_at: Option<String>
+}
+
+/// The state of the first (most recent) pull request in a `/pulls` response.
+fn parse_pulls(body: &str) -> io::Result<Option<PrState>> {
+    let pulls: Vec<Pull> = serde_json::from_str(body).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    Ok(pulls.first().map(|pull| match (pull.state.as_str(), &pull.merged_at) {
+        (_, Some(_)) => PrState::Merged,
+        ("open", None) => PrState::Open,
+        _ => PrState::Closed
+    }))
+}
+
+impl Provider for GitHubRepo {
+    fn pr_state(&self, branch: &str) -> io::Result<Option<PrState>> {
+        let api = env::var("GITHUB_API_URL").unwrap_or_else(|_| "https://api.github.com".to_string());
+        let url = format!("{}/repos/{}/{}/pulls", api.trim_end_matches('/'), self.owner, self.name);
+        let head = format!("{}:{}", self.owner, branch);
+
+        let mut request =
+            ureq::get(&url)
+                .query("head", &head)
+                .query("state", "all")
+                .set("Accept", "application/vnd.github+json");
+
+        if let Ok(token) = env::var("GITHUB_TOKEN") {
+            request = request.set("Authorization", &format!("Bearer {}", token))
+        }
+
+        parse_pulls(&provider::fetch(request, branch)?)
+    }
+}
+
+
+/// 1. SSH
+/// 2. HTTPS with and without .git
+/// 3. Another host
+/// 4. Not a repository path
+#[test]
+fn github_remote_urls() {
+    let repo = Some(GitHubRepo { owner: "ssanj".to_string(), name: "brune".to_string() });
+    assert_eq!(parse_remote_url("git@github.com:ssanj/brune.git"), repo);
+    assert_eq!(parse_remote_url("https://github.com/ssanj/brune.git"), repo);
+    assert_eq!(parse_remote_url("https://github.com/ssanj/brune/"), repo);
+    assert_eq!(parse_remote_url("git@gitlab.com:ssanj/brune.git"), None);
+    assert_eq!(parse_remote_url("https://github.com/ssanj"), None);
+}
+
+/// 1. Merged, including squash merges which GitHub reports as closed
+/// 2. Open
+/// 3. Closed without merging
+/// 4. No pull requests
+#[test]
+fn github_pull_states() {
+    assert_eq!(parse_pulls(r#"[{"state": "closed", "merged_at": "2024-01-01T00:00:00Z"}]"#).unwrap(), Some(PrState::Merged));
+    assert_eq!(parse_pulls(r#"[{"state": "open", "merged_at": null}, {"state": "closed", "merged_at": "2024-01-01T00:00:00Z"}]"#).unwrap(), Some(PrState::Open));
+    assert_eq!(parse_pulls(r#"[{"state": "closed", "merged_at": null}]"#).unwrap(), Some(PrState::Closed));
+    assert_eq!(parse_pulls("[]").unwrap(), None);
+    assert!(parse_pulls("{}").is_err());
+}
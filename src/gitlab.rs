@@ -0,0 +1,89 @@
+//! Looking up merge requests on GitLab.
+//!
+//! Remotes on `gitlab.com`, or any host with `gitlab` in its name, are
+//! treated as GitLab. Requests go to `https://<host>/api/v4`, or
+//! `GITLAB_API_URL` when it is set. A `GITLAB_TOKEN` is sent when present.
+
+use std::env;
+use std::io;
+
+use serde::Deserialize;
+
+use crate::provider::{self, Provider};
+use crate::PrState;
+
+
+/// A project on a GitLab instance.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GitLabProject {
+    pub host: String,
+    /// The full path including any groups, e.g. `group/sub/project`.
+    pub path: String
+}
+
+/// Finds the GitLab project a remote URL points at.
+pub fn parse_remote_url(url: &str) -> Option<GitLabProject> {
+    let (host, path) = provider::split_remote_url(url)?;
+    if host.contains("gitlab") && path.contains('/') {
+        Some(GitLabProject { host: host.to_string(), path: path.to_string() })
+    } else {
+        None
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct MergeRequest {
+    state: String
+}
+
+/// The state of the first (most recent) merge request in a response.
+fn parse_merge_requests(body: &str) -> io::Result<Option<PrState>> {
+    let requests: Vec<MergeRequest> = serde_json::from_str(body).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    Ok(requests.first().map(|request| match request.state.as_str() {
+        "merged" => PrState::Merged,
+        "opened" => PrState::Open,
+        _ => PrState::Closed
+    }))
+}
+
+impl Provider for GitLabProject {
+    fn pr_state(&self, branch: &str) -> io::Result<Option<PrState>> {
+        let api = env::var("GITLAB_API_URL").unwrap_or_else(|_| format!("https://{}/api/v4", self.host));
+        let url = format!("{}/projects/{}/merge_requests", api.trim_end_matches('/'), self.path.replace('/', "%2F"));
+
+        let mut request =
+            ureq::get(&url)
+                .query("source_branch", branch)
+                .query("state", "all");
+
+        if let Ok(token) = env::var("GITLAB_TOKEN") {
+            request = request.set("PRIVATE-TOKEN", &token)
+        }
+
+        parse_merge_requests(&provider::fetch(request, branch)?)
+    }
+}
+
+
+/// 1. gitlab.com
+/// 2. Self-hosted with nested groups
+/// 3. Another host
+#[test]
+fn gitlab_remote_urls() {
+    assert_eq!(parse_remote_url("git@gitlab.com:ssanj/brune.git"), Some(GitLabProject { host: "gitlab.com".to_string(), path: "ssanj/brune".to_string() }));
+    assert_eq!(parse_remote_url("https://gitlab.example.com/team/tools/brune"), Some(GitLabProject { host: "gitlab.example.com".to_string(), path: "team/tools/brune".to_string() }));
+    assert_eq!(parse_remote_url("git@github.com:ssanj/brune.git"), None);
+}
+
+/// 1. Merged
+/// 2. Opened
+/// 3. Closed and locked
+/// 4. No merge requests
+#[test]
+fn gitlab_merge_request_states() {
+    assert_eq!(parse_merge_requests(r#"[{"state": "merged"}]"#).unwrap(), Some(PrState::Merged));
+    assert_eq!(parse_merge_requests(r#"[{"state": "opened"}, {"state": "merged"}]"#).unwrap(), Some(PrState::Open));
+    assert_eq!(parse_merge_requests(r#"[{"state": "closed"}]"#).unwrap(), Some(PrState::Closed));
+    assert_eq!(parse_merge_requests(r#"[{"state": "locked"}]"#).unwrap(), Some(PrState::Closed));
+    assert_eq!(parse_merge_requests("[]").unwrap(), None);
+}
@@ -0,0 +1,62 @@
+//! Running a command of the user's for each branch, so they can archive,
+//! notify or do whatever else brune has no built in action for.
+
+use std::process::Command;
+
+use crate::error::BruneError;
+use crate::BranchLine;
+
+
+/// The shell command `shell_command`, with the branch described to it by
+/// environment variables:
+///
+/// * `BRUNE_BRANCH`, the branch name
+/// * `BRUNE_SHA`, its commit as listed
+/// * `BRUNE_STATUS`, its tracking status as [`name`](crate::BranchStatus::name) gives it, e.g. `gone`
+/// * `BRUNE_UPSTREAM`, its upstream, or empty when it has none
+pub fn command(shell_command: &str, b: &BranchLine) -> Command {
+    #[cfg(windows)]
+    let mut command = { let mut c = Command::new("cmd"); c.arg("/C"); c };
+    #[cfg(not(windows))]
+    let mut command = { let mut c = Command::new("sh"); c.arg("-c"); c };
+
+    command
+        .arg(shell_command)
+        .env("BRUNE_BRANCH", &b.branch_name)
+        .env("BRUNE_SHA", b.commit.as_str())
+        .env("BRUNE_STATUS", b.status.name())
+        .env("BRUNE_UPSTREAM", b.upstream.as_ref().map_or(String::new(), |u| u.to_string()));
+    command
+}
+
+/// Runs `shell_command` for each branch in turn, stopping at the first
+/// that fails.
+pub fn run_for_each(shell_command: &str, branches: &[&BranchLine]) -> Result<(), BruneError> {
+    for b in branches {
+        tracing::info!("running {} for {}", shell_command, b.branch_name);
+        let status = command(shell_command, b).status()?;
+        if !status.success() {
+            return Err(BruneError::Hook { command: shell_command.to_string(), branch: b.branch_name.clone(), status })
+        }
+    }
+    Ok(())
+}
+
+
+#[cfg(unix)]
+#[test]
+fn hook_environment() {
+    let branches = crate::parse_branch_lines("  FeatureC dddd3333 [origin/FeatureC: gone] X\n  local eeee4444 Y\n");
+    let run = |b| String::from_utf8(command("echo \"$BRUNE_BRANCH $BRUNE_SHA $BRUNE_STATUS $BRUNE_UPSTREAM\"", b).output().unwrap().stdout).unwrap();
+    assert_eq!(run(&branches[0]), "FeatureC dddd3333 gone origin/FeatureC\n");
+    assert_eq!(run(&branches[1]), "local eeee4444 no_upstream \n");
+}
+
+#[cfg(unix)]
+#[test]
+fn hook_failure_stops() {
+    let branches = crate::parse_branch_lines("  FeatureC dddd3333 [gone] X\n");
+    let branches: Vec<&BranchLine> = branches.iter().collect();
+    assert!(run_for_each("true", &branches).is_ok());
+    assert!(matches!(run_for_each("exit 3", &branches), Err(BruneError::Hook { branch, .. }) if branch == "FeatureC"));
+}
@@ -0,0 +1,66 @@
+//! Recognising the code host a remote lives on from its URL.
+
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+
+/// A code host brune knows about.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum CodeHost {
+    GitHub,
+    GitLab,
+    Bitbucket
+}
+
+impl CodeHost {
+    /// The host of a remote URL such as `https://github.com/ssanj/brune.git`
+    /// or `git@gitlab.example.com:group/project.git`, going by its name so
+    /// self-hosted instances are recognised too.
+    pub fn from_remote_url(url: &str) -> Option<CodeHost> {
+        let host = host_name(url).to_ascii_lowercase();
+        if host.contains("github") {
+            Some(CodeHost::GitHub)
+        } else if host.contains("gitlab") {
+            Some(CodeHost::GitLab)
+        } else if host.contains("bitbucket") {
+            Some(CodeHost::Bitbucket)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for CodeHost {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CodeHost::GitHub => write!(f, "github"),
+            CodeHost::GitLab => write!(f, "gitlab"),
+            CodeHost::Bitbucket => write!(f, "bitbucket")
+        }
+    }
+}
+
+/// The host part of a URL or scp-like `user@host:path` address.
+fn host_name(url: &str) -> &str {
+    let address = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let address = address.split_once('@').map_or(address, |(_, rest)| rest);
+    address.split([':', '/']).next().unwrap_or_default()
+}
+
+
+/// 1. HTTPS
+/// 2. scp-like SSH
+/// 3. SSH URL with a port on a self-hosted instance
+/// 4. Unknown hosts and local paths
+#[test]
+fn code_host_from_remote_url() {
+    assert_eq!(CodeHost::from_remote_url("https://github.com/ssanj/brune.git"), Some(CodeHost::GitHub));
+    assert_eq!(CodeHost::from_remote_url("git@gitlab.com:group/project.git"), Some(CodeHost::GitLab));
+    assert_eq!(CodeHost::from_remote_url("ssh://git@bitbucket.example.com:7999/proj/repo.git"), Some(CodeHost::Bitbucket));
+    assert_eq!(CodeHost::from_remote_url("https://git.example.com/repo.git"), None);
+    assert_eq!(CodeHost::from_remote_url("/srv/git/repo.git"), None);
+}
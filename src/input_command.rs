@@ -0,0 +1,131 @@
+//! Running a command of the user's for the branch listing, such as one
+//! that fetches it from a build machine over ssh.
+
+use std::fmt;
+use std::io::{self, Read};
+use std::process::{Command, Stdio};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::error::BruneError;
+
+
+/// How long the command may run when `--input-command-timeout` isn't given.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How often the command is checked on while it runs.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// A command that prints a branch listing.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputCommand {
+    /// A command line for the shell.
+    Shell(String),
+    /// A program and its arguments, run as they are without a shell.
+    Argv(Vec<String>)
+}
+
+impl InputCommand {
+    fn command(&self) -> Command {
+        match self {
+            InputCommand::Shell(line) => {
+                #[cfg(windows)]
+                let mut command = { let mut c = Command::new("cmd"); c.arg("/C"); c };
+                #[cfg(not(windows))]
+                let mut command = { let mut c = Command::new("sh"); c.arg("-c"); c };
+                command.arg(line);
+                command
+            },
+            InputCommand::Argv(argv) => {
+                let mut command = Command::new(argv.first().map_or("", String::as_str));
+                command.args(argv.iter().skip(1));
+                command
+            }
+        }
+    }
+
+    /// Runs the command and returns its stdout, or its stderr in the error
+    /// when it fails. It is killed when it runs for longer than `timeout`.
+    pub fn run(&self, timeout: Duration) -> Result<String, BruneError> {
+        tracing::debug!("running {}", self);
+        let mut child = self.command().stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+
+        let stdout = read_all(child.stdout.take());
+        let stderr = read_all(child.stderr.take());
+
+        let deadline = Instant::now() + timeout;
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status
+            }
+            if Instant::now() >= deadline {
+                // The readers are left behind, as anything the command
+                // started may still hold its pipes open.
+                child.kill()?;
+                child.wait()?;
+                return Err(BruneError::InputCommandTimeout { command: self.to_string(), timeout })
+            }
+            thread::sleep(POLL_INTERVAL)
+        };
+
+        let stdout = joined(stdout)?;
+        let stderr = joined(stderr)?;
+        if status.success() {
+            Ok(String::from_utf8_lossy(&stdout).into_owned())
+        } else {
+            Err(BruneError::InputCommand { command: self.to_string(), status, stderr: String::from_utf8_lossy(&stderr).trim().to_string() })
+        }
+    }
+}
+
+/// Reads `pipe` to the end on a thread of its own, so a command with a lot
+/// to say doesn't stall on a full pipe while it is waited for.
+fn read_all<R: Read + Send + 'static>(pipe: Option<R>) -> JoinHandle<io::Result<Vec<u8>>> {
+    thread::spawn(move || {
+        let mut bytes = Vec::new();
+        pipe.map_or(Ok(0), |mut pipe| pipe.read_to_end(&mut bytes)).map(|_| bytes)
+    })
+}
+
+fn joined(reader: JoinHandle<io::Result<Vec<u8>>>) -> io::Result<Vec<u8>> {
+    reader.join().unwrap_or_else(|_| Err(io::Error::other("couldn't read the command's output")))
+}
+
+impl fmt::Display for InputCommand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InputCommand::Shell(line) => write!(f, "{}", line),
+            InputCommand::Argv(argv) => write!(f, "{}", argv.join(" "))
+        }
+    }
+}
+
+
+/// 1. Through the shell and as argv, both parsed as a listing
+#[cfg(unix)]
+#[test]
+fn input_command_output() {
+    let listing = InputCommand::Shell("printf '* main 0000bbbb [origin/main] Blah\\n  FeatureC dddd3333 [gone] X\\n'".to_string());
+    let names: Vec<String> = crate::parse_branch_lines(&listing.run(DEFAULT_TIMEOUT).unwrap()).into_iter().map(|b| b.branch_name).collect();
+    assert_eq!(names, vec!["main", "FeatureC"]);
+
+    let argv = InputCommand::Argv(vec!["sh".to_string(), "-c".to_string(), "printf '  local eeee4444 Y\\n'".to_string()]);
+    assert_eq!(argv.run(DEFAULT_TIMEOUT).unwrap(), "  local eeee4444 Y\n");
+}
+
+/// 1. A failing command reports its stderr
+/// 2. A slow one is stopped at the timeout
+/// 3. A missing program can't be run
+#[cfg(unix)]
+#[test]
+fn input_command_failure() {
+    let failing = InputCommand::Shell("printf 'no such host\\n' >&2; exit 3".to_string());
+    assert!(matches!(failing.run(DEFAULT_TIMEOUT), Err(BruneError::InputCommand { stderr, status, .. }) if stderr == "no such host" && status.code() == Some(3)));
+
+    let slow = InputCommand::Argv(vec!["sleep".to_string(), "5".to_string()]);
+    let start = Instant::now();
+    assert!(matches!(slow.run(Duration::from_millis(100)), Err(BruneError::InputCommandTimeout { .. })));
+    assert!(start.elapsed() < Duration::from_secs(5));
+
+    assert!(matches!(InputCommand::Argv(vec!["brune-no-such-program".to_string()]).run(DEFAULT_TIMEOUT), Err(BruneError::Io(_))));
+}
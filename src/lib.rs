@@ -0,0 +1,1322 @@
+//! Parsers for the output of `git branch -vv`.
+//!
+//! ```
+//! use brune::{parse_branch_line, BranchStatus};
+//!
+//! let (_, line) = parse_branch_line("  feature/x abc1234 [gone] Fix things").unwrap();
+//! assert_eq!(line.branch_name, "feature/x");
+//! assert_eq!(line.status, BranchStatus::Gone);
+//! ```
+//!
+//! Without default features only the parsers and what works on parsed
+//! branches are built, with nothing that runs git or reads files, so the
+//! crate builds for `wasm32-unknown-unknown`. The `serde` feature adds
+//! serialization and the output formats, and `system` running git.
+
+#[cfg(feature = "system")]
+pub mod backup;
+#[cfg(feature = "system")]
+pub mod complete;
+#[cfg(feature = "bitbucket")]
+pub mod bitbucket;
+#[cfg(all(feature = "serde", feature = "system"))]
+pub mod config;
+pub mod dates;
+pub mod error;
+pub mod filter;
+pub mod for_each_ref;
+#[cfg(feature = "system")]
+pub mod discover;
+pub mod dot;
+#[cfg(feature = "system")]
+pub mod git;
+#[cfg(feature = "system")]
+pub mod git_hook;
+#[cfg(feature = "github")]
+pub mod github;
+#[cfg(feature = "gitlab")]
+pub mod gitlab;
+#[cfg(feature = "system")]
+pub mod hook;
+pub mod host;
+#[cfg(feature = "system")]
+pub mod input_command;
+#[cfg(feature = "git2")]
+pub mod libgit2;
+pub mod lint;
+pub mod messages;
+#[cfg(feature = "notify")]
+pub mod notify;
+#[cfg(feature = "serde")]
+pub mod output;
+#[cfg(feature = "system")]
+pub mod pager;
+pub mod policy;
+pub mod preprocess;
+pub mod prompt;
+pub mod protect;
+#[cfg(feature = "provider")]
+pub mod provider;
+pub mod reconcile;
+pub mod remote;
+#[cfg(feature = "serde")]
+pub mod report;
+pub mod scan;
+#[cfg(feature = "serde")]
+pub mod shield;
+#[cfg(feature = "system")]
+pub mod show;
+pub mod sort;
+pub mod stale;
+pub mod stream;
+pub mod summary;
+#[cfg(feature = "serde")]
+pub mod template;
+pub mod trace;
+#[cfg(feature = "tui")]
+pub mod tui;
+pub mod watch;
+
+use std::fmt;
+use std::str::FromStr;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize};
+#[cfg(feature = "serde")]
+use serde::ser::{SerializeStruct, Serializer};
+
+use crate::host::CodeHost;
+use crate::stream::ParseError;
+
+use nom::{IResult, branch::alt, bytes::complete::{tag, tag_no_case, take_until, take_while, take_while1, take_while_m_n}, character::complete::{digit1, one_of, satisfy, space1}, combinator::{map, map_opt, map_res, not, opt, verify}, multi::separated_list1, sequence::{delimited, preceded, separated_pair, terminated, tuple}};
+
+
+/// The length of an unabbreviated commit hash, as shown with `--abbrev=40`.
+pub const FULL_HASH_LEN: usize = 40;
+
+/// The fewest digits git abbreviates a commit hash to.
+pub const MIN_HASH_LEN: usize = 4;
+
+/// An abbreviated or full commit hash.
+#[derive(Debug,Clone,PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct HexValue(pub String);
+
+impl HexValue {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The number of hex digits.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Whether this is an unabbreviated hash.
+    pub fn is_full(&self) -> bool {
+        self.len() == FULL_HASH_LEN
+    }
+
+    /// The hash shortened to `len` digits, or `None` when it is already
+    /// shorter than that and so can't be normalised to `len`.
+    pub fn abbreviated(&self, len: usize) -> Option<HexValue> {
+        self.0.get(..len).map(|digits| HexValue(digits.to_string()))
+    }
+}
+
+impl fmt::Display for HexValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// How a branch stands against its upstream.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum BranchStatus {
+    /// Tracking an upstream it matches.
+    UpToDate,
+    Ahead(u32),
+    Behind(u32),
+    Diverged { ahead: u32, behind: u32 },
+    /// The upstream no longer exists.
+    Gone,
+    /// Not tracking anything.
+    NoUpstream
+}
+
+impl BranchStatus {
+    /// The status of a branch with an upstream.
+    pub fn tracking(gone: bool, ahead_behind: AheadBehind) -> BranchStatus {
+        match (gone, ahead_behind.ahead, ahead_behind.behind) {
+            (true, _, _) => BranchStatus::Gone,
+            (false, 0, 0) => BranchStatus::UpToDate,
+            (false, ahead, 0) => BranchStatus::Ahead(ahead),
+            (false, 0, behind) => BranchStatus::Behind(behind),
+            (false, ahead, behind) => BranchStatus::Diverged { ahead, behind }
+        }
+    }
+
+    pub fn is_gone(&self) -> bool {
+        *self == BranchStatus::Gone
+    }
+
+    /// The commits ahead and behind, zero unless ahead, behind or diverged.
+    pub fn ahead_behind(&self) -> AheadBehind {
+        match *self {
+            BranchStatus::Ahead(ahead) => AheadBehind { ahead, behind: 0 },
+            BranchStatus::Behind(behind) => AheadBehind { ahead: 0, behind },
+            BranchStatus::Diverged { ahead, behind } => AheadBehind { ahead, behind },
+            BranchStatus::UpToDate | BranchStatus::Gone | BranchStatus::NoUpstream => AheadBehind::default()
+        }
+    }
+
+    /// `gone` or `active`, the words the porcelain and tabular formats have
+    /// always used.
+    pub fn kind(&self) -> &'static str {
+        if self.is_gone() { "gone" } else { "active" }
+    }
+
+    /// The variant as a word, e.g. `up_to_date` or `diverged`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            BranchStatus::UpToDate => "up_to_date",
+            BranchStatus::Ahead(_) => "ahead",
+            BranchStatus::Behind(_) => "behind",
+            BranchStatus::Diverged { .. } => "diverged",
+            BranchStatus::Gone => "gone",
+            BranchStatus::NoUpstream => "no_upstream"
+        }
+    }
+
+    /// The status [`name`](BranchStatus::name) gives `name`, with the
+    /// counts it carries taken from `ahead_behind`.
+    pub fn from_name(name: &str, ahead_behind: AheadBehind) -> Option<BranchStatus> {
+        let AheadBehind { ahead, behind } = ahead_behind;
+        match name {
+            "up_to_date" => Some(BranchStatus::UpToDate),
+            "ahead" => Some(BranchStatus::Ahead(ahead)),
+            "behind" => Some(BranchStatus::Behind(behind)),
+            "diverged" => Some(BranchStatus::Diverged { ahead, behind }),
+            "gone" => Some(BranchStatus::Gone),
+            "no_upstream" => Some(BranchStatus::NoUpstream),
+            _ => None
+        }
+    }
+}
+
+/// Serialized as `type`, `status`, `ahead` and `behind` fields, which are
+/// flattened into the line they belong to.
+#[cfg(feature = "serde")]
+impl Serialize for BranchStatus {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let AheadBehind { ahead, behind } = self.ahead_behind();
+        let mut fields = serializer.serialize_struct("BranchStatus", 4)?;
+        fields.serialize_field("type", self.kind())?;
+        fields.serialize_field("status", self.name())?;
+        fields.serialize_field("ahead", &ahead)?;
+        fields.serialize_field("behind", &behind)?;
+        fields.end()
+    }
+}
+
+/// Read back from the `status`, `ahead` and `behind` fields. `type` follows
+/// from `status`, so it is ignored.
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for BranchStatus {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct Fields {
+            status: String,
+            #[serde(default)]
+            ahead: u32,
+            #[serde(default)]
+            behind: u32
+        }
+
+        const NAMES: &[&str] = &["up_to_date", "ahead", "behind", "diverged", "gone", "no_upstream"];
+        let Fields { status, ahead, behind } = Fields::deserialize(deserializer)?;
+        BranchStatus::from_name(&status, AheadBehind { ahead, behind })
+            .ok_or_else(|| serde::de::Error::unknown_variant(&status, NAMES))
+    }
+}
+
+
+/// How many commits a branch is ahead of and behind its upstream.
+#[derive(Debug,Default,Clone,Copy,PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AheadBehind {
+    pub ahead: u32,
+    pub behind: u32
+}
+
+impl AheadBehind {
+    pub fn is_in_sync(&self) -> bool {
+        self.ahead == 0 && self.behind == 0
+    }
+}
+
+impl fmt::Display for AheadBehind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.ahead, self.behind) {
+            (0, 0) => Ok(()),
+            (ahead, 0) => write!(f, "ahead {}", ahead),
+            (0, behind) => write!(f, "behind {}", behind),
+            (ahead, behind) => write!(f, "ahead {}, behind {}", ahead, behind)
+        }
+    }
+}
+
+
+/// The branch a local branch tracks, e.g. `feature` on `origin`.
+#[derive(Debug,Clone,PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Upstream {
+    /// The remote, or `.` for a branch tracking another local branch.
+    pub remote: String,
+    pub branch: String
+}
+
+impl Upstream {
+    pub fn new<S: Into<String>, T: Into<String>>(remote: S, branch: T) -> Upstream {
+        Upstream { remote: remote.into(), branch: branch.into() }
+    }
+
+    /// Splits a short upstream ref like `origin/feature/x` at its first `/`.
+    /// Without one, the upstream is a local branch.
+    pub fn parse(short_ref: &str) -> Upstream {
+        match short_ref.split_once('/') {
+            Some((remote, branch)) => Upstream::new(remote, branch),
+            None => Upstream::new(".", short_ref)
+        }
+    }
+}
+
+impl fmt::Display for Upstream {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.remote == "." {
+            f.write_str(&self.branch)
+        } else {
+            write!(f, "{}/{}", self.remote, self.branch)
+        }
+    }
+}
+
+
+/// A single parsed line of `git branch -vv` output.
+#[derive(Debug,PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BranchLine {
+    #[cfg_attr(feature = "serde", serde(rename = "name"))]
+    pub branch_name: String,
+    /// Whether this is the checked out branch, marked with `*`.
+    #[cfg_attr(feature = "serde", serde(rename = "current"))]
+    pub is_current: bool,
+    /// Whether the branch is checked out in another worktree, marked with `+`.
+    #[cfg_attr(feature = "serde", serde(rename = "worktree"))]
+    pub checked_out_in_worktree: bool,
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub status: BranchStatus,
+    /// The commit at the head of the branch.
+    pub commit: HexValue,
+    /// The upstream, when the listing includes it.
+    pub upstream: Option<Upstream>,
+    pub comment: String,
+    /// When the head commit was made, in seconds since the Unix epoch. The
+    /// listing doesn't include this, see [`git::add_commit_dates`].
+    /// Serialized as a number, or as [`dates::serializing_as`] says.
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::dates::serialize_date"))]
+    pub last_commit_date: Option<i64>,
+    /// Whether the branch is already merged into the base branch, see
+    /// [`git::mark_merged`].
+    pub merged: bool,
+    /// The state of the branch's pull request, when it has been looked up.
+    pub pr_state: Option<PrState>,
+    /// Where the upstream's remote is hosted, see [`git::add_providers`].
+    pub provider: Option<CodeHost>
+}
+
+/// The old name of [`BranchLine`], from before it applied to any host.
+#[deprecated(note = "renamed to BranchLine")]
+pub type GitHubBranchLine = BranchLine;
+
+/// The old name of [`BranchLineRef`].
+#[deprecated(note = "renamed to BranchLineRef")]
+pub type GitHubBranchLineRef<'a> = BranchLineRef<'a>;
+
+/// The old name of [`BranchStatus`], which replaced its `Active` and
+/// `Deleted` variants with richer ones.
+#[deprecated(note = "replaced by BranchStatus")]
+pub type GitHubBranchType = BranchStatus;
+
+
+/// A [`BranchLine`] borrowing its text from the parsed input, for
+/// parsing lots of lines without allocating for each one.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct BranchLineRef<'a> {
+    pub branch_name: &'a str,
+    pub is_current: bool,
+    pub checked_out_in_worktree: bool,
+    pub status: BranchStatus,
+    pub commit: &'a str,
+    pub upstream: Option<&'a str>,
+    pub comment: &'a str
+}
+
+impl BranchLineRef<'_> {
+    /// Copies the borrowed text into an owned [`BranchLine`].
+    pub fn to_owned(&self) -> BranchLine {
+        BranchLine {
+            branch_name: self.branch_name.to_string(),
+            is_current: self.is_current,
+            checked_out_in_worktree: self.checked_out_in_worktree,
+            status: self.status,
+            commit: HexValue(self.commit.to_string()),
+            upstream: self.upstream.map(Upstream::parse),
+            comment: self.comment.to_string(),
+            last_commit_date: None,
+            merged: false,
+            pr_state: None,
+            provider: None
+        }
+    }
+}
+
+
+/// The state of the most recent pull request for a branch.
+#[derive(Debug,Clone,Copy,PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum PrState {
+    Open,
+    /// Closed without being merged.
+    Closed,
+    Merged
+}
+
+impl fmt::Display for PrState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PrState::Open => write!(f, "open"),
+            PrState::Closed => write!(f, "closed"),
+            PrState::Merged => write!(f, "merged")
+        }
+    }
+}
+
+
+/// The line git shows when HEAD doesn't point at a branch.
+#[derive(Debug,PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DetachedHead {
+    /// What git says about HEAD, e.g. `HEAD detached at abc1234`.
+    pub description: String,
+    pub commit: HexValue,
+    pub comment: String
+}
+
+/// Any line of `git branch -vv` output.
+#[derive(Debug,PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ListingLine {
+    Branch(BranchLine),
+    DetachedHead(DetachedHead)
+}
+
+
+fn is_alphabetic(c: char) -> bool {
+    c.is_alphabetic()
+}
+
+fn is_whitespace(c: char) -> bool {
+    c.is_whitespace()
+}
+
+fn is_hex_digit(c: char) -> bool {
+  c.is_ascii_hexdigit()
+}
+
+/// Characters `git check-ref-format` allows in a ref name.
+///
+/// Git only rules out ASCII spaces and control characters, so any other
+/// Unicode character, including non-ASCII whitespace, is part of the name.
+fn is_ref_name_char(c: char) -> bool {
+    !c.is_ascii_whitespace() && !c.is_ascii_control() && !"~^:?*[\\".contains(c)
+}
+
+/// Checks `name` against the rules of `git check-ref-format --branch`,
+/// besides the characters it may contain.
+pub fn is_valid_branch_name(name: &str) -> bool {
+    !name.is_empty() &&
+        name != "@" &&
+        !name.starts_with('-') &&
+        !name.starts_with('/') &&
+        !name.ends_with('/') &&
+        !name.ends_with('.') &&
+        !name.contains("..") &&
+        !name.contains("@{") &&
+        !name.contains("//") &&
+        name.chars().all(is_ref_name_char) &&
+        name.split('/').all(|component| !component.starts_with('.') && !component.ends_with(".lock"))
+}
+
+
+/// Consumes the literal `prefix` from the start of `input`.
+pub fn take_tag<'a>(prefix: &'a str, input: &'a str) -> IResult<&'a str, &'a str> {
+    tag(prefix)(input)
+}
+
+
+/// Consumes a run of whitespace, including the `\r` of a CRLF line ending.
+fn take_whitespace(input: &str) -> IResult<&str, &str> {
+    take_while(is_whitespace)(input)
+}
+
+/// The rest of a line, as the comment ending it, without the `\r` of a
+/// CRLF line ending.
+pub(crate) fn take_comment(input: &str) -> &str {
+    input.trim_end_matches('\r')
+}
+
+
+/// Consumes a run of alphabetic characters.
+pub fn take_alphabetic(input: &str) -> IResult<&str, &str> {
+    take_while(is_alphabetic)(input)
+}
+
+
+fn take_branch_name(input: &str) -> IResult<&str, &str> {
+    verify(take_while1(is_ref_name_char), is_valid_branch_name)(input)
+}
+
+/// The parts of a tracking annotation such as `[origin/feature: ahead 1, behind 2]`.
+#[derive(Debug,Default,PartialEq)]
+struct Annotation<'a> {
+    upstream: Option<&'a str>,
+    gone: bool,
+    ahead_behind: AheadBehind
+}
+
+impl Annotation<'_> {
+    fn status(&self) -> BranchStatus {
+        BranchStatus::tracking(self.gone, self.ahead_behind)
+    }
+}
+
+#[derive(Debug,PartialEq)]
+enum TrackingStatus {
+    Gone,
+    Ahead(u32),
+    Behind(u32)
+}
+
+fn take_count(input: &str) -> IResult<&str, u32> {
+    map_res(digit1, str::parse)(input)
+}
+
+fn take_tracking_status(input: &str) -> IResult<&str, TrackingStatus> {
+    alt((
+        map(tag_no_case("gone"), |_| TrackingStatus::Gone),
+        map(preceded(tag_no_case("ahead"), preceded(space1, take_count)), TrackingStatus::Ahead),
+        map(preceded(tag_no_case("behind"), preceded(space1, take_count)), TrackingStatus::Behind)
+    ))(input)
+}
+
+fn take_tracking_statuses(input: &str) -> IResult<&str, Annotation<'_>> {
+    map(separated_list1(tag(", "), take_tracking_status), |statuses| {
+        statuses.into_iter().fold(Annotation::default(), |mut annotation, status| {
+            match status {
+                TrackingStatus::Gone => annotation.gone = true,
+                TrackingStatus::Ahead(ahead) => annotation.ahead_behind.ahead = ahead,
+                TrackingStatus::Behind(behind) => annotation.ahead_behind.behind = behind
+            }
+            annotation
+        })
+    })(input)
+}
+
+/// Takes the upstream ref of an annotation. `]` is legal in a ref name but
+/// would swallow the end of `[origin/feature]`, so it isn't accepted here.
+fn take_upstream(input: &str) -> IResult<&str, &str> {
+    take_while1(|c| is_ref_name_char(c) && c != ']')(input)
+}
+
+/// Whether an upstream has the `remote/branch` shape of a remote-tracking
+/// ref.
+fn is_remote_upstream(upstream: &str) -> bool {
+    upstream.split_once('/').is_some_and(|(remote, branch)| !remote.is_empty() && !branch.is_empty())
+}
+
+/// Possible variations:
+/// "[origin/feature: ahead 1, behind 2]"
+/// "[origin/feature: gone]"
+/// "[origin/feature]"
+/// "[behind 3]"
+/// "[ahead 2, behind 5]"
+/// "[gone]"
+///
+/// A bare `[ref]` is only an upstream when it looks like `remote/branch`,
+/// so a subject such as `[WIP] fix` is left as the comment.
+fn take_annotation(input: &str) -> IResult<&str, Annotation<'_>> {
+    preceded(
+        tag("["),
+        alt((
+            map(
+                terminated(separated_pair(take_upstream, tag(": "), take_tracking_statuses), tag("]")),
+                |(upstream, annotation)| Annotation { upstream: Some(upstream), ..annotation }
+            ),
+            terminated(take_tracking_statuses, tag("]")),
+            map(terminated(verify(take_upstream, is_remote_upstream), tag("]")), |upstream| Annotation { upstream: Some(upstream), ..Annotation::default() })
+        ))
+    )(input)
+}
+
+/// Consumes the leading whitespace and the marker in front of the branch name:
+/// `*` for the checked out branch and `+` for a branch checked out in another worktree.
+fn take_marker(input: &str) -> IResult<&str, Option<char>> {
+    map(
+        tuple((take_whitespace, opt(one_of("*+")), take_whitespace)),
+        |(_, marker, _)| marker
+    )(input)
+}
+
+/// Consumes the `(/path/to/worktree)` git shows for branches checked out in other worktrees.
+fn take_worktree_path(input: &str) -> IResult<&str, &str> {
+    delimited(tag("("), take_until(")"), tag(")"))(input)
+}
+
+/// Between [`MIN_HASH_LEN`] and [`FULL_HASH_LEN`] hex digits, not followed by anything
+/// else that could be part of the same word. A missing hash or a token like
+/// `abc123xyz` is an error rather than an empty or truncated [`HexValue`].
+fn take_hex_str(input: &str) -> IResult<&str, &str> {
+    terminated(take_while_m_n(MIN_HASH_LEN, FULL_HASH_LEN, is_hex_digit), not(satisfy(char::is_alphanumeric)))(input)
+}
+
+fn take_hex(input: &str) -> IResult<&str, HexValue> {
+    map(take_hex_str, |hv: &str| HexValue(hv.to_string()))(input)
+}
+
+/// Parses a single branch line of `git branch -vv` output.
+///
+/// The remaining input is the comment of the branch. Detached HEAD lines
+/// are rejected; use [`parse_line`] to accept them too.
+pub fn parse_branch_line(input: &str) -> IResult<&str, BranchLine> {
+    map_opt(parse_line, |line| match line {
+        ListingLine::Branch(branch_line) => Some(branch_line),
+        ListingLine::DetachedHead(_) => None
+    })(input)
+}
+
+/// Like [`parse_branch_line`], but borrows from `input` instead of allocating.
+pub fn parse_branch_line_ref(input: &str) -> IResult<&str, BranchLineRef<'_>> {
+    preceded(not(detached_head_parser), git_line_ref_parser)(input)
+}
+
+impl FromStr for BranchLine {
+    type Err = ParseError;
+
+    /// Parses a whole branch line, without the nom details of
+    /// [`parse_branch_line`].
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        parse_branch_line(line)
+            .map(|(_, branch_line)| branch_line)
+            .map_err(|e| ParseError::invalid_line(1, line, e))
+    }
+}
+
+/// Parses any line of `git branch -vv` output.
+pub fn parse_line(input: &str) -> IResult<&str, ListingLine> {
+    alt((
+        map(detached_head_parser, ListingLine::DetachedHead),
+        map(git_line_parser, ListingLine::Branch)
+    ))(input)
+}
+
+/// The lines of `input`, ending with LF or CRLF. Unlike [`str::lines`], a
+/// `\r` left over at the very end, e.g. from input cut off between the two,
+/// is dropped too.
+fn split_lines(input: &str) -> impl Iterator<Item = &str> {
+    input.lines().map(take_comment)
+}
+
+/// The non-blank lines of `input`, numbered from 1.
+fn numbered_lines(input: &str) -> Vec<(usize, &str)> {
+    split_lines(input).enumerate().map(|(i, line)| (i + 1, line)).filter(|(_, line)| !line.trim().is_empty()).collect()
+}
+
+/// How the multi-line parsers treat lines they can't parse.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ParseMode {
+    /// Skip them with a warning saying where.
+    #[default]
+    Lenient,
+    /// Fail on the first one.
+    Strict,
+    /// Ignore them silently, for input that is mostly something else,
+    /// like a CI log. See [`scan`].
+    Scan
+}
+
+/// The outcome of parsing one numbered line: `Ok(None)` for lines that
+/// parse but aren't wanted, such as detached HEADs.
+pub(crate) type LineResult<'a, T> = (usize, &'a str, Result<Option<T>, nom::Err<nom::error::Error<&'a str>>>);
+
+/// Everything a multi-line parser found, in one pass.
+#[derive(Debug)]
+pub struct ParseReport<T> {
+    /// What parsed, in input order.
+    pub parsed: Vec<T>,
+    /// The lines that didn't parse and where they went wrong.
+    pub skipped: Vec<ParseError>,
+    /// Lines that parsed but aren't wanted, such as detached HEADs.
+    pub ignored: usize
+}
+
+impl<T> ParseReport<T> {
+    /// The number of non-blank lines read.
+    pub fn total(&self) -> usize {
+        self.parsed.len() + self.skipped.len() + self.ignored
+    }
+
+    /// What parsed, or the first skipped line in [`ParseMode::Strict`].
+    /// Skipped lines are logged as warnings in [`ParseMode::Lenient`].
+    pub fn into_result(self, mode: ParseMode) -> Result<Vec<T>, ParseError> {
+        match mode {
+            ParseMode::Strict => if let Some(error) = self.skipped.into_iter().next() {
+                return Err(error)
+            },
+            ParseMode::Lenient => for error in &self.skipped {
+                tracing::warn!("skipping {}", error)
+            },
+            ParseMode::Scan => {}
+        }
+        Ok(self.parsed)
+    }
+}
+
+/// Gathers parsed lines into a report, in order.
+pub(crate) fn report_lines<'a, T>(results: impl IntoIterator<Item = LineResult<'a, T>>) -> ParseReport<T> {
+    let mut report = ParseReport { parsed: Vec::new(), skipped: Vec::new(), ignored: 0 };
+    for (line_number, line, result) in results {
+        match result {
+            Ok(Some(item)) => report.parsed.push(item),
+            Ok(None) => report.ignored += 1,
+            Err(e) => report.skipped.push(ParseError::invalid_line(line_number, line, e))
+        }
+    }
+
+    tracing::info!("parsed {} of {} lines", report.parsed.len(), report.total());
+    report
+}
+
+fn parse_branch_only(line: &str) -> Result<Option<BranchLine>, nom::Err<nom::error::Error<&str>>> {
+    match parse_line(line)? {
+        (_, ListingLine::Branch(branch_line)) => Ok(Some(branch_line)),
+        (_, ListingLine::DetachedHead(_)) => Ok(None)
+    }
+}
+
+/// Parses every non-blank line of `git branch -vv` output.
+///
+/// Detached HEAD lines and lines that don't parse are skipped, see
+/// [`parse_branch_lines_with`] to fail on the latter instead.
+pub fn parse_branch_lines(input: &str) -> Vec<BranchLine> {
+    parse_branch_lines_with(input, ParseMode::Lenient).unwrap_or_default()
+}
+
+/// Parses every non-blank line of `git branch -vv` output, treating lines
+/// that don't parse as `mode` says. Detached HEAD lines are always skipped.
+pub fn parse_branch_lines_with(input: &str, mode: ParseMode) -> Result<Vec<BranchLine>, ParseError> {
+    if mode == ParseMode::Scan {
+        return Ok(scan::scan_branch_lines(input))
+    }
+    parse_branch_lines_report(input).into_result(mode)
+}
+
+/// Parses every non-blank line of `git branch -vv` output, reporting the
+/// branches along with the lines that were skipped or ignored.
+pub fn parse_branch_lines_report(input: &str) -> ParseReport<BranchLine> {
+    report_lines(numbered_lines(input).into_iter().map(|(n, line)| (n, line, parse_branch_only(line))))
+}
+
+/// Like [`parse_branch_lines`], but borrows from `input` instead of
+/// allocating for every branch.
+pub fn parse_branch_line_refs(input: &str) -> Vec<BranchLineRef<'_>> {
+    split_lines(input)
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| parse_branch_line_ref(line).ok())
+        .map(|(_, branch_line)| branch_line)
+        .collect()
+}
+
+/// Like [`parse_branch_lines_with`], but parses lines in parallel on the
+/// current rayon thread pool. The branches are still in input order.
+#[cfg(feature = "parallel")]
+pub fn par_parse_branch_lines_with(input: &str, mode: ParseMode) -> Result<Vec<BranchLine>, ParseError> {
+    use rayon::prelude::*;
+
+    let results: Vec<_> = numbered_lines(input).into_par_iter().map(|(n, line)| (n, line, parse_branch_only(line))).collect();
+    report_lines(results).into_result(mode)
+}
+
+/// Possible variations:
+/// "* (HEAD detached at abc1234) abc1234 Random weird comments"
+/// "* (HEAD detached from origin/main) abc1234 Random weird comments"
+/// "* (no branch, rebasing FeatureA) abc1234 Random weird comments"
+///
+fn detached_head_parser(input: &str) -> IResult<&str, DetachedHead> {
+    let (tail2, _)           = take_marker(input)?;
+    let (tail3, description) = delimited(tag("("), take_until(")"), tag(")"))(tail2)?;
+    let (tail4, _)           = take_whitespace(tail3)?;
+    let (tail5, commit)      = take_hex(tail4)?;
+    let (tail6, _)           = take_whitespace(tail5)?;
+
+    let detached_head = DetachedHead { description: description.to_string(), commit, comment: take_comment(tail6).to_string() };
+
+    Ok((tail6, detached_head))
+}
+
+/// Possible variations:
+/// "[info]   FeatureA         dddeeee Random weird comments"
+/// "[info]   FeatureD         ffff1111 [Ahead 1] Random weird comments"
+/// "[info]   FeatureB         eeee3333 [behind 3] Random weird comments"
+/// "[info] * master           0000bbbb [behind 2] Random weird comments"
+/// "[info]   FeatureC         dddd3333 [gone] Random weird comments"
+/// "[info]   PERSON1/FeatureD eeee4444 [gone] Random weird comments"
+///
+fn git_line_parser(input: &str) -> IResult<&str, BranchLine> {
+    map(git_line_ref_parser, |line| line.to_owned())(input)
+}
+
+fn git_line_ref_parser<'a>(input: &'a str) -> IResult<&'a str, BranchLineRef<'a>> {
+    observed_git_line_ref_parser(input, &mut |_, _, _| {})
+}
+
+/// Told the name, input and outcome of each step of the branch line parser,
+/// for [`trace`](crate::trace) to follow it: the input left after the step, or why the
+/// step failed. A failed step that the parser goes on from was optional.
+pub(crate) type Observer<'o, 'a> = dyn FnMut(&'static str, &'a str, Result<&'a str, &nom::Err<nom::error::Error<&'a str>>>) + 'o;
+
+/// Runs `parser` on `input` as the step `name`, telling `observe` about it.
+fn observed<'a, O>(name: &'static str, observe: &mut Observer<'_, 'a>, input: &'a str, mut parser: impl FnMut(&'a str) -> IResult<&'a str, O>) -> IResult<&'a str, O> {
+    let result = parser(input);
+    observe(name, input, result.as_ref().map(|(rest, _)| *rest));
+    result
+}
+
+/// Like [`opt`], for an observed step.
+fn observed_opt<'a, O>(name: &'static str, observe: &mut Observer<'_, 'a>, input: &'a str, parser: impl FnMut(&'a str) -> IResult<&'a str, O>) -> IResult<&'a str, Option<O>> {
+    match observed(name, observe, input, parser) {
+        Ok((rest, output)) => Ok((rest, Some(output))),
+        Err(nom::Err::Error(_)) => Ok((input, None)),
+        Err(e) => Err(e)
+    }
+}
+
+/// [`git_line_ref_parser`], telling `observe` about each step.
+pub(crate) fn observed_git_line_ref_parser<'a>(input: &'a str, observe: &mut Observer<'_, 'a>) -> IResult<&'a str, BranchLineRef<'a>> {
+    let (tail2, marker)     = observed("take_marker", observe, input, take_marker)?;
+    let (tail3, branch_n)   = observed("take_branch_name", observe, tail2, take_branch_name)?;
+    let (tail4, _)          = observed("take_whitespace", observe, tail3, take_whitespace)?;
+    let (tail5, commit)     = observed("take_hex", observe, tail4, take_hex_str)?;
+    let (tail6, _)          = observed("take_whitespace", observe, tail5, take_whitespace)?;
+    let (tail7, _)          =
+        if marker == Some('+') {
+            observed_opt("take_worktree_path", observe, tail6, terminated(take_worktree_path, take_whitespace))?
+        } else {
+            (tail6, None)
+        };
+    let (tail8, annotation) = observed_opt("take_annotation", observe, tail7, take_annotation)?;
+    let (tail9, _)          = observed("take_whitespace", observe, tail8, take_whitespace)?;
+
+    let is_current = marker == Some('*');
+    let checked_out_in_worktree = marker == Some('+');
+
+    let status = annotation.as_ref().map_or(BranchStatus::NoUpstream, Annotation::status);
+    let upstream = annotation.and_then(|annotation| annotation.upstream);
+
+    // The rest of the line is the comment, and is also left as the remaining
+    // input, as parse_branch_line documents.
+    Ok((tail9, BranchLineRef { branch_name: branch_n, is_current, checked_out_in_worktree, status, commit, upstream, comment: take_comment(tail9) }))
+}
+
+#[test]
+fn parse_git_line_remove_info() {
+    let git_line = "[info]abc";
+    let (r, m) = take_tag("[info]", git_line).unwrap();
+
+    assert_eq!(m, "[info]");
+    assert_eq!(r, "abc");
+}
+
+#[test]
+fn parse_git_line_remove_whitespace() {
+    let git_line = "   FeatureC  abcd";
+    let (r, m) = take_whitespace(git_line).unwrap();
+
+    assert_eq!(m, "   ");
+    assert_eq!(r, "FeatureC  abcd");
+}
+
+#[test]
+fn parse_git_line_take_alphabetics() {
+    let git_line = "FeatureC         dddd3333";
+    let (r, m) = take_alphabetic(git_line).unwrap();
+    assert_eq!(m, "FeatureC");
+    assert_eq!(r, "         dddd3333");
+}
+
+/// Branch name with dashes and slashes
+#[test]
+fn parse_git_line_take_branch_name() {
+    let git_line = "xyz/some-name-with-dashes         dddd3333";
+    let (r, m) = take_branch_name(git_line).unwrap();
+    assert_eq!(m, "xyz/some-name-with-dashes");
+    assert_eq!(r, "         dddd3333");
+}
+
+#[test]
+fn parse_git_line_take_branch_name_2() {
+    let git_line = "ID-9AB-blee-blah-2                              dddd3333 Blah de blah";
+    let (r, m) = take_branch_name(git_line).unwrap();
+    assert_eq!(m, "ID-9AB-blee-blah-2");
+    assert_eq!(r, "                              dddd3333 Blah de blah");
+}
+
+/// 1. Star marks the current branch
+/// 2. Plus marks a branch in another worktree
+/// 3. Whitespace only
+#[test]
+fn parse_git_line_take_marker() {
+    assert_eq!(take_marker(" * master"), Ok(("master", Some('*'))));
+    assert_eq!(take_marker("*master"), Ok(("master", Some('*'))));
+    assert_eq!(take_marker("+ wt1"), Ok(("wt1", Some('+'))));
+    assert_eq!(take_marker("   master"), Ok(("master", None)));
+}
+
+/// 1. Plus marker
+/// 2. Worktree path after the hash
+/// 3. Full tracking annotation
+#[test]
+fn parse_git_line_worktree() {
+    let git_line = "+ wt1    8ef9921 (/tmp/e2e/wt) [origin/wt1: behind 1] init";
+    let (_, m) = git_line_parser(git_line).unwrap();
+    let expected = BranchLine { branch_name: "wt1".to_string(), is_current: false, checked_out_in_worktree: true, status: BranchStatus::Behind(1), commit: HexValue("8ef9921".to_string()), upstream: Some(Upstream::new("origin", "wt1")), comment: "init".to_string(), last_commit_date: None, merged: false, pr_state: None, provider: None };
+    assert_eq!(m, expected);
+}
+
+/// 1. Parenthesised comment on a branch that isn't in a worktree
+#[test]
+fn parse_git_line_parenthesised_comment() {
+    let git_line = "  FeatureA dddeeee (fix) Random weird comments";
+    let (_, m) = git_line_parser(git_line).unwrap();
+    assert_eq!(m.comment, "(fix) Random weird comments");
+}
+
+/// 1. Dots in version numbers
+/// 2. At signs, plus signs and hashes
+#[test]
+fn parse_git_line_take_branch_name_3() {
+    let cases = ["release/v1.2.3", "user@feature", "fix+feature", "issue#123", "ID-9AB_blee.blah-2"];
+    for name in cases {
+        let git_line = format!("{}         dddd3333", name);
+        let (r, m) = take_branch_name(&git_line).unwrap();
+        assert_eq!(m, name);
+        assert_eq!(r, "         dddd3333");
+    }
+}
+
+/// 1. CJK characters and Japanese punctuation
+/// 2. Emoji, including a zero width joiner sequence
+/// 3. Combining accents
+/// 4. Non-ASCII spaces, which git allows in ref names
+#[test]
+fn parse_git_line_take_branch_name_unicode() {
+    let cases = ["機能/新しい-ブランチ", "功能/修复・错误", "feature/🚀-launch", "team/👨‍👩‍👧", "cafe\u{301}/naïve", "wip\u{3000}draft", "a\u{a0}b"];
+    for name in cases {
+        let git_line = format!("{}  dddd3333 Blah", name);
+        let (r, m) = take_branch_name(&git_line).unwrap();
+        assert_eq!(m, name);
+        assert_eq!(r, "  dddd3333 Blah");
+    }
+}
+
+/// 1. CJK branch name
+/// 2. CJK upstream in the annotation
+/// 3. Emoji in comment
+#[test]
+fn parse_git_line_unicode() {
+    let git_line = "* 機能/新しい-ブランチ eeee4444 [origin/機能/新しい-ブランチ: ahead 1] 修正 😃";
+    let (_, m) = git_line_parser(git_line).unwrap();
+    assert_eq!(m.branch_name, "機能/新しい-ブランチ");
+    assert_eq!(m.upstream, Some(Upstream::new("origin", "機能/新しい-ブランチ")));
+    assert_eq!(m.status, BranchStatus::Ahead(1));
+    assert_eq!(m.comment, "修正 😃");
+}
+
+#[test]
+fn branch_name_validity() {
+    let valid = ["main", "release/v1.2.3", "user@feature", "a.b/c-d", "])"];
+    let invalid = ["", "@", "-feature", "/feature", "feature/", "feature.", "a..b", "a@{1}", "a//b", "a/.hidden", "a.lock/b", "feature.lock", "a~1", "a^", "a:b", "a?", "a*", "a[b", "a\\b", "a b", "a\u{7f}"];
+
+    for name in valid {
+        assert!(is_valid_branch_name(name), "{:?} should be valid", name);
+    }
+
+    for name in invalid {
+        assert!(!is_valid_branch_name(name), "{:?} should be invalid", name);
+    }
+}
+
+/// 1. Dotted branch name with a matching upstream
+#[test]
+fn parse_git_line_dotted_branch() {
+    let git_line = "  release/v1.2.3 dddd3333 [origin/release/v1.2.3: ahead 2] Release";
+    let (_, m) = git_line_parser(git_line).unwrap();
+    assert_eq!(m.branch_name, "release/v1.2.3");
+    assert_eq!(m.upstream, Some(Upstream::new("origin", "release/v1.2.3")));
+    assert_eq!(m.comment, "Release");
+}
+
+/// 1. An unabbreviated hash
+/// 2. Shortening it
+/// 3. Too short to shorten
+#[test]
+fn parse_full_hash() {
+    let (_, m) = parse_branch_line("  FeatureC 8ef99217a2f1e3b9c0d4a5b6c7d8e9f0a1b2c3d4 [gone] X").unwrap();
+    assert_eq!(m.commit.len(), 40);
+    assert!(m.commit.is_full());
+    assert_eq!(m.commit.abbreviated(7), Some(HexValue("8ef9921".to_string())));
+    assert_eq!(HexValue("8ef9921".to_string()).abbreviated(12), None);
+}
+
+#[test]
+fn parse_git_line_take_hex() {
+    let git_line = "dddd3333 G32H";
+    let (r, m) = take_hex(git_line).unwrap();
+    assert_eq!(m, HexValue("dddd3333".to_string()));
+    assert_eq!(r, " G32H");
+}
+
+/// 1. Missing
+/// 2. Not hex
+/// 3. Hex followed by other letters
+/// 4. Longer than a full hash
+/// 5. Shorter than git ever abbreviates
+#[test]
+fn parse_git_line_take_hex_invalid() {
+    assert!(take_hex(" [gone]").is_err());
+    assert!(take_hex("zzzz3333").is_err());
+    assert!(take_hex("dddd3333G32H").is_err());
+    assert!(take_hex(&"a".repeat(FULL_HASH_LEN + 1)).is_err());
+    assert!(parse_branch_line("  FeatureC  [gone] Random").is_err());
+    assert!(take_hex("ddd G32H").is_err());
+    assert!(parse_branch_line("  deadbeef a b").is_err());
+    assert!(take_hex("dddd G32H").is_ok());
+}
+
+
+/// 1. Alphabetic branch name
+/// 2. [gone] annotation
+#[test]
+fn parse_git_line() {
+    let git_line = "   FeatureC         dddd3333 [gone] Random weird comments";
+    let (r, m) = git_line_parser(git_line).unwrap();
+    let expected = BranchLine { branch_name: "FeatureC".to_string(), is_current: false, checked_out_in_worktree: false, status: BranchStatus::Gone, commit: HexValue("dddd3333".to_string()), upstream: None, comment: "Random weird comments".to_string(), last_commit_date: None, merged: false, pr_state: None, provider: None };
+    assert_eq!(m,  expected);
+    assert_eq!(r, "Random weird comments");
+}
+
+/// 1. hyphenated branch name
+/// 2. No annotation
+#[test]
+fn parse_git_line_2() {
+    let git_line = "   ID-9AB-blee-blah-2                              dddd3333 Blah de blah";
+    let (r, m) = git_line_parser(git_line).unwrap();
+    let expected = BranchLine { branch_name: "ID-9AB-blee-blah-2".to_string(), is_current: false, checked_out_in_worktree: false, status: BranchStatus::NoUpstream, commit: HexValue("dddd3333".to_string()), upstream: None, comment: "Blah de blah".to_string(), last_commit_date: None, merged: false, pr_state: None, provider: None };
+    assert_eq!(m,  expected);
+    assert_eq!(r, "Blah de blah");
+}
+
+/// 1. Hyphenated branch name
+/// 2. Star (representing current branch)
+/// 3. No annotation
+#[test]
+fn parse_git_line_3() {
+    let git_line = " * ID-9AB-blee-blah-2                              dddd3333 Blah de blah";
+    let (r, m) = git_line_parser(git_line).unwrap();
+    let expected = BranchLine { branch_name: "ID-9AB-blee-blah-2".to_string(), is_current: true, checked_out_in_worktree: false, status: BranchStatus::NoUpstream, commit: HexValue("dddd3333".to_string()), upstream: None, comment: "Blah de blah".to_string(), last_commit_date: None, merged: false, pr_state: None, provider: None };
+    assert_eq!(m,  expected);
+    assert_eq!(r, "Blah de blah");
+}
+
+/// 1. Alphabetic branch name
+/// 2. [behind 3] annotation
+#[test]
+fn parse_git_line_4() {
+    let git_line = "FeatureB         eeee3333 [behind 3] Random weird comments";
+    let (r, m) = git_line_parser(git_line).unwrap();
+    let expected = BranchLine { branch_name: "FeatureB".to_string(), is_current: false, checked_out_in_worktree: false, status: BranchStatus::Behind(3), commit: HexValue("eeee3333".to_string()), upstream: None, comment: "Random weird comments".to_string(), last_commit_date: None, merged: false, pr_state: None, provider: None };
+    assert_eq!(m,  expected);
+    assert_eq!(r, "Random weird comments");
+}
+
+
+/// 1. Hyphenated and slashed branch name
+/// 2. Star (representing current branch)
+/// 3. [ahead 1] annotation
+#[test]
+fn parse_git_line_5() {
+    let git_line = " * XYZ/ID-9AB-blee-blah-2                        dddd3333   [ahead 1]   Blah de blah";
+    let (r, m) = git_line_parser(git_line).unwrap();
+    let expected = BranchLine { branch_name: "XYZ/ID-9AB-blee-blah-2".to_string(), is_current: true, checked_out_in_worktree: false, status: BranchStatus::Ahead(1), commit: HexValue("dddd3333".to_string()), upstream: None, comment: "Blah de blah".to_string(), last_commit_date: None, merged: false, pr_state: None, provider: None };
+    assert_eq!(m,  expected);
+    assert_eq!(r, "Blah de blah");
+}
+
+/// 1. Hyphenated and slashed branch name
+/// 2. Star (representing current branch)
+/// 3. [ahead 1] annotation
+/// 4. Emoji in comment
+#[test]
+fn parse_git_line_6() {
+    let git_line = " * XYZ/ID-9AB-blee-blah-2                        dddd3333   [ahead 1]   Blah 😃 blah";
+    let (r, m) = git_line_parser(git_line).unwrap();
+    let expected = BranchLine { branch_name: "XYZ/ID-9AB-blee-blah-2".to_string(), is_current: true, checked_out_in_worktree: false, status: BranchStatus::Ahead(1), commit: HexValue("dddd3333".to_string()), upstream: None, comment: "Blah 😃 blah".to_string(), last_commit_date: None, merged: false, pr_state: None, provider: None };
+    assert_eq!(m,  expected);
+    assert_eq!(r, "Blah 😃 blah");
+}
+
+/// 1. Multiple lines
+/// 2. Blank lines are ignored
+#[test]
+fn parse_git_lines() {
+    let input = "  FeatureA         dddeeee Random weird comments\n\n* master           0000bbbb [behind 2] More comments\n   FeatureC         dddd3333 [gone] Gone comments\n";
+    let branches = parse_branch_lines(input);
+    let names: Vec<&str> = branches.iter().map(|b| b.branch_name.as_str()).collect();
+    assert_eq!(names, vec!["FeatureA", "master", "FeatureC"]);
+    assert_eq!(branches[2].status, BranchStatus::Gone);
+}
+
+/// 1. Lower and upper case statuses
+/// 2. Anything else isn't a tracking status
+#[test]
+fn parse_git_line_take_tracking_status() {
+    assert_eq!(take_tracking_status("ahead 12"), Ok(("", TrackingStatus::Ahead(12))));
+    assert_eq!(take_tracking_status("Behind 3"), Ok(("", TrackingStatus::Behind(3))));
+    assert_eq!(take_tracking_status("gone"), Ok(("", TrackingStatus::Gone)));
+    assert!(take_tracking_status("ahead").is_err());
+    assert!(take_tracking_status("info").is_err());
+}
+
+#[test]
+fn parse_git_line_take_annotation() {
+    let cases = [
+        ("[origin/feature: ahead 1, behind 2]", Annotation { upstream: Some("origin/feature"), gone: false, ahead_behind: AheadBehind { ahead: 1, behind: 2 } }),
+        ("[origin/PERSON1/FeatureD: gone]", Annotation { upstream: Some("origin/PERSON1/FeatureD"), gone: true, ahead_behind: AheadBehind::default() }),
+        ("[origin/main]", Annotation { upstream: Some("origin/main"), gone: false, ahead_behind: AheadBehind::default() }),
+        ("[behind 3]", Annotation { upstream: None, gone: false, ahead_behind: AheadBehind { ahead: 0, behind: 3 } }),
+        ("[ahead 2, behind 5]", Annotation { upstream: None, gone: false, ahead_behind: AheadBehind { ahead: 2, behind: 5 } }),
+        ("[gone]", Annotation { upstream: None, gone: true, ahead_behind: AheadBehind::default() }),
+    ];
+
+    for (input, expected) in cases {
+        assert_eq!(take_annotation(input), Ok(("", expected)), "{}", input);
+    }
+
+    assert!(take_annotation("[WIP]").is_err());
+    assert!(take_annotation("[/x]").is_err());
+}
+
+/// 1. A subject starting with a bracketed word isn't an upstream
+#[test]
+fn parse_git_line_bracketed_subject() {
+    let (_, m) = git_line_parser("  feature abc1 [WIP] subject").unwrap();
+    assert_eq!(m.status, BranchStatus::NoUpstream);
+    assert_eq!(m.upstream, None);
+    assert_eq!(m.comment, "[WIP] subject");
+}
+
+/// 1. Full tracking annotation with upstream
+/// 2. Gone upstream
+#[test]
+fn parse_git_line_full_annotation() {
+    let git_line = "  feature/x 8ef9921 [origin/feature/x: gone] init";
+    let (_, m) = git_line_parser(git_line).unwrap();
+    let expected = BranchLine { branch_name: "feature/x".to_string(), is_current: false, checked_out_in_worktree: false, status: BranchStatus::Gone, commit: HexValue("8ef9921".to_string()), upstream: Some(Upstream::new("origin", "feature/x")), comment: "init".to_string(), last_commit_date: None, merged: false, pr_state: None, provider: None };
+    assert_eq!(m, expected);
+}
+
+/// 1. Full tracking annotation with upstream
+/// 2. Diverged from upstream
+#[test]
+fn parse_git_line_full_annotation_2() {
+    let git_line = "* master 0000bbbb [origin/master: ahead 1, behind 2] Random weird comments";
+    let (_, m) = git_line_parser(git_line).unwrap();
+    let expected = BranchLine { branch_name: "master".to_string(), is_current: true, checked_out_in_worktree: false, status: BranchStatus::Diverged { ahead: 1, behind: 2 }, commit: HexValue("0000bbbb".to_string()), upstream: Some(Upstream::new("origin", "master")), comment: "Random weird comments".to_string(), last_commit_date: None, merged: false, pr_state: None, provider: None };
+    assert_eq!(m, expected);
+}
+
+/// 1. Tracked and in sync
+/// 2. Ahead
+/// 3. Diverged
+/// 4. Gone
+/// 5. Not tracking anything
+#[test]
+fn parse_branch_status() {
+    let cases = [
+        ("  a 0000bbbb [origin/a] X", BranchStatus::UpToDate),
+        ("  a 0000bbbb [origin/a: ahead 3] X", BranchStatus::Ahead(3)),
+        ("  a 0000bbbb [origin/a: ahead 1, behind 2] X", BranchStatus::Diverged { ahead: 1, behind: 2 }),
+        ("  a 0000bbbb [origin/a: gone] X", BranchStatus::Gone),
+        ("  a 0000bbbb X", BranchStatus::NoUpstream),
+    ];
+
+    for (input, expected) in cases {
+        assert_eq!(parse_branch_line(input).unwrap().1.status, expected, "{}", input);
+    }
+    assert_eq!(BranchStatus::Diverged { ahead: 1, behind: 2 }.ahead_behind(), AheadBehind { ahead: 1, behind: 2 });
+}
+
+/// 1. A remote branch with slashes in its name
+/// 2. A local branch
+#[test]
+fn parse_upstream() {
+    assert_eq!(Upstream::parse("origin/feature/x"), Upstream::new("origin", "feature/x"));
+    assert_eq!(Upstream::parse("main"), Upstream::new(".", "main"));
+    assert_eq!(Upstream::parse("origin/feature/x").to_string(), "origin/feature/x");
+    assert_eq!(Upstream::parse("main").to_string(), "main");
+}
+
+#[test]
+#[allow(deprecated)]
+fn deprecated_names_still_work() {
+    let line: GitHubBranchLine = "  a 0000bbbb [gone] X".parse().unwrap();
+    let status: GitHubBranchType = line.status;
+    assert!(status.is_gone());
+}
+
+#[test]
+fn ahead_behind_display() {
+    assert_eq!(AheadBehind { ahead: 0, behind: 0 }.to_string(), "");
+    assert_eq!(AheadBehind { ahead: 2, behind: 0 }.to_string(), "ahead 2");
+    assert_eq!(AheadBehind { ahead: 0, behind: 5 }.to_string(), "behind 5");
+    assert_eq!(AheadBehind { ahead: 2, behind: 5 }.to_string(), "ahead 2, behind 5");
+}
+
+/// 1. Detached at a commit
+/// 2. Not a branch line
+#[test]
+fn parse_git_line_detached_head() {
+    let git_line = "* (HEAD detached at 8ef9921) 8ef9921 init";
+    let (_, m) = parse_line(git_line).unwrap();
+    let expected = ListingLine::DetachedHead(DetachedHead { description: "HEAD detached at 8ef9921".to_string(), commit: HexValue("8ef9921".to_string()), comment: "init".to_string() });
+    assert_eq!(m, expected);
+    assert!(parse_branch_line(git_line).is_err());
+}
+
+/// 1. Rebasing, with a comma in the description
+#[test]
+fn parse_git_line_detached_head_rebasing() {
+    let git_line = "* (no branch, rebasing FeatureA) dddd3333 Random weird comments";
+    let (_, m) = parse_line(git_line).unwrap();
+    let expected = ListingLine::DetachedHead(DetachedHead { description: "no branch, rebasing FeatureA".to_string(), commit: HexValue("dddd3333".to_string()), comment: "Random weird comments".to_string() });
+    assert_eq!(m, expected);
+}
+
+#[test]
+fn parse_git_lines_skips_detached_head() {
+    let input = "* (HEAD detached at 8ef9921) 8ef9921 init\n  master 8ef9921 [origin/master: gone] init\n";
+    let names: Vec<String> = parse_branch_lines(input).into_iter().map(|b| b.branch_name).collect();
+    assert_eq!(names, vec!["master"]);
+}
+
+/// 1. CRLF line endings, with and without a comment
+/// 2. A line cut off after its `\r`
+/// 3. A single line parsed on its own
+#[test]
+fn parse_git_lines_crlf() {
+    let input = "  FeatureA dddeeee Random weird comments\r\n\r\n* master 0000bbbb [origin/master: behind 2]\r\n  FeatureC dddd3333 [gone] Gone\r";
+    let comments: Vec<(String, String)> = parse_branch_lines_with(input, ParseMode::Strict).unwrap().into_iter().map(|b| (b.branch_name, b.comment)).collect();
+    assert_eq!(comments, vec![
+        ("FeatureA".to_string(), "Random weird comments".to_string()),
+        ("master".to_string(), "".to_string()),
+        ("FeatureC".to_string(), "Gone".to_string()),
+    ]);
+    assert_eq!(parse_branch_line_refs(input)[2].comment, "Gone");
+    assert_eq!(parse_branch_line("  FeatureA dddeeee Random\r").unwrap().1.comment, "Random");
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn parse_lines_in_parallel_keeps_order() {
+    let input: String = (0..1000).map(|i| format!("  feature/{} dddd3333 Comment {}\n", i, i)).collect();
+    assert_eq!(par_parse_branch_lines_with(&input, ParseMode::Lenient).unwrap(), parse_branch_lines(&input));
+}
+
+/// 1. Borrowed fields match the owned parse
+/// 2. Detached HEAD lines are rejected
+#[test]
+fn parse_branch_line_refs_borrow_input() {
+    let input = "* (HEAD detached at 8ef9921) 8ef9921 init\n  FeatureC dddd3333 [origin/FeatureC: gone] Random\n* master 0000bbbb [origin/master: ahead 1] Blah\n";
+    let refs = parse_branch_line_refs(input);
+
+    assert_eq!(refs.len(), 2);
+    assert_eq!(refs[0].upstream, Some("origin/FeatureC"));
+    assert_eq!(refs[1].comment, "Blah");
+    assert_eq!(refs.iter().map(BranchLineRef::to_owned).collect::<Vec<_>>(), parse_branch_lines(input));
+}
+
+#[test]
+fn parse_from_str() {
+    let line: BranchLine = "  FeatureC dddd3333 [gone] Random".parse().unwrap();
+    assert_eq!(line.status, BranchStatus::Gone);
+    assert!("*".parse::<BranchLine>().is_err());
+}
+
+/// 1. Lenient skips the bad line
+/// 2. Strict fails with its position
+#[test]
+fn parse_modes() {
+    let input = "  FeatureA dddd3333 A\n  bad.lock eeee3333 B\n  FeatureC ffff1111 C\n";
+    let names: Vec<String> = parse_branch_lines_with(input, ParseMode::Lenient).unwrap().into_iter().map(|b| b.branch_name).collect();
+    assert_eq!(names, vec!["FeatureA", "FeatureC"]);
+
+    let error = parse_branch_lines_with(input, ParseMode::Strict).unwrap_err();
+    assert_eq!(error.to_string(), "line 2, column 3: can't parse \"  bad.lock eeee3333 B\"");
+}
+
+#[test]
+fn parse_report_counts() {
+    let input = "* (HEAD detached at 8ef9921) 8ef9921 init\n  FeatureA dddd3333 A\n\n  bad.lock eeee3333 B\n";
+    let report = parse_branch_lines_report(input);
+
+    assert_eq!(report.parsed.len(), 1);
+    assert_eq!(report.ignored, 1);
+    assert_eq!(report.total(), 3);
+    assert_eq!(report.skipped.iter().map(ToString::to_string).collect::<Vec<_>>(), vec!["line 4, column 3: can't parse \"  bad.lock eeee3333 B\""]);
+}
+
+/// 1. Every status survives a JSON round trip
+/// 2. Unknown statuses are rejected
+#[cfg(feature = "serde")]
+#[test]
+fn serde_round_trip() {
+    let mut branches = parse_branch_lines(
+        "* master 0000bbbb [origin/master: ahead 1, behind 2] A\n  \
+           FeatureC dddd3333 [origin/FeatureC: gone] B\n  \
+           develop eeee4444 [origin/develop: behind 1] C\n  \
+           local aaaa1111 D\n"
+    );
+    branches[0].pr_state = Some(PrState::Open);
+    branches[0].provider = Some(CodeHost::GitHub);
+
+    let json = serde_json::to_string(&branches).unwrap();
+    assert_eq!(serde_json::from_str::<Vec<BranchLine>>(&json).unwrap(), branches);
+
+    let json = json.replacen("\"status\":\"diverged\"", "\"status\":\"sideways\"", 1);
+    assert!(serde_json::from_str::<Vec<BranchLine>>(&json).is_err());
+}
@@ -0,0 +1,99 @@
+//! Reading branches through libgit2 instead of parsing git's output.
+//!
+//! Only available with the `git2` feature.
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use git2::{BranchType, Repository};
+
+use crate::{AheadBehind, BranchStatus, BranchLine, HexValue, Upstream};
+
+
+/// Reads the local branches of the repository at `dir`, with the same
+/// information `git branch -vv` would show.
+pub fn branches(dir: &Path) -> Result<Vec<BranchLine>, git2::Error> {
+    let repo = Repository::open(dir)?;
+    let in_worktrees = worktree_branches(&repo)?;
+
+    let mut lines = Vec::new();
+    for branch in repo.branches(Some(BranchType::Local))? {
+        let (branch, _) = branch?;
+        let reference = branch.get();
+        let refname = reference.name().unwrap_or_default().to_string();
+        let branch_name = branch.name()?.unwrap_or_default().to_string();
+        let head = reference.peel_to_commit()?;
+
+        let upstream_ref = repo.branch_upstream_name(&refname).ok().and_then(|name| name.as_str().map(str::to_string));
+        let upstream_oid = upstream_ref.as_deref().and_then(|name| repo.refname_to_id(name).ok());
+
+        let status = match (&upstream_ref, upstream_oid) {
+            (None, _) => BranchStatus::NoUpstream,
+            (Some(_), None) => BranchStatus::Gone,
+            (Some(_), Some(upstream)) => {
+                let (ahead, behind) = repo.graph_ahead_behind(head.id(), upstream)?;
+                BranchStatus::tracking(false, AheadBehind { ahead: ahead as u32, behind: behind as u32 })
+            }
+        };
+
+        let commit = HexValue(head.as_object().short_id()?.as_str().unwrap_or_default().to_string());
+        let upstream = upstream_ref.map(|name| match name.strip_prefix("refs/heads/") {
+            Some(local) => Upstream::new(".", local),
+            None => Upstream::parse(name.trim_start_matches("refs/remotes/"))
+        });
+
+        lines.push(BranchLine {
+            is_current: branch.is_head(),
+            checked_out_in_worktree: !branch.is_head() && in_worktrees.contains(&refname),
+            status,
+            commit,
+            upstream,
+            comment: head.summary().unwrap_or_default().to_string(),
+            last_commit_date: Some(head.time().seconds()),
+            merged: false,
+            pr_state: None,
+            provider: None,
+            branch_name
+        });
+    }
+
+    Ok(lines)
+}
+
+/// The refs checked out in linked worktrees.
+fn worktree_branches(repo: &Repository) -> Result<HashSet<String>, git2::Error> {
+    let mut refs = HashSet::new();
+    for name in repo.worktrees()?.iter().flatten() {
+        let worktree = repo.find_worktree(name)?;
+        if let Ok(worktree_repo) = Repository::open_from_worktree(&worktree) {
+            if let Some(head) = worktree_repo.head().ok().and_then(|h| h.name().map(str::to_string)) {
+                refs.insert(head);
+            }
+        }
+    }
+    Ok(refs)
+}
+
+
+/// 1. Current branch
+/// 2. Gone upstream
+/// 3. No upstream
+#[test]
+fn libgit2_branches() {
+    let dir = crate::git::temp_repo("libgit2");
+    for args in [
+        &["remote", "add", "origin", "https://example.com/brune.git"][..],
+        &["config", "branch.feature/a.remote", "origin"][..],
+        &["config", "branch.feature/a.merge", "refs/heads/feature/a"][..],
+    ] {
+        crate::git::run_git(&dir, args).unwrap();
+    }
+
+    let branches = branches(&dir).unwrap();
+    let mut expected = crate::parse_branch_lines(&crate::git::branch_listing(&dir, None).unwrap());
+    crate::git::add_commit_dates(&dir, &mut expected).unwrap();
+
+    assert_eq!(branches, expected);
+    assert_eq!(branches[0].status, BranchStatus::Gone);
+    assert!(branches[1].is_current);
+}
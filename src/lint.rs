@@ -0,0 +1,256 @@
+//! Checking branch names against naming rules, such as
+//! `<type>/<ticket>-<slug>` with only some types allowed.
+
+use std::fmt;
+use std::io::{self, Write};
+
+use regex::Regex;
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use crate::protect::ProtectedBranches;
+use crate::BranchLine;
+
+
+/// How many violations lint allows when neither `--max-violations` nor the
+/// config say otherwise.
+pub const DEFAULT_MAX_VIOLATIONS: usize = 0;
+
+/// The placeholders a structured rule can be written with, and what each
+/// matches.
+const PLACEHOLDERS: [(&str, &str); 3] = [
+    ("type", "[a-z]+"),
+    ("ticket", "[A-Z][A-Z0-9]*-[0-9]+"),
+    ("slug", "[a-z0-9]+(?:-[a-z0-9]+)*")
+];
+
+
+/// A rule every branch name has to keep.
+#[derive(Debug, Clone)]
+pub struct Rule {
+    pub name: String,
+    kind: RuleKind
+}
+
+#[derive(Debug, Clone)]
+enum RuleKind {
+    /// A regex the whole name, or the part of it the regex anchors, has to
+    /// match.
+    Pattern(Regex),
+    /// A structure such as `<type>/<ticket>-<slug>`, with the types allowed
+    /// when <type> is in it. No types allows any.
+    Structure { structure: String, regex: Regex, types: Vec<String> }
+}
+
+impl Rule {
+    /// A rule that names have to match `pattern`.
+    pub fn pattern(name: &str, pattern: &str) -> Result<Rule, regex::Error> {
+        Ok(Rule { name: name.to_string(), kind: RuleKind::Pattern(Regex::new(pattern)?) })
+    }
+
+    /// A rule that names are in the form of `structure`, where `<type>`,
+    /// `<ticket>` and `<slug>` stand for lower case words, tickets such as
+    /// `ABC-123` and lower case words joined by dashes, and anything else
+    /// is itself. Only `types` are allowed for `<type>`, unless it's empty.
+    pub fn structure(name: &str, structure: &str, types: &[String]) -> Result<Rule, String> {
+        let mut pattern = String::from("^");
+        let mut rest = structure;
+        while let Some(start) = rest.find('<') {
+            pattern.push_str(&regex::escape(&rest[..start]));
+            let end = rest[start..].find('>').ok_or_else(|| format!("{} has a < without a >", structure))? + start;
+            let placeholder = &rest[start + 1..end];
+            let matching = PLACEHOLDERS.iter().find(|(p, _)| *p == placeholder).map(|(_, m)| m).ok_or_else(|| {
+                format!("{} has <{}>, where only <type>, <ticket> and <slug> can be used", structure, placeholder)
+            })?;
+            pattern.push_str(&format!("(?P<{}>{})", placeholder, matching));
+            rest = &rest[end + 1..];
+        }
+        pattern.push_str(&regex::escape(rest));
+        pattern.push('$');
+
+        let regex = Regex::new(&pattern).map_err(|e| e.to_string())?;
+        Ok(Rule { name: name.to_string(), kind: RuleKind::Structure { structure: structure.to_string(), regex, types: types.to_vec() } })
+    }
+
+    /// Why `name` breaks the rule, or None when it keeps it.
+    pub fn check(&self, name: &str) -> Option<String> {
+        match &self.kind {
+            RuleKind::Pattern(regex) if regex.is_match(name) => None,
+            RuleKind::Pattern(regex) => Some(format!("doesn't match {}", regex)),
+            RuleKind::Structure { structure, regex, types } => match regex.captures(name) {
+                None => Some(format!("isn't in the form {}", structure)),
+                Some(captures) => captures
+                    .name("type")
+                    .map(|found| found.as_str())
+                    .filter(|found| !types.is_empty() && !types.iter().any(|t| t == found))
+                    .map(|found| format!("has type {}, which isn't one of {}", found, types.join(", ")))
+            }
+        }
+    }
+}
+
+
+/// A branch breaking a rule.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Violation<'a> {
+    pub branch: &'a str,
+    pub rule: &'a str,
+    pub reason: String
+}
+
+impl fmt::Display for Violation<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} breaks {}: it {}", self.branch, self.rule, self.reason)
+    }
+}
+
+
+/// Every rule each branch breaks, in the order of the branches and then the
+/// rules. Branches in `exempt` aren't checked.
+pub fn lint<'a>(branches: &[&'a BranchLine], rules: &'a [Rule], exempt: &ProtectedBranches) -> Vec<Violation<'a>> {
+    branches
+        .iter()
+        .filter(|b| !exempt.is_protected(&b.branch_name))
+        .flat_map(|b| rules.iter().filter_map(move |rule| {
+            rule.check(&b.branch_name).map(|reason| Violation { branch: &b.branch_name, rule: &rule.name, reason })
+        }))
+        .collect()
+}
+
+
+/// Whether there are more violations than `max_violations`, which makes
+/// lint exit with 1.
+pub fn fails(violations: &[Violation], max_violations: usize) -> bool {
+    violations.len() > max_violations
+}
+
+
+/// One violation a line.
+pub fn write_human<W: Write>(out: &mut W, violations: &[Violation]) -> io::Result<()> {
+    violations.iter().try_for_each(|v| writeln!(out, "{}", v))
+}
+
+#[cfg(feature = "serde")]
+pub fn write_json<W: Write>(out: &mut W, violations: &[Violation]) -> io::Result<()> {
+    serde_json::to_writer_pretty(&mut *out, violations)?;
+    writeln!(out)
+}
+
+/// How serious a GitHub Actions annotation is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Level {
+    Warning,
+    Error
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Level::Warning => write!(f, "warning"),
+            Level::Error => write!(f, "error")
+        }
+    }
+}
+
+/// One GitHub Actions workflow command a line, which the run shows as an
+/// annotation, e.g. `::error title=brune lint::feature/x breaks ...`.
+pub fn write_annotation<W: Write>(out: &mut W, level: Level, title: &str, message: &str) -> io::Result<()> {
+    writeln!(out, "::{} title={}::{}", level, escape_property(title), escape_data(message))
+}
+
+/// Annotations for the violations, as errors when `level` says so.
+pub fn write_github<W: Write>(out: &mut W, violations: &[Violation], level: Level) -> io::Result<()> {
+    violations.iter().try_for_each(|v| write_annotation(out, level, "brune lint", &v.to_string()))
+}
+
+/// The message of a workflow command, escaped as the runner expects.
+fn escape_data(text: &str) -> String {
+    text.replace('%', "%25").replace('\r', "%0D").replace('\n', "%0A")
+}
+
+/// A property of a workflow command, which can't have `:` or `,` either.
+fn escape_property(text: &str) -> String {
+    escape_data(text).replace(':', "%3A").replace(',', "%2C")
+}
+
+
+#[cfg(test)]
+fn branch(name: &str) -> BranchLine {
+    format!("  {} 0000aaaa [origin/{}] Blah", name, name).parse().unwrap()
+}
+
+/// 1. A branch can break several rules, reported in rule order
+/// 2. A structure checks the allowed types separately from the form
+/// 3. Branches keeping every rule aren't reported
+#[test]
+fn lint_multiple_rules() {
+    let types = vec!["feature".to_string(), "fix".to_string()];
+    let rules = vec![
+        Rule::structure("naming", "<type>/<ticket>-<slug>", &types).unwrap(),
+        Rule::pattern("short", "^.{1,24}$").unwrap()
+    ];
+    let branches = [branch("feature/ABC-12-login"), branch("wip/ABC-3-x"), branch("Feature/login-page-that-is-long"), branch("fix/ABC-9-logout")];
+    let refs: Vec<&BranchLine> = branches.iter().collect();
+
+    let violations = lint(&refs, &rules, &ProtectedBranches::new(Vec::<String>::new()));
+    let found: Vec<String> = violations.iter().map(Violation::to_string).collect();
+    assert_eq!(found, vec![
+        "wip/ABC-3-x breaks naming: it has type wip, which isn't one of feature, fix",
+        "Feature/login-page-that-is-long breaks naming: it isn't in the form <type>/<ticket>-<slug>",
+        "Feature/login-page-that-is-long breaks short: it doesn't match ^.{1,24}$"
+    ]);
+}
+
+/// 1. Protected branches aren't checked
+/// 2. Without types any type is allowed
+#[test]
+fn lint_exemptions() {
+    let rules = vec![Rule::structure("naming", "<type>/<slug>", &[]).unwrap()];
+    let branches = [branch("main"), branch("release"), branch("spike/dot-output")];
+    let refs: Vec<&BranchLine> = branches.iter().collect();
+
+    let mut exempt = ProtectedBranches::default();
+    assert_eq!(lint(&refs, &rules, &exempt).iter().map(|v| v.branch).collect::<Vec<_>>(), vec!["release"]);
+    exempt.protect("release");
+    assert!(lint(&refs, &rules, &exempt).is_empty());
+}
+
+/// 1. Unknown placeholders and unclosed ones are rejected
+/// 2. Literal parts of a structure aren't regexes
+#[test]
+fn lint_structures() {
+    assert!(Rule::structure("naming", "<type>/<name>", &[]).unwrap_err().contains("only <type>, <ticket> and <slug>"));
+    assert!(Rule::structure("naming", "<type/x", &[]).unwrap_err().contains("without a >"));
+
+    let dotted = Rule::structure("naming", "<type>.<slug>", &[]).unwrap();
+    assert_eq!(dotted.check("fix.login"), None);
+    assert!(dotted.check("fixxlogin").is_some());
+    assert!(Rule::pattern("bad", "(").is_err());
+}
+
+/// 1. Annotations escape what the runner would read as syntax
+#[test]
+fn lint_github_annotations() {
+    let violations = vec![Violation { branch: "wip", rule: "naming", reason: "isn't in the form <type>/<slug>".to_string() }];
+    let mut out = Vec::new();
+    write_github(&mut out, &violations, Level::Error).unwrap();
+    write_annotation(&mut out, Level::Warning, "a: b, c", "100%\ndone").unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), "::error title=brune lint::wip breaks naming: it isn't in the form <type>/<slug>\n::warning title=a%3A b%2C c::100%25%0Adone\n");
+}
+
+/// 1. No violations pass
+/// 2. Violations up to the threshold pass, and one more fails
+#[test]
+fn lint_exit() {
+    let rules = vec![Rule::pattern("lower", "^[a-z/-]+$").unwrap()];
+    let branches = [branch("feature/ok"), branch("WIP"), branch("Spike")];
+    let refs: Vec<&BranchLine> = branches.iter().collect();
+    let exempt = ProtectedBranches::default();
+
+    assert!(!fails(&lint(&refs[..1], &rules, &exempt), DEFAULT_MAX_VIOLATIONS));
+    let violations = lint(&refs, &rules, &exempt);
+    assert!(fails(&violations, DEFAULT_MAX_VIOLATIONS));
+    assert!(!fails(&violations, 2));
+    assert!(fails(&violations, 1));
+}
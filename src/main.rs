@@ -1,239 +1,1482 @@
-use nom::{IResult, bytes::complete::{tag, take_while}, combinator::{map, opt}, sequence::delimited};
+mod cli;
 
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io::{self, BufRead, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::OnceLock;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-#[derive(Debug,PartialEq)]
-struct HexValue(String);
+use clap::{Parser, ValueEnum};
+use tracing::Level;
 
-#[derive(Debug,PartialEq)]
-enum GitHubBranchType {
-    Active,
-    Deleted
+use brune::backup;
+use brune::complete;
+use brune::config::{self, Config};
+use brune::dates::{self, DateStyle};
+use brune::discover::Discovery;
+use brune::dot;
+use brune::error::BruneError;
+use brune::filter::{self, BranchFilter};
+use brune::for_each_ref;
+use brune::git::{self, Action};
+use brune::git_hook::{self, Hook, Reminder};
+use brune::hook;
+use brune::input_command::{self, InputCommand};
+use brune::lint;
+use brune::messages::Messages;
+use brune::output::{self, Palette};
+use brune::pager::Pager;
+use brune::policy;
+use brune::preprocess;
+use brune::prompt;
+use brune::protect::ProtectedBranches;
+use brune::reconcile::{self, RemoteHead};
+use brune::remote::{self, RemoteBranchLine};
+use brune::shield::Badge;
+use brune::show;
+use brune::sort::{self, SortKey};
+use brune::stream::{BranchLines, ParseError};
+use brune::stale::{self, StaleBranch};
+use brune::summary::Summary;
+use brune::template::{self, Template};
+use brune::watch;
+use brune::{parse_branch_lines_with, BranchLine, ParseMode, PrState};
+use cli::{BranchState, Cli, ColorChoice, Command, CompleteCommand, Format, HookName, InputFormat, OutputFormat, PolicyCommand, SortBy, GroupBy};
+
+
+/// The width and height of the terminal, or 80 by 24 when they can't be
+/// told.
+fn terminal_size() -> (usize, usize) {
+    terminal_size::terminal_size().map_or((80, 24), |(width, height)| (width.0 as usize, height.0 as usize))
 }
 
+fn badge(cli: &Cli) -> Badge {
+    Badge { label: cli.shield_label.clone(), max_gone: cli.max_gone }
+}
 
-#[derive(Debug,PartialEq)]
-struct GitHubBranchLine {
-    branch_name: String,
-    branch_type: GitHubBranchType,
-    comment: String
+/// The branches a shield badge counts as stale out of those listed, the
+/// same ones the exit status reports.
+fn stale_count<'a>(branches: impl IntoIterator<Item = &'a &'a BranchLine>) -> usize {
+    branches.into_iter().filter(|b| b.status.is_gone() || b.merged).count()
 }
 
+fn palette(choice: ColorChoice) -> Palette {
+    let enabled = match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none()
+    };
+    Palette::new(enabled)
+}
 
-fn is_alphabetic(c: char) -> bool {
-    c.is_alphabetic()
+/// How dates are shown by the human formats, as of `now`, and written
+/// exactly by the others.
+fn date_style(cli: &Cli, now: i64) -> DateStyle {
+    let relative_to = if cli.absolute_dates || cli.date_format.is_some() { None } else { Some(now) };
+    DateStyle { relative_to, format: cli.date_format.clone().unwrap_or_default() }
 }
 
-fn is_whitespace(c: char) -> bool {
-    c.is_whitespace()
+/// How the branch listing is printed.
+struct Printer {
+    format: OutputFormat,
+    palette: Palette,
+    dates: DateStyle,
+    porcelain: Option<bool>,
+    group_by: Option<GroupBy>,
+    current: bool,
+    /// Replaces the human output when given.
+    template: Option<Template>,
+    /// Set with --summary, to count the protected branches.
+    summary: Option<ProtectedBranches>,
+    /// The width of the terminal, to lay the human output out as a table
+    /// when writing to one.
+    table_width: Option<usize>,
+    /// Set when writing to a terminal, to page output that doesn't fit.
+    pager: Option<Pager>,
+    badge: Badge
 }
 
-fn is_hex_digit(c: char) -> bool {
-  c.is_digit(16)
+impl Printer {
+    fn print(&self, branches: &[&BranchLine]) -> io::Result<()> {
+        paged(self.pager.as_ref(), |mut out| {
+            let summary = match &self.summary {
+                Some(protected) => Summary::of(branches, protected),
+                None => return self.print_branches(&mut out, branches)
+            };
+
+            match self.format {
+                OutputFormat::Human => {
+                    self.print_branches(&mut out, branches)?;
+                    output::write_summary_human(&mut out, &summary, messages())
+                },
+                OutputFormat::Json => output::write_summary_json(&mut out, &summary),
+                OutputFormat::Ndjson => output::write_json_line(&mut out, &summary),
+                OutputFormat::Csv => output::write_summary_delimited(&mut out, &summary, b','),
+                OutputFormat::Tsv => output::write_summary_delimited(&mut out, &summary, b'\t'),
+                OutputFormat::Markdown => {
+                    self.print_branches(&mut out, branches)?;
+                    writeln!(out)?;
+                    output::write_summary_markdown(&mut out, &summary)
+                },
+                // Rejected along with --summary, as a graph has no room for
+                // totals and a badge is one already.
+                OutputFormat::Dot | OutputFormat::Shield => self.print_branches(&mut out, branches),
+            }
+        })
+    }
+
+    /// Stale branches with their reasons, or as plain branches when the
+    /// options ask for a layout that has no room for reasons.
+    fn print_stale(&self, stale: &[StaleBranch]) -> io::Result<()> {
+        if self.porcelain.is_some() || self.group_by.is_some() || self.summary.is_some() || self.current || self.template.is_some() {
+            return self.print(&stale.iter().map(|s| s.branch).collect::<Vec<_>>())
+        }
+
+        paged(self.pager.as_ref(), |mut out| match self.format {
+            OutputFormat::Human => match self.table_width {
+                Some(width) => output::write_stale_table(&mut out, stale, &self.palette, width),
+                None => output::write_stale_human(&mut out, stale, &self.palette)
+            },
+            OutputFormat::Json => output::write_json(&mut out, &stale.iter().collect::<Vec<_>>()),
+            OutputFormat::Ndjson => output::write_ndjson(&mut out, &stale.iter().collect::<Vec<_>>()),
+            OutputFormat::Csv => output::write_stale_delimited(&mut out, stale, b','),
+            OutputFormat::Tsv => output::write_stale_delimited(&mut out, stale, b'\t'),
+            OutputFormat::Markdown => output::write_stale_markdown(&mut out, stale, &self.dates),
+            OutputFormat::Dot => dot::write_dot(&mut out, &stale.iter().map(|s| s.branch).collect::<Vec<_>>()),
+            OutputFormat::Shield => self.badge.write(&mut out, stale.len()),
+        })
+    }
+
+    fn print_branches<W: Write>(&self, out: &mut W, branches: &[&BranchLine]) -> io::Result<()> {
+        if let Some(GroupBy::Prefix) = self.group_by {
+            return output::write_human_grouped(out, &sort::group_by_prefix(branches), &self.palette, &self.dates)
+        }
+
+        if let Some(nul_terminated) = self.porcelain {
+            return output::write_porcelain(out, branches, nul_terminated)
+        }
+
+        if let Some(template) = &self.template {
+            return template::write_templated(out, branches, template)
+        }
+
+        match self.format {
+            OutputFormat::Human if self.current => branches.iter().try_for_each(|b| output::write_current(out, b, &self.palette)),
+            OutputFormat::Human => match self.table_width {
+                Some(width) => output::write_human_table(out, branches, &self.palette, &self.dates, width),
+                None => output::write_human(out, branches, &self.palette, &self.dates)
+            },
+            OutputFormat::Json => output::write_json(out, branches),
+            OutputFormat::Ndjson => output::write_ndjson(out, branches),
+            OutputFormat::Csv => output::write_csv(out, branches, &self.dates.format),
+            OutputFormat::Tsv => output::write_tsv(out, branches, &self.dates.format),
+            OutputFormat::Markdown => output::write_markdown(out, branches, &self.dates),
+            OutputFormat::Dot => dot::write_dot(out, branches),
+            OutputFormat::Shield => self.badge.write(out, stale_count(branches)),
+        }
+    }
 }
 
-fn is_allowed_punctuation(c: char) -> bool {
-    c == '-' || c == '_' || c == '/'
+fn print_remote_branches(branches: &[&RemoteBranchLine], format: OutputFormat, badge: &Badge, pager: Option<&Pager>) -> io::Result<()> {
+    paged(pager, |mut out| match format {
+        OutputFormat::Human => output::write_remote_human(&mut out, branches),
+        OutputFormat::Json => output::write_json(&mut out, branches),
+        OutputFormat::Ndjson => output::write_ndjson(&mut out, branches),
+        OutputFormat::Csv => output::write_remote_delimited(&mut out, branches, b','),
+        OutputFormat::Tsv => output::write_remote_delimited(&mut out, branches, b'\t'),
+        OutputFormat::Markdown => output::write_remote_markdown(&mut out, branches),
+        OutputFormat::Dot => dot::write_remote_dot(&mut out, branches),
+        // Every remote branch without a local one is stale.
+        OutputFormat::Shield => badge.write(&mut out, branches.len()),
+    })
 }
 
-fn is_digit(c: char) -> bool {
-    c.is_digit(10)
+/// Runs `write` on stdout, or on a buffer that then goes through `pager`
+/// when there is one.
+fn paged(pager: Option<&Pager>, write: impl FnOnce(&mut dyn Write) -> io::Result<()>) -> io::Result<()> {
+    match pager {
+        Some(pager) => {
+            let mut buffer = Vec::new();
+            write(&mut buffer)?;
+            pager.page(&buffer)
+        },
+        None => write(&mut io::stdout().lock())
+    }
 }
 
+/// The branch's name, with when its last commit was made if that's known,
+/// for prune to ask about it.
+fn with_date(b: &BranchLine, dates: &DateStyle) -> String {
+    match b.last_commit_date {
+        Some(date) => text("prune.last_commit", &[("branch", &b.branch_name), ("date", &dates.render(date))]),
+        None => b.branch_name.clone()
+    }
+}
 
-fn take_tag<'a>(prefix: &'a str, input: &'a str) -> IResult<&'a str, &'a str> {
-    tag(prefix)(input)
+/// How prune checks with the user before deleting.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Confirmation {
+    /// Once for all branches.
+    Ask,
+    /// Separately for each branch.
+    EachBranch,
+    /// Not at all.
+    Yes
 }
 
+/// How prune goes about deleting branches.
+#[derive(Debug, Clone, Copy)]
+struct PruneOptions {
+    confirmation: Confirmation,
+    /// The time to back up deleted branches under, if they are to be.
+    backup: Option<i64>,
+    /// Also delete the upstream of each branch if it still exists.
+    push_delete: bool,
+    /// Delete branches that fail a [`Check`].
+    force: bool,
+    /// Delete branches that fail only [`Check::Protected`].
+    include_protected: bool,
+    dry_run: bool
+}
 
-fn take_whitespace<'a>(input: &'a str) -> IResult<&'a str, &'a str> {
-    take_while(is_whitespace)(input)
+/// A check that stops prune deleting a branch unless forced.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Check {
+    Protected,
+    /// Stashes were made on the branch, and would no longer say which branch they belong to.
+    Stashes(usize),
+    /// A gone branch has commits no remote or other local branch has.
+    Unpushed(usize)
 }
 
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Check::Protected => write!(f, "{}", text("check.protected", &[])),
+            Check::Stashes(n) => write!(f, "{}", text("check.stashes", &[("stashes", &messages().counted("stash", *n))])),
+            Check::Unpushed(n) => write!(f, "{}", text("check.unpushed", &[("commits", &messages().counted("commit", *n))]))
+        }
+    }
+}
 
-fn take_alphabetic<'a>(input: &'a str) -> IResult<&'a str, &'a str> {
-    take_while(is_alphabetic)(input)
+/// The checks, to be read out in one go.
+fn check_list(checks: &[Check]) -> String {
+    checks.iter().map(Check::to_string).reduce(|first, second| text("check.and", &[("first", &first), ("second", &second)])).unwrap_or_default()
 }
 
+/// Deletes `branches` after checking with the user. Each is first backed up
+/// when asked to be, then with `push_delete` its upstream is deleted too if
+/// it still exists.
+///
+/// Branches checked out here or in another worktree are always skipped,
+/// going by git itself as well as the listing, since the listing may be
+/// stale or from a git that doesn't mark them. So are those failing any
+/// [`Check`] unless forced, in which case the checks overridden are printed
+/// for each.
+fn prune(dir: &Path, branches: &[&BranchLine], protected: &ProtectedBranches, options: PruneOptions, dates: &DateStyle, show: &ShowOptions) -> Result<(), BruneError> {
+    let PruneOptions { confirmation, backup, push_delete, force, include_protected, dry_run } = options;
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+
+    // A listing read from --input-file may name branches this repository
+    // doesn't have, which there is nothing to check or delete for.
+    let (branches, missing): (Vec<&BranchLine>, Vec<&BranchLine>) = branches.iter().partition(|b| git::has_branch(dir, &b.branch_name));
+    for b in missing {
+        writeln!(out, "{}", text("prune.missing", &[("branch", &b.branch_name)]))?
+    }
+
+    let in_worktrees = git::worktree_branches(dir)?;
+    let stashes = git::stash_branches(dir)?;
+    let stashed = |b: &BranchLine| stashes.iter().filter(|s| **s == b.branch_name).count();
+    let unpushed: HashMap<&str, usize> =
+        branches
+            .iter()
+            .filter(|b| b.status.is_gone())
+            .map(|b| Ok((b.branch_name.as_str(), git::unpushed_commits(dir, &b.branch_name)?)))
+            .collect::<Result<_, BruneError>>()?;
+    let unpushed = |b: &BranchLine| unpushed.get(b.branch_name.as_str()).copied().unwrap_or_default();
+
+    let failed_checks = |b: &BranchLine| {
+        let checks = [
+            (protected.is_protected(&b.branch_name), Check::Protected),
+            (stashed(b) > 0, Check::Stashes(stashed(b))),
+            (unpushed(b) > 0, Check::Unpushed(unpushed(b)))
+        ];
+        checks.iter().filter(|(fails, _)| *fails).map(|(_, check)| *check).collect::<Vec<_>>()
+    };
+
+    let mut deleting = Vec::new();
+    for b in branches {
+        let checks = failed_checks(b);
+        if b.is_current {
+            writeln!(out, "{}", text("prune.current", &[("branch", &b.branch_name)]))?
+        } else if b.checked_out_in_worktree || in_worktrees.contains(&b.branch_name) {
+            writeln!(out, "{}", text("prune.worktree", &[("branch", &b.branch_name)]))?
+        } else if checks.is_empty() {
+            deleting.push(b)
+        } else if force || (include_protected && checks == [Check::Protected]) {
+            writeln!(out, "{}", text("prune.forcing", &[("branch", &b.branch_name), ("checks", &check_list(&checks))]))?;
+            deleting.push(b)
+        } else {
+            writeln!(out, "{}", text("prune.skipping", &[("branch", &b.branch_name), ("checks", &check_list(&checks))]))?
+        }
+    }
+    let branches = deleting;
+
+    if branches.is_empty() {
+        writeln!(out, "{}", text("prune.none", &[]))?;
+        return Ok(())
+    }
+
+    let needs_terminal = confirmation == Confirmation::EachBranch || (confirmation == Confirmation::Ask && !dry_run);
+    if needs_terminal && !io::stdin().is_terminal() {
+        return Err(BruneError::Usage(text("prune.needs_terminal", &[])))
+    }
 
-fn take_branch_name<'a>(input: &'a str) -> IResult<&'a str, &'a str> {
-    take_while(|c| is_alphabetic(c) || is_allowed_punctuation(c) || is_digit(c))(input)
+    let branches = match confirmation {
+        Confirmation::EachBranch =>
+            {
+            let preview = |b: &BranchLine| show_commits(dir, &b.branch_name, show, dates).map_err(io::Error::other);
+            prompt::choose_each(&branches, |b| text("prune.ask_each", &[("branch", &with_date(b, dates))]), Some(&preview), messages(), &mut io::stdin().lock(), &mut io::stdout())?
+        },
+        Confirmation::Ask if !dry_run => {
+            for b in &branches {
+                writeln!(out, "{}", with_date(b, dates))?
+            }
+
+            let question = text("prune.ask", &[("count", &branches.len())]);
+            if !prompt::confirm(&question, &mut io::stdin().lock(), &mut io::stdout())? {
+                writeln!(out, "{}", text("prune.nothing_deleted", &[]))?;
+                return Ok(())
+            }
+            branches
+        },
+        Confirmation::Ask | Confirmation::Yes => branches
+    };
+
+    let actions = |b: &BranchLine| {
+        let mut actions: Vec<Action> = backup.map(|time| backup::backup_action(time, &b.branch_name)).into_iter().collect();
+        match &b.upstream {
+            // A `.` remote is a local branch, and a gone one has nothing left to delete.
+            Some(upstream) if push_delete && upstream.remote != "." && !b.status.is_gone() =>
+                actions.push(Action::DeleteRemoteBranch { remote: upstream.remote.clone(), branch: upstream.branch.clone() }),
+            _ => {}
+        }
+        actions.push(Action::DeleteBranch(b.branch_name.clone()));
+        actions
+    };
+    let plan: Vec<(&BranchLine, Vec<Action>)> = branches.iter().map(|b| (*b, actions(b))).collect();
+
+    if dry_run {
+        for action in plan.iter().flat_map(|(_, actions)| actions) {
+            writeln!(out, "{}", text("dry_run.would_run", &[("action", action)]))?
+        }
+        return Ok(())
+    }
+
+    for (b, actions) in &plan {
+        for action in actions {
+            git::perform(dir, action)?
+        }
+        writeln!(out, "{}", text("prune.deleted", &[("branch", &b.branch_name)]))?
+    }
+
+    if let Some(time) = backup {
+        writeln!(out, "{}", text("prune.backed_up", &[("refs", &format!("{}/{}", backup::BACKUP_REFS, time))]))?
+    }
+
+    Ok(())
+}
+
+/// What show looks at, and how, for a branch.
+struct ShowOptions {
+    /// The branch whose commits aren't shown.
+    base: String,
+    max_commits: usize,
+    stat: bool,
+    pager: Option<Pager>
+}
+
+/// Writes the commits of `branch` that the base hasn't, through the pager
+/// when they don't fit.
+fn show_commits(dir: &Path, branch: &str, options: &ShowOptions, dates: &DateStyle) -> Result<(), BruneError> {
+    let unique = show::unique_commits(dir, branch, &options.base, options.max_commits, options.stat)?;
+    Ok(paged(options.pager.as_ref(), |mut out| show::write_human(&mut out, &unique, dates, messages()))?)
 }
 
-fn take_annotation<'a>(input: &'a str) -> IResult<&'a str, &'a str> {
-    delimited(
-        tag("["),
-        take_while(|c| is_alphabetic(c) || is_allowed_punctuation(c) || is_digit(c) || is_whitespace(c)),
-        tag("]")
-    )(input)
+/// Lists the backups, or restores the chosen ones: those of `names` or,
+/// when there are none, those made by the latest prune.
+fn undo(dir: &Path, list: bool, names: &[String], interactive: bool, format: OutputFormat, dry_run: bool) -> Result<(), BruneError> {
+    let backups = backup::list(dir)?;
+
+    if list {
+        let backups: Vec<&backup::Backup> = backups.iter().collect();
+        let stdout = io::stdout();
+        let mut out = stdout.lock();
+        return Ok(match format {
+            OutputFormat::Human => output::write_backups_human(&mut out, &backups),
+            OutputFormat::Json => output::write_json(&mut out, &backups),
+            OutputFormat::Ndjson => output::write_ndjson(&mut out, &backups),
+            OutputFormat::Csv => output::write_backups_delimited(&mut out, &backups, b','),
+            OutputFormat::Tsv => output::write_backups_delimited(&mut out, &backups, b'\t'),
+            OutputFormat::Markdown => output::write_backups_markdown(&mut out, &backups),
+            OutputFormat::Dot => return Err(BruneError::Usage(text("undo.no_dot", &[]))),
+            OutputFormat::Shield => return Err(BruneError::Usage(text("undo.no_shield", &[]))),
+        }?)
+    }
+
+    let chosen: Vec<&backup::Backup> = if names.is_empty() {
+        backup::latest(&backups)
+    } else {
+        names
+            .iter()
+            .map(|name| backups.iter().rev().find(|b| &b.branch == name).ok_or_else(|| BruneError::Usage(text("undo.no_backup", &[("branch", name)]))))
+            .collect::<Result<_, _>>()?
+    };
+
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    if chosen.is_empty() {
+        writeln!(out, "{}", text("undo.none", &[]))?;
+        return Ok(())
+    }
+
+    let chosen = if interactive {
+        if !io::stdin().is_terminal() {
+            return Err(BruneError::Usage(text("undo.needs_terminal", &[])))
+        }
+        prompt::choose_each(&chosen, |b| text("undo.ask_each", &[("branch", &b.branch), ("commit", &b.commit)]), None, messages(), &mut io::stdin().lock(), &mut io::stdout())?
+    } else {
+        chosen
+    };
+
+    for b in chosen {
+        let actions = b.restore_actions();
+        if dry_run {
+            for action in &actions {
+                writeln!(out, "{}", text("dry_run.would_run", &[("action", action)]))?
+            }
+            continue
+        }
+        for action in &actions {
+            git::perform(dir, action)?
+        }
+        writeln!(out, "{}", text("undo.restored", &[("branch", &b.branch), ("commit", &b.commit)]))?
+    }
+
+    Ok(())
 }
 
-// TODO: How can we write this in terms of other Parsers instead of creating a new one?
-fn take_whitespace_or_star<'a>(input: &'a str) -> IResult<&'a str, &'a str> {
-    take_while(|c| is_whitespace(c) || c == '*')(input)
+/// Shortens every commit to `len` digits. Commits git already shortened
+/// further can't be lengthened, so they are an error.
+fn abbreviate(branches: &mut [BranchLine], len: usize) -> Result<(), BruneError> {
+    for b in branches {
+        b.commit = b.commit.abbreviated(len).ok_or_else(|| {
+            BruneError::Usage(text("abbrev.too_short", &[("commit", &b.commit), ("branch", &b.branch_name), ("digits", &len)]))
+        })?
+    }
+    Ok(())
 }
 
-fn take_hex<'a>(input: &'a str) -> IResult<&'a str, HexValue> {
-    map(take_while(is_hex_digit), |hv: &str| HexValue(hv.to_string()))(input)
+/// The configured protected branches, plus `extra`, the checked out branch
+/// and the default branch.
+fn protected_branches(config: &Config, extra: Vec<String>, dir: &Path) -> ProtectedBranches {
+    let mut protected = config.protected.clone().map_or_else(ProtectedBranches::default, ProtectedBranches::new);
+    extra.into_iter().for_each(|name| protected.protect(name));
+    if let Some(current) = git::current_branch(dir).ok().flatten() {
+        protected.protect(current)
+    }
+    if let Some(default) = git::default_branch(dir, remote(config)) {
+        protected.protect(default)
+    }
+    protected
 }
 
-/// Possible variations:
-/// "[info]   FeatureA         dddeeee Random weird comments"
-/// "[info]   FeatureD         ffff1111 [Ahead 1] Random weird comments"
-/// "[info]   FeatureB         eeee3333 [behind 3] Random weird comments"
-/// "[info] * master           0000bbbb [behind 2] Random weird comments"
-/// "[info]   FeatureC         dddd3333 [gone] Random weird comments"
-/// "[info]   PERSON1/FeatureD eeee4444 [gone] Random weird comments"
-///
-fn git_line_parser<'a>(input: &'a str) -> IResult<&'a str, GitHubBranchLine> {
-    let (tail2, _)          = take_whitespace_or_star(input)?;
-    let (tail3, branch_n)   = take_branch_name(tail2)?;
-    let (tail4, _)          = take_whitespace(tail3)?;
-    let (tail5, _hex_value) = take_hex(tail4)?;
-    let (tail6, _)          = take_whitespace(tail5)?;
-    let (tail7, gone_op)    = opt(|i: &'a str| take_annotation(i))(tail6)?;
-    let (tail8, _)          = opt(|i: &'a str| take_whitespace(i))(tail7)?;
+/// The name of the command when it checks branches against rules, as lint
+/// and policy check do, with whether it prints annotations.
+fn checked_by(cli: &Cli) -> Option<(&'static str, bool)> {
+    match cli.command {
+        Some(Command::Lint { github, .. }) => Some(("lint", github)),
+        Some(Command::Policy { command: PolicyCommand::Check { github, .. } }) => Some(("policy check", github)),
+        _ => None
+    }
+}
 
-    let branch_type = match gone_op {
-        Some("gone") => GitHubBranchType::Deleted,
-        _ => GitHubBranchType::Active
+/// The naming rules from the config, of which lint needs at least one.
+fn lint_rules(config: &Config) -> Result<Vec<lint::Rule>, BruneError> {
+    let rules = config.lint.as_ref().map_or(&[][..], |lint| &lint.rules[..]);
+    if rules.is_empty() {
+        return Err(BruneError::Usage(text("lint.no_rules", &[])))
+    }
+    rules
+        .iter()
+        .map(|rule| {
+            let compiled = match (&rule.pattern, &rule.structure) {
+                (Some(_), None) if !rule.types.is_empty() => Err(text("lint.types_need_structure", &[])),
+                (Some(pattern), None) => lint::Rule::pattern(&rule.name, pattern).map_err(|e| e.to_string()),
+                (None, Some(structure)) => lint::Rule::structure(&rule.name, structure, &rule.types),
+                _ => Err(text("lint.pattern_or_structure", &[]))
+            };
+            compiled.map_err(|e| BruneError::Usage(text("lint.invalid_rule", &[("rule", &rule.name), ("reason", &e)])))
+        })
+        .collect()
+}
+
+/// The policy rules from the config, of which policy check needs at least
+/// one.
+fn policy_rules(config: &Config) -> Result<Vec<policy::Rule>, BruneError> {
+    let rules = config.policy.as_ref().map_or(&[][..], |policy| &policy.rules[..]);
+    if rules.is_empty() {
+        return Err(BruneError::Usage(text("policy.no_rules", &[])))
+    }
+    rules
+        .iter()
+        .enumerate()
+        .map(|(i, rule)| {
+            let check = match (&rule.max_age, rule.tracks_upstream, rule.max_gone, &rule.pattern) {
+                (Some(age), false, None, None) => filter::parse_duration(age).map(policy::Check::MaxAge),
+                (None, true, None, None) => Ok(policy::Check::TracksUpstream),
+                (None, false, Some(max), None) => Ok(policy::Check::MaxGone(max)),
+                (None, false, None, Some(pattern)) => regex::Regex::new(pattern).map(policy::Check::Pattern).map_err(|e| e.to_string()),
+                _ => Err(text("policy.one_check", &[]))
+            };
+            let check = check.map_err(|e| {
+                let name = rule.name.clone().unwrap_or_else(|| text("policy.rule_number", &[("n", &(i + 1))]));
+                BruneError::Usage(text("policy.invalid_rule", &[("rule", &name), ("reason", &e)]))
+            })?;
+            Ok(policy::Rule { name: rule.name.clone().unwrap_or_else(|| check.to_string()), severity: rule.severity, check })
+        })
+        .collect()
+}
+
+/// The branches lint and policy check pass over: the protected branches
+/// and the default branch, unless `include_protected`. Unlike for prune,
+/// the current branch is checked.
+fn exempt_branches(config: &Config, dir: &Path, include_protected: bool) -> ProtectedBranches {
+    if include_protected {
+        return ProtectedBranches::new(Vec::<String>::new())
+    }
+    let mut exempt = config.protected.clone().map_or_else(ProtectedBranches::default, ProtectedBranches::new);
+    if let Some(default) = git::default_branch(dir, remote(config)) {
+        exempt.protect(default)
+    }
+    exempt
+}
+
+/// The configured remote, origin by default.
+fn remote(config: &Config) -> &str {
+    config.remote.as_deref().unwrap_or("origin")
+}
+
+/// The branch to check merges against: `base` when given, otherwise the
+/// default branch, as the remote has it when there's no local one, and
+/// failing that HEAD.
+/// What prune shows of a branch when asked to: its latest commits that the
+/// default branch hasn't.
+fn prune_show(config: &Config, dir: &Path, pager: Option<Pager>) -> ShowOptions {
+    ShowOptions { base: merge_base(config, dir, None), max_commits: show::DEFAULT_MAX_COMMITS, stat: false, pager }
+}
+
+fn merge_base(config: &Config, dir: &Path, base: Option<&str>) -> String {
+    if let Some(base) = base {
+        return base.to_string()
+    }
+    match git::default_branch(dir, remote(config)) {
+        Some(default) if git::has_branch(dir, &default) => default,
+        Some(default) => format!("{}/{}", remote(config), default),
+        None => "HEAD".to_string()
+    }
+}
+
+#[cfg(feature = "provider")]
+fn add_pr_states(dir: &Path, config: &Config, branches: &mut [BranchLine]) -> Result<(), BruneError> {
+    let remote = remote(config);
+    let url = git::remote_url(dir, remote)?;
+    let provider = brune::provider::detect(&url)
+        .ok_or_else(|| BruneError::Usage(text("provider.unsupported", &[("remote", &remote), ("url", &url)])))?;
+    Ok(brune::provider::add_pr_states(provider.as_ref(), branches)?)
+}
+
+fn load_config(path: Option<&Path>) -> Result<Config, BruneError> {
+    match path.map(Path::to_path_buf).or_else(config::default_path) {
+        Some(path) => Config::load(&path),
+        None => Ok(Config::default())
+    }
+}
+
+fn parse_mode(cli: &Cli) -> ParseMode {
+    match (cli.strict, cli.scan) {
+        (true, _) => ParseMode::Strict,
+        (false, true) => ParseMode::Scan,
+        (false, false) => ParseMode::Lenient
+    }
+}
+
+/// The listing as it should be parsed.
+fn clean(input: String, keep_ansi: bool, prefixes: &[String]) -> String {
+    let input = if keep_ansi { Cow::Borrowed(input.as_str()) } else { preprocess::strip_ansi(&input) };
+    preprocess::strip_prefixes(&input, prefixes).into_owned()
+}
+
+/// The command given to `--input-command`, or to `--input-command-arg`
+/// one argument at a time.
+fn input_command(cli: &Cli) -> Option<InputCommand> {
+    match (&cli.input_command, cli.input_command_arg.is_empty()) {
+        (Some(line), _) => Some(InputCommand::Shell(line.clone())),
+        (None, false) => Some(InputCommand::Argv(cli.input_command_arg.clone())),
+        (None, true) => None
+    }
+}
+
+/// The stdout of the input command.
+fn run_input_command(cli: &Cli, command: &InputCommand) -> Result<String, BruneError> {
+    command.run(cli.input_command_timeout.unwrap_or(input_command::DEFAULT_TIMEOUT))
+}
+
+/// Whether an `--input-file` of `path` means stdin.
+fn is_stdin(path: &Path) -> bool {
+    path == Path::new("-")
+}
+
+/// The branches to work on, from the input when there is some and
+/// `from_git` isn't set, otherwise from git in `dir`.
+fn read_branches(cli: &Cli, dir: &Path, abbrev: Option<usize>, from_git: bool) -> Result<Vec<BranchLine>, BruneError> {
+    #[cfg(feature = "git2")]
+    if cli.libgit2 {
+        return Ok(brune::libgit2::branches(dir)?)
+    }
+
+    let input = match (cli.input_file.as_deref(), input_command(cli)) {
+        (_, Some(command)) => run_input_command(cli, &command)?,
+        (Some(path), None) if !is_stdin(path) => fs::read_to_string(path)?,
+        // --git-args only means anything to git, so it is run whatever stdin is.
+        (None, None) if from_git || !cli.git_args.is_empty() || io::stdin().is_terminal() => match cli.input {
+            InputFormat::BranchVv => git::branch_listing_with(dir, abbrev, &cli.git_args)?,
+            InputFormat::ForEachRef => git::for_each_ref_listing(dir)?
+        },
+        (Some(_) | None, None) => {
+            let mut buffer = String::new();
+            io::stdin().read_to_string(&mut buffer)?;
+            buffer
+        }
     };
 
-    let branch_name = branch_n.to_string();
-    let comment = tail8.clone().to_string();
+    let input = clean(input, cli.keep_ansi, &cli.strip_prefix);
+    let mode = parse_mode(cli);
 
-    // TODO: We don't need to return tail8 here as we are done.
-    let pair = (tail8 ,GitHubBranchLine { branch_name, branch_type, comment });
+    #[cfg(feature = "parallel")]
+    if let (Some(jobs), InputFormat::BranchVv, false) = (cli.jobs, cli.input, mode == ParseMode::Scan) {
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(usize::from(jobs)).build().map_err(io::Error::other)?;
+        return Ok(pool.install(|| brune::par_parse_branch_lines_with(&input, mode))?)
+    }
 
-    Ok(pair)
+    Ok(match cli.input {
+        InputFormat::BranchVv => parse_branch_lines_with(&input, mode)?,
+        InputFormat::ForEachRef => for_each_ref::parse_for_each_ref_lines_with(&input, mode)?
+    })
+}
 
+/// Whether prune should also delete branches whose pull request was merged.
+#[cfg(feature = "provider")]
+fn merged_prs(cli: &Cli) -> bool {
+    matches!(cli.command, Some(Command::Prune { merged_prs: true, .. }))
 }
 
-fn main() {
-    let git_line = "   PERSON1/FeatureD eeee4444 [gone] Random weird comments";
-    println!("parsing '{}'", git_line);
-    let (_, branch_line) = git_line_parser(git_line).unwrap();
-    println!("{:?}", branch_line)
+#[cfg(not(feature = "provider"))]
+fn merged_prs(_: &Cli) -> bool {
+    false
+}
+
+/// Reads the branches as [`read_branches`] does, then fills in what the
+/// command and options need from the repository at `dir`.
+#[cfg_attr(not(feature = "provider"), allow(unused_variables))]
+fn load_branches(cli: &Cli, config: &Config, dir: &Path, abbrev: Option<usize>, needs_dates: bool, from_git: bool) -> Result<Vec<BranchLine>, BruneError> {
+    let mut branches = read_branches(cli, dir, abbrev, from_git)?;
+    git::add_providers(dir, &mut branches);
+    if let Some(len) = abbrev {
+        abbreviate(&mut branches, len)?
+    }
+    if needs_dates {
+        git::add_commit_dates(dir, &mut branches)?
+    }
+    match &cli.command {
+        Some(Command::Merged { base } | Command::Stale { base } | Command::Scan { base, .. }) => git::mark_merged(dir, &merge_base(config, dir, base.as_deref()), &mut branches)?,
+        Some(Command::Report { .. } | Command::Prune { push_delete: true, .. }) => git::mark_merged(dir, &merge_base(config, dir, None), &mut branches)?,
+        // The listing may not come from this repository, so the merged count
+        // is best effort.
+        _ if cli.summary => {
+            if let Err(e) = git::mark_merged(dir, &merge_base(config, dir, None), &mut branches) {
+                tracing::warn!("not counting merged branches: {}", e)
+            }
+        },
+        _ => {}
+    }
+
+    #[cfg(feature = "provider")]
+    if cli.prs || merged_prs(cli) {
+        add_pr_states(dir, config, &mut branches)?
+    }
+    Ok(branches)
+}
+
+/// The branches the filter matches, in the order asked for.
+fn select<'a>(cli: &Cli, filter: &BranchFilter, branches: &'a [BranchLine]) -> Vec<&'a BranchLine> {
+    let mut matching = filter.apply(branches);
+    if cli.current {
+        matching.retain(|b| b.is_current)
+    }
+
+    if let Some(sort_by) = cli.sort {
+        let key = match sort_by {
+            SortBy::Name => SortKey::Name,
+            SortBy::Hash => SortKey::Hash,
+            SortBy::Status => SortKey::Status,
+            SortBy::Age => SortKey::Age
+        };
+        sort::sort_branches(&mut matching, key, cli.reverse)
+    }
+    matching
+}
+
+/// Notifies the desktop about `count` gone branches, `newly` gone or not.
+/// Not being able to isn't worth stopping for, as on a machine without a
+/// desktop, so it is only a warning.
+#[cfg(feature = "notify")]
+fn notify_prunable(count: usize, newly: bool) {
+    if let Some(message) = brune::notify::prunable_message(count, newly) {
+        if let Err(e) = brune::notify::send(&message) {
+            tracing::warn!("{}", e)
+        }
+    }
 }
 
-#[test]
-fn parse_git_line_remove_info() {
-    let git_line = "[info]abc";
-    let (r, m) = take_tag("[info]", git_line).unwrap();
-
-    assert_eq!(m, "[info]");
-    assert_eq!(r, "abc");
-}
-
-#[test]
-fn parse_git_line_remove_whitespace() {
-    let git_line = "   FeatureC  abcd";
-    let (r, m) = take_whitespace(git_line).unwrap();
-
-    assert_eq!(m, "   ");
-    assert_eq!(r, "FeatureC  abcd");
-}
-
-#[test]
-fn parse_git_line_take_alphabetics() {
-    let git_line = "FeatureC         dddd3333";
-    let (r, m) = take_alphabetic(git_line).unwrap();
-    assert_eq!(m, "FeatureC");
-    assert_eq!(r, "         dddd3333");
-}
-
-/// Branch name with dashes and slashes
-#[test]
-fn parse_git_line_take_branch_name() {
-    let git_line = "xyz/some-name-with-dashes         dddd3333";
-    let (r, m) = take_branch_name(git_line).unwrap();
-    assert_eq!(m, "xyz/some-name-with-dashes");
-    assert_eq!(r, "         dddd3333");
-}
-
-#[test]
-fn parse_git_line_take_branch_name_2() {
-    let git_line = "ID-9AB-blee-blah-2                              dddd3333 Blah de blah";
-    let (r, m) = take_branch_name(git_line).unwrap();
-    assert_eq!(m, "ID-9AB-blee-blah-2");
-    assert_eq!(r, "                              dddd3333 Blah de blah");
-}
-
-#[test]
-fn parse_git_line_take_hex() {
-    let git_line = "dddd3333G32H";
-    let (r, m) = take_hex(git_line).unwrap();
-    assert_eq!(m, HexValue("dddd3333".to_string()));
-    assert_eq!(r, "G32H");
-}
-
-
-/// 1. Alphabetic branch name
-/// 2. [gone] annotation
-#[test]
-fn parse_git_line() {
-    let git_line = "   FeatureC         dddd3333 [gone] Random weird comments";
-    let (r, m) = git_line_parser(git_line).unwrap();
-    let expected = GitHubBranchLine { branch_name: "FeatureC".to_string(), branch_type: GitHubBranchType::Deleted, comment: "Random weird comments".to_string() };
-    assert_eq!(m,  expected);
-    assert_eq!(r, "Random weird comments");
-}
-
-/// 1. hyphenated branch name
-/// 2. No annotation
-#[test]
-fn parse_git_line_2() {
-    let git_line = "   ID-9AB-blee-blah-2                              dddd3333 Blah de blah";
-    let (r, m) = git_line_parser(git_line).unwrap();
-    let expected = GitHubBranchLine { branch_name: "ID-9AB-blee-blah-2".to_string(), branch_type: GitHubBranchType::Active, comment: "Blah de blah".to_string() };
-    assert_eq!(m,  expected);
-    assert_eq!(r, "Blah de blah");
-}
-
-/// 1. Hyphenated branch name
-/// 2. Star (representing current branch)
-/// 3. No annotation
-#[test]
-fn parse_git_line_3() {
-    let git_line = " * ID-9AB-blee-blah-2                              dddd3333 Blah de blah";
-    let (r, m) = git_line_parser(git_line).unwrap();
-    let expected = GitHubBranchLine { branch_name: "ID-9AB-blee-blah-2".to_string(), branch_type: GitHubBranchType::Active, comment: "Blah de blah".to_string() };
-    assert_eq!(m,  expected);
-    assert_eq!(r, "Blah de blah");
-}
-
-/// 1. Alphabetic branch name
-/// 2. [behind 3] annotation
-#[test]
-fn parse_git_line_4() {
-    let git_line = "FeatureB         eeee3333 [behind 3] Random weird comments";
-    let (r, m) = git_line_parser(git_line).unwrap();
-    let expected = GitHubBranchLine { branch_name: "FeatureB".to_string(), branch_type: GitHubBranchType::Active, comment: "Random weird comments".to_string() };
-    assert_eq!(m,  expected);
-    assert_eq!(r, "Random weird comments");
-}
-
-
-/// 1. Hyphenated and slashed branch name
-/// 2. Star (representing current branch)
-/// 3. [ahead 1] annotation
-#[test]
-fn parse_git_line_5() {
-    let git_line = " * XYZ/ID-9AB-blee-blah-2                        dddd3333   [ahead 1]   Blah de blah";
-    let (r, m) = git_line_parser(git_line).unwrap();
-    let expected = GitHubBranchLine { branch_name: "XYZ/ID-9AB-blee-blah-2".to_string(), branch_type: GitHubBranchType::Active, comment: "Blah de blah".to_string() };
-    assert_eq!(m,  expected);
-    assert_eq!(r, "Blah de blah");
-}
-
-/// 1. Hyphenated and slashed branch name
-/// 2. Star (representing current branch)
-/// 3. [ahead 1] annotation
-/// 4. Emoji in comment
-#[test]
-fn parse_git_line_6() {
-    let git_line = " * XYZ/ID-9AB-blee-blah-2                        dddd3333   [ahead 1]   Blah 😃 blah";
-    let (r, m) = git_line_parser(git_line).unwrap();
-    let expected = GitHubBranchLine { branch_name: "XYZ/ID-9AB-blee-blah-2".to_string(), branch_type: GitHubBranchType::Active, comment: "Blah 😃 blah".to_string() };
-    assert_eq!(m,  expected);
-    assert_eq!(r, "Blah 😃 blah");
+/// Notifies the desktop that a run over `repos` repositories, of which
+/// those in `loaded` could be read, is done, when it took longer than
+/// `after`. A desktop that can't be reached only gets a debug line, as the
+/// run itself went fine and the result has been printed.
+#[cfg(feature = "notify")]
+fn notify_finished(cli: &Cli, filter: &BranchFilter, took: Duration, after: Duration, repos: usize, loaded: &[(String, Vec<BranchLine>)]) {
+    if took < after {
+        tracing::debug!("not notifying, the run took {:?}", took);
+        return
+    }
+    let gone = loaded.iter().map(|(_, branches)| select(cli, filter, branches).iter().filter(|b| b.status.is_gone()).count()).sum();
+    let message = brune::notify::repos_message(loaded.len(), gone, repos - loaded.len());
+    if let Err(e) = brune::notify::send(&message) {
+        tracing::debug!("couldn't notify the desktop: {}", e)
+    }
+}
+
+/// Looks at the branches from `load` every `interval`, printing what
+/// changed since the look before, until interrupted. A look that fails is
+/// skipped with a warning, as git may just be busy with the repository.
+fn watch(cli: &Cli, filter: &BranchFilter, format: OutputFormat, interval: Duration, load: impl Fn() -> Result<Vec<BranchLine>, BruneError>) -> Result<Outcome, BruneError> {
+    let mut before = load()?;
+    if format == OutputFormat::Human {
+        let watched = select(cli, filter, &before);
+        writeln!(io::stdout().lock(), "{}", text("watch.watching", &[("count", &watched.len()), ("gone", &watched.iter().filter(|b| b.status.is_gone()).count())]))?
+    }
+
+    loop {
+        thread::sleep(interval);
+        let after = match load() {
+            Ok(branches) => branches,
+            Err(e) => {
+                tracing::warn!("skipping a look: {}", e);
+                continue
+            }
+        };
+
+        let changes = watch::changes(&select(cli, filter, &before), &select(cli, filter, &after));
+        let stdout = io::stdout();
+        let mut out = stdout.lock();
+        for change in &changes {
+            match format {
+                OutputFormat::Ndjson => output::write_json_line(&mut out, change)?,
+                _ => writeln!(out, "{}", change)?
+            }
+        }
+        out.flush()?;
+
+        #[cfg(feature = "notify")]
+        notify_prunable(changes.iter().filter(|c| matches!(c, watch::Change::Gone { .. })).count(), true);
+        before = after
+    }
+}
+
+/// The branches of each repository, loaded from git as [`load_branches`]
+/// does. Repositories that can't be read are skipped with a warning.
+fn load_repos(cli: &Cli, config: &Config, repos: &[PathBuf], abbrev: Option<usize>, needs_dates: bool) -> Vec<(String, Vec<BranchLine>)> {
+    repos
+        .iter()
+        .filter_map(|repo| match load_branches(cli, config, repo, abbrev, needs_dates, true) {
+            Ok(branches) => Some((repo.display().to_string(), branches)),
+            Err(e) => {
+                tracing::warn!("skipping {}: {}", repo.display(), e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Lists, as list, gone or merged would, the branches of each repository
+/// in one report tagged with the repository they are in.
+fn report_repos(cli: &Cli, loaded: &[(String, Vec<BranchLine>)], format: OutputFormat, filter: &BranchFilter, now: i64) -> Result<Outcome, BruneError> {
+    let merged_only = matches!(cli.command, Some(Command::Merged { .. }));
+    let listed: Vec<(&str, Vec<&BranchLine>)> =
+        loaded
+            .iter()
+            .map(|(repo, branches)| {
+                let mut matching = select(cli, filter, branches);
+                if merged_only {
+                    matching.retain(|b| b.merged)
+                }
+                (repo.as_str(), matching)
+            })
+            .collect();
+
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let tagged = || listed.iter().flat_map(|(repo, branches)| branches.iter().map(move |branch| output::RepoBranch { repo, branch })).collect::<Vec<_>>();
+    match format {
+        OutputFormat::Human => output::write_human_grouped(&mut out, &listed, &palette(cli.color), &date_style(cli, now))?,
+        OutputFormat::Json => output::write_json(&mut out, &tagged().iter().collect::<Vec<_>>())?,
+        OutputFormat::Ndjson => output::write_ndjson(&mut out, &tagged().iter().collect::<Vec<_>>())?,
+        OutputFormat::Csv => output::write_repos_delimited(&mut out, &listed, b',', &date_style(cli, now).format)?,
+        OutputFormat::Tsv => output::write_repos_delimited(&mut out, &listed, b'\t', &date_style(cli, now).format)?,
+        OutputFormat::Markdown => output::write_repos_markdown(&mut out, &listed, &date_style(cli, now))?,
+        OutputFormat::Dot => dot::write_repos_dot(&mut out, &listed)?,
+        OutputFormat::Shield => badge(cli).write(&mut out, stale_count(listed.iter().flat_map(|(_, branches)| branches)))?,
+    }
+
+    let stale = listed.iter().flat_map(|(_, branches)| branches).any(|b| merged_only || b.status.is_gone());
+    Ok(if stale { Outcome::StaleFound } else { Outcome::Clean })
+}
+
+/// Lists the stale branches of each repository, as stale would in each.
+fn scan_repos(cli: &Cli, loaded: &[(String, Vec<BranchLine>)], format: OutputFormat, filter: &BranchFilter, age: Duration, now: i64) -> Result<Outcome, BruneError> {
+    let stale: Vec<(&str, Vec<StaleBranch>)> =
+        loaded.iter().map(|(repo, branches)| (repo.as_str(), stale::classify(&select(cli, filter, branches), now, age))).collect();
+
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let tagged = || stale.iter().flat_map(|(repo, stale)| stale.iter().map(move |branch| output::RepoBranch { repo, branch })).collect::<Vec<_>>();
+    match format {
+        OutputFormat::Human => output::write_repos_stale_human(&mut out, &stale, &palette(cli.color))?,
+        OutputFormat::Json => output::write_json(&mut out, &tagged().iter().collect::<Vec<_>>())?,
+        OutputFormat::Ndjson => output::write_ndjson(&mut out, &tagged().iter().collect::<Vec<_>>())?,
+        OutputFormat::Csv => output::write_repos_stale_delimited(&mut out, &stale, b',')?,
+        OutputFormat::Tsv => output::write_repos_stale_delimited(&mut out, &stale, b'\t')?,
+        OutputFormat::Markdown => output::write_repos_stale_markdown(&mut out, &stale, &date_style(cli, now))?,
+        OutputFormat::Dot => dot::write_repos_dot(&mut out, &stale.iter().map(|(repo, stale)| (*repo, stale.iter().map(|s| s.branch).collect())).collect::<Vec<_>>())?,
+        OutputFormat::Shield => badge(cli).write(&mut out, stale.iter().map(|(_, stale)| stale.len()).sum())?,
+    }
+
+    Ok(if stale.iter().any(|(_, stale)| !stale.is_empty()) { Outcome::StaleFound } else { Outcome::Clean })
+}
+
+/// Whether the branches can be written as they are parsed, because nothing
+/// asked for needs the whole listing first.
+fn can_stream(cli: &Cli, format: OutputFormat, older_than: Option<Duration>) -> bool {
+    #[cfg(feature = "git2")]
+    if cli.libgit2 {
+        return false
+    }
+    #[cfg(feature = "parallel")]
+    if cli.jobs.is_some() {
+        return false
+    }
+    #[cfg(feature = "provider")]
+    if cli.prs {
+        return false
+    }
+    #[cfg(feature = "notify")]
+    if cli.notify {
+        return false
+    }
+
+    format == OutputFormat::Ndjson &&
+        cli.input == InputFormat::BranchVv &&
+        matches!(cli.command, None | Some(Command::List | Command::Gone)) &&
+        cli.sort.is_none() && older_than.is_none() && !cli.dates && !cli.summary && !cli.count && cli.exec.is_none()
+}
+
+/// Writes each matching branch as a line of JSON as soon as it is parsed,
+/// for huge listings and pipelines that start work on the first branch.
+fn stream_ndjson(cli: &Cli, dir: &Path, abbrev: Option<usize>, filter: &BranchFilter) -> Result<Outcome, BruneError> {
+    let reader: Box<dyn BufRead> = match (cli.input_file.as_deref(), input_command(cli)) {
+        (_, Some(command)) => Box::new(io::Cursor::new(run_input_command(cli, &command)?)),
+        (Some(path), None) if !is_stdin(path) => Box::new(io::BufReader::new(fs::File::open(path)?)),
+        (None, None) if !cli.git_args.is_empty() || io::stdin().is_terminal() => Box::new(io::Cursor::new(git::branch_listing_with(dir, abbrev, &cli.git_args)?)),
+        (Some(_) | None, None) => Box::new(io::stdin().lock())
+    };
+
+    let (keep_ansi, prefixes) = (cli.keep_ansi, cli.strip_prefix.clone());
+    let mut lines = BranchLines::new(reader).with_clean(move |line| {
+        match if keep_ansi { Cow::Borrowed(line) } else { preprocess::strip_ansi(line) } {
+            Cow::Borrowed(line) => preprocess::strip_prefixes(line, &prefixes),
+            Cow::Owned(line) => Cow::Owned(preprocess::strip_prefixes(&line, &prefixes).into_owned())
+        }
+    });
+    if parse_mode(cli) == ParseMode::Scan {
+        lines = lines.scanning()
+    }
+
+    let mut providers = git::Providers::new(dir);
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let mut outcome = Outcome::Clean;
+    for line in lines {
+        let mut b = match line {
+            Ok(b) => b,
+            Err(e) if parse_mode(cli) != ParseMode::Strict && !matches!(e, ParseError::Io(_)) => {
+                if parse_mode(cli) == ParseMode::Lenient {
+                    tracing::warn!("skipping {}", e)
+                }
+                continue
+            },
+            Err(e) => return Err(e.into())
+        };
+        if !filter.accepts(&b) || (cli.current && !b.is_current) {
+            continue
+        }
+
+        providers.add(&mut b);
+        if let Some(len) = abbrev {
+            abbreviate(std::slice::from_mut(&mut b), len)?
+        }
+        if b.status.is_gone() {
+            outcome = Outcome::StaleFound
+        }
+        output::write_json_line(&mut out, &b)?
+    }
+    Ok(outcome)
+}
+
+/// What a successful run found, reported through the exit status so scripts
+/// don't have to parse the output.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Outcome {
+    /// Exit 0: nothing stale, or everything stale was dealt with.
+    Clean,
+    /// Exit 1: stale branches were listed.
+    StaleFound,
+    /// Exit 1: more branches broke the lint rules than are allowed.
+    Violations
+}
+
+/// Exit status for errors, including bad arguments as clap reports them.
+const ERROR_EXIT_CODE: i32 = 2;
+
+impl Outcome {
+    fn listed<T>(branches: &[T]) -> Outcome {
+        if branches.is_empty() { Outcome::Clean } else { Outcome::StaleFound }
+    }
+
+    /// StaleFound only when there are more branches than `allowed`.
+    fn listed_beyond<T>(branches: &[T], allowed: usize) -> Outcome {
+        if branches.len() > allowed { Outcome::StaleFound } else { Outcome::Clean }
+    }
+
+    fn exit_code(self) -> i32 {
+        match self {
+            Outcome::Clean => 0,
+            Outcome::StaleFound | Outcome::Violations => 1
+        }
+    }
+}
+
+/// The messages in the language of --lang or the locale, chosen once
+/// before brune says anything.
+static MESSAGES: OnceLock<Messages> = OnceLock::new();
+
+fn messages() -> &'static Messages {
+    MESSAGES.get_or_init(Messages::default)
+}
+
+/// The message for `key` in the chosen language, with `args` filled in.
+fn text(key: &str, args: &[(&str, &dyn fmt::Display)]) -> String {
+    messages().render(key, args)
+}
+
+/// What went wrong, in the chosen language where brune words it itself.
+/// Errors from the system, such as I/O, and those already worded through
+/// the messages come as they are.
+fn explain(e: &BruneError) -> String {
+    match e {
+        BruneError::Git { args, stderr } => text("error.git", &[("args", args), ("stderr", stderr)]),
+        BruneError::Config { path, source } => text("error.config", &[("path", &path.display()), ("reason", source)]),
+        BruneError::Hook { command, branch, status } => text("error.exec", &[("command", command), ("branch", branch), ("status", status)]),
+        BruneError::InputCommand { command, status, stderr } => text("error.input_command", &[("command", command), ("status", status), ("stderr", stderr)]),
+        BruneError::InputCommandTimeout { command, timeout } => text("error.input_command_timeout", &[("command", command), ("timeout", &format!("{:?}", timeout))]),
+        #[cfg(feature = "notify")]
+        BruneError::Notify(reason) => text("error.notify", &[("reason", reason)]),
+        _ => e.to_string()
+    }
+}
+
+/// The name `format` is given by on the command line.
+fn format_name(format: OutputFormat) -> String {
+    format.to_possible_value().map_or(String::new(), |v| v.get_name().to_owned())
+}
+
+fn git_hook_for(hook: HookName) -> Hook {
+    match hook {
+        HookName::PostCheckout => Hook::PostCheckout,
+        HookName::PrePush => Hook::PrePush
+    }
+}
+
+fn run(cli: Cli) -> Result<Outcome, BruneError> {
+    #[cfg(feature = "notify")]
+    let started = std::time::Instant::now();
+    if let Some(Command::Completions { shell }) = cli.command {
+        // Generated into a buffer first, as clap_complete panics on write errors.
+        io::stdout().write_all(cli::completion_script(shell).as_bytes())?;
+        return Ok(Outcome::Clean)
+    }
+
+    // Run by the completion scripts on every tab, so it reads no config and
+    // looks nothing up.
+    if let Some(Command::Complete { names }) = &cli.command {
+        let dir = Path::new(".");
+        let mut out = io::stdout().lock();
+        match names {
+            CompleteCommand::Branches { status } => {
+                let status = status.map(|status| match status {
+                    BranchState::Gone => complete::Status::Gone,
+                    BranchState::Active => complete::Status::Active
+                });
+                complete::write_names(&mut out, &complete::branch_names(&complete::local_branches(dir)?, status))?
+            },
+            CompleteCommand::Backups => complete::write_names(&mut out, &complete::backup_names(&backup::list(dir)?))?
+        }
+        return Ok(Outcome::Clean)
+    }
+
+    // Writes the hook alone, so what it runs is rendered from the arguments
+    // here rather than the config.
+    if let Some(Command::InstallHook { hook, blocking }) = &cli.command {
+        let reminder = Reminder { max_gone: cli.max_gone, blocking: *blocking };
+        let path = git_hook::install(Path::new("."), git_hook_for(*hook), &reminder)?;
+        writeln!(io::stdout().lock(), "{}", text("hook.added", &[("path", &path.display())]))?;
+        return Ok(Outcome::Clean)
+    }
+
+    if let Some(Command::UninstallHook { hook }) = &cli.command {
+        let hook = git_hook_for(*hook);
+        let mut out = io::stdout().lock();
+        match git_hook::uninstall(Path::new("."), hook)? {
+            Some(path) => writeln!(out, "{}", text("hook.removed", &[("path", &path.display())]))?,
+            None => writeln!(out, "{}", text("hook.absent", &[("hook", &hook)]))?
+        }
+        return Ok(Outcome::Clean)
+    }
+
+    let dir = Path::new(".");
+    let config = load_config(cli.config.as_deref())?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map_err(io::Error::other)?.as_secs() as i64;
+
+    let format = match (cli.format.clone(), &config.format) {
+        (Some(format), _) => format,
+        (None, Some(name)) => cli::parse_format(name)
+            .map_err(|e| BruneError::Usage(text("config.invalid_format", &[("reason", &e)])))?,
+        (None, None) => Format::Builtin(OutputFormat::Human)
+    };
+    let (format, template) = match format {
+        Format::Builtin(format) => (format, None),
+        Format::Template(template) => (OutputFormat::Human, Some(template))
+    };
+
+    if template.is_some() && (cli.group_by.is_some() || matches!(cli.command, Some(Command::Remote { .. } | Command::Undo { .. }))) {
+        return Err(BruneError::Usage(text("usage.template", &[])))
+    }
+
+    if cli.group_by.is_some() && format != OutputFormat::Human {
+        return Err(BruneError::Usage(text("usage.group_by", &[("format", &format_name(format))])))
+    }
+
+    if cli.exec.is_some() && !matches!(cli.command, None | Some(Command::List | Command::Gone | Command::Merged { .. } | Command::Stale { .. })) {
+        return Err(BruneError::Usage(text("usage.exec", &[])))
+    }
+
+    if cli.count && !matches!(cli.command, Some(Command::Gone | Command::Merged { .. } | Command::Stale { .. })) {
+        return Err(BruneError::Usage(text("usage.count", &[])))
+    }
+
+    if !cli.git_args.is_empty() && cli.input == InputFormat::ForEachRef {
+        return Err(BruneError::Usage(text("usage.git_args", &[])))
+    }
+
+    if cli.summary && matches!(format, OutputFormat::Dot | OutputFormat::Shield) {
+        return Err(BruneError::Usage(text("usage.summary", &[("format", &format_name(format))])))
+    }
+
+    #[cfg(feature = "notify")]
+    if cli.notify && !matches!(cli.command, None | Some(Command::List | Command::Gone | Command::Watch { .. } | Command::Scan { .. })) {
+        return Err(BruneError::Usage(text("usage.notify", &[])))
+    }
+
+    if let Some((name, github)) = checked_by(&cli) {
+        if template.is_some() || cli.summary || cli.porcelain || cli.group_by.is_some() || cli.current {
+            return Err(BruneError::Usage(text("usage.checks", &[("command", &name)])))
+        }
+        if !matches!(format, OutputFormat::Human | OutputFormat::Json) || (github && format != OutputFormat::Human) {
+            return Err(BruneError::Usage(text("usage.checks_format", &[("command", &name)])))
+        }
+    }
+
+    // Looks at the one branch, without a listing.
+    if let Some(Command::Show { branch, stat, max_commits, base }) = &cli.command {
+        if template.is_some() || cli.summary || cli.porcelain || cli.exec.is_some() || cli.group_by.is_some() || cli.current || !cli.repo.is_empty() {
+            return Err(BruneError::Usage(text("usage.show", &[])))
+        }
+        if format != OutputFormat::Human {
+            return Err(BruneError::Usage(text("usage.show_format", &[])))
+        }
+        if !git::has_branch(dir, branch) {
+            return Err(BruneError::Usage(text("show.no_branch", &[("branch", branch)])))
+        }
+        let pager = if io::stdout().is_terminal() && !cli.no_pager { Pager::from_env(terminal_size().1) } else { None };
+        let options = ShowOptions { base: merge_base(&config, dir, base.as_deref()), max_commits: *max_commits, stat: *stat, pager };
+        show_commits(dir, branch, &options, &date_style(&cli, now))?;
+        return Ok(Outcome::Clean)
+    }
+
+    // Works from the backups alone, without a listing.
+    if let Some(Command::Undo { list, interactive, branches }) = &cli.command {
+        undo(dir, *list, branches, *interactive, format, cli.dry_run)?;
+        return Ok(Outcome::Clean)
+    }
+
+    // Works on the line given alone, without a listing.
+    if let Some(Command::Parse { trace, line }) = &cli.command {
+        if *trace {
+            writeln!(io::stdout().lock(), "{}", brune::trace::trace(line))?
+        }
+        let branch: BranchLine = line.parse()?;
+        if !*trace {
+            let printer = Printer {
+                format,
+                palette: palette(cli.color),
+                dates: date_style(&cli, now),
+                porcelain: if cli.porcelain { Some(cli.nul_terminated) } else { None },
+                group_by: None,
+                current: false,
+                template,
+                summary: None,
+                table_width: None,
+                pager: None,
+                badge: badge(&cli)
+            };
+            printer.print(&[&branch])?
+        }
+        return Ok(Outcome::Clean)
+    }
+
+    #[cfg(feature = "notify")]
+    let notify_after = match (cli.notify, cli.notify_after, &config.notify_after) {
+        (false, _, _) => None,
+        (true, Some(after), _) => Some(after),
+        (true, None, Some(after)) => Some(watch::parse_interval(after).map_err(|e| BruneError::Usage(text("config.invalid_notify_after", &[("reason", &e)])))?),
+        (true, None, None) => Some(brune::notify::DEFAULT_NOTIFY_AFTER)
+    };
+
+    let mode = parse_mode(&cli);
+    let older_than = match (cli.older_than, &config.older_than) {
+        (Some(age), _) => Some(age),
+        (None, Some(age)) => Some(filter::parse_duration(age).map_err(|e| BruneError::Usage(text("config.invalid_older_than", &[("reason", &e)])))?),
+        (None, None) => None
+    };
+
+    let mut filter =
+        BranchFilter::new()
+            .with_include(&cli.include)
+            .and_then(|filter| filter.with_exclude(&cli.exclude))?;
+
+    if let Some(pattern) = cli.filter.clone() {
+        filter = filter.with_regex(pattern)
+    }
+
+    if cli.gone_only || matches!(cli.command, Some(Command::Gone)) {
+        filter = filter.with_gone_only()
+    }
+
+    let abbrev = cli.abbrev.map(usize::from).or(config.abbrev);
+    let listing_stale = matches!(cli.command, Some(Command::Stale { .. } | Command::Scan { .. } | Command::Report { .. }));
+
+    // Stale lists old branches among others, rather than only old ones.
+    if let (Some(age), false) = (older_than, listing_stale) {
+        filter = filter.with_older_than(now, age)
+    }
+
+    if let Some(Command::Scan { dir: root, max_depth, ignore, .. }) = &cli.command {
+        if template.is_some() || cli.summary || cli.porcelain || cli.exec.is_some() || cli.group_by.is_some() || cli.current || cli.input_file.is_some() || input_command(&cli).is_some() || !cli.repo.is_empty() {
+            return Err(BruneError::Usage(text("usage.scan", &[])))
+        }
+        let discovery = Discovery { max_depth: *max_depth, ignore: filter::glob_set(ignore)? };
+        let found = discovery.find_repos(root)?;
+        let loaded = load_repos(&cli, &config, &found, abbrev, true);
+        let outcome = scan_repos(&cli, &loaded, format, &filter, older_than.unwrap_or(stale::DEFAULT_STALE_AGE), now)?;
+        #[cfg(feature = "notify")]
+        if let Some(after) = notify_after {
+            notify_finished(&cli, &filter, started.elapsed(), after, found.len(), &loaded)
+        }
+        return Ok(outcome)
+    }
+
+    // Lines up two listings, or the repository with its remote, on its own.
+    if let Some(Command::Reconcile { local_file, remote_file, check_remote, remote: name }) = &cli.command {
+        if template.is_some() || cli.summary || cli.porcelain || cli.exec.is_some() || cli.group_by.is_some() || cli.current || !cli.repo.is_empty() {
+            return Err(BruneError::Usage(text("usage.reconcile", &[])))
+        }
+        if !matches!(format, OutputFormat::Human | OutputFormat::Json) {
+            return Err(BruneError::Usage(text("usage.reconcile_format", &[])))
+        }
+        let name = name.as_deref().unwrap_or_else(|| remote(&config));
+        let branches = match local_file {
+            Some(path) => parse_branch_lines_with(&clean(fs::read_to_string(path)?, cli.keep_ansi, &cli.strip_prefix), mode)?,
+            None => load_branches(&cli, &config, dir, abbrev, false, false)?
+        };
+        let remote_input = match (remote_file, check_remote) {
+            (Some(path), _) => fs::read_to_string(path)?,
+            (None, true) => git::ls_remote_heads(dir, name)?,
+            (None, false) => git::remote_branch_listing(dir)?
+        };
+        let remotes: Vec<RemoteHead> =
+            reconcile::parse_remote_heads_with(&clean(remote_input, cli.keep_ansi, &cli.strip_prefix), name, mode)?.into_iter().filter(|r| filter.matches(&r.name)).collect();
+        let reconciliation = reconcile::reconcile(&select(&cli, &filter, &branches), &remotes, name);
+        let mut out = io::stdout().lock();
+        match format {
+            OutputFormat::Json => reconcile::write_json(&mut out, &reconciliation)?,
+            _ => reconcile::write_human(&mut out, &reconciliation)?
+        }
+        return Ok(if reconciliation.in_sync() { Outcome::Clean } else { Outcome::StaleFound })
+    }
+
+    let needs_dates = cli.dates || matches!(cli.command, Some(Command::Policy { .. })) || cli.sort == Some(SortBy::Age) || older_than.is_some() || listing_stale || template.as_ref().is_some_and(Template::uses_dates);
+
+    if matches!(cli.command, Some(Command::Report { .. })) && (template.is_some() || cli.porcelain || cli.group_by.is_some() || cli.summary) {
+        return Err(BruneError::Usage(text("usage.report", &[])))
+    }
+
+    if let Some(Command::Watch { interval }) = &cli.command {
+        if template.is_some() || cli.summary || cli.porcelain || cli.exec.is_some() || cli.group_by.is_some() || cli.input_file.is_some() || !cli.repo.is_empty() {
+            return Err(BruneError::Usage(text("usage.watch", &[])))
+        }
+        if !matches!(format, OutputFormat::Human | OutputFormat::Ndjson) {
+            return Err(BruneError::Usage(text("usage.watch_format", &[])))
+        }
+        return watch(&cli, &filter, format, *interval, || load_branches(&cli, &config, dir, abbrev, needs_dates, true))
+    }
+    let lists = matches!(cli.command, None | Some(Command::List | Command::Gone | Command::Merged { .. }));
+    let repos: Vec<PathBuf> = match &config.repos {
+        _ if !cli.repo.is_empty() => cli.repo.clone(),
+        Some(repos) if lists && cli.input_file.is_none() && input_command(&cli).is_none() => repos.iter().map(|repo| config::expand_home(repo)).collect(),
+        _ => Vec::new()
+    };
+    if !repos.is_empty() {
+        // Most of these are turned down by clap for --repo, but not for repos from the config.
+        if !lists || template.is_some() || cli.summary || cli.porcelain || cli.exec.is_some() || cli.group_by.is_some() || cli.current || cli.count {
+            return Err(BruneError::Usage(text("usage.repos", &[])))
+        }
+        let loaded = load_repos(&cli, &config, &repos, abbrev, needs_dates);
+        let outcome = report_repos(&cli, &loaded, format, &filter, now)?;
+        #[cfg(feature = "notify")]
+        if let Some(after) = notify_after {
+            notify_finished(&cli, &filter, started.elapsed(), after, repos.len(), &loaded)
+        }
+        return Ok(outcome)
+    }
+
+    if can_stream(&cli, format, older_than) {
+        return stream_ndjson(&cli, dir, abbrev, &filter)
+    }
+
+    let branches = load_branches(&cli, &config, dir, abbrev, needs_dates, false)?;
+    let merged_prs = merged_prs(&cli);
+    let matching = select(&cli, &filter, &branches);
+
+    let printer = Printer {
+        format,
+        palette: palette(cli.color),
+        dates: date_style(&cli, now),
+        porcelain: if cli.porcelain { Some(cli.nul_terminated) } else { None },
+        group_by: cli.group_by,
+        current: cli.current,
+        template,
+        summary: if cli.summary { Some(protected_branches(&config, vec![], dir)) } else { None },
+        table_width: io::stdout().is_terminal().then(|| terminal_size().0),
+        pager: if io::stdout().is_terminal() && !cli.no_pager { Pager::from_env(terminal_size().1) } else { None },
+        badge: badge(&cli)
+    };
+
+    let exec = cli.exec.as_deref();
+    let count = cli.count;
+    let show = |branches: &[&BranchLine]| match exec {
+        Some(command) => hook::run_for_each(command, branches),
+        None if count => Ok(writeln!(io::stdout().lock(), "{}", branches.len())?),
+        None => Ok(printer.print(branches)?)
+    };
+    // With --count, listing a few is fine.
+    let allowed = if count { cli.max_gone } else { 0 };
+
+    let gone: Vec<&BranchLine> = matching.iter().copied().filter(|b| b.status.is_gone()).collect();
+
+    #[cfg(feature = "notify")]
+    if cli.notify {
+        notify_prunable(gone.len(), false)
+    }
+
+    let outcome = match cli.command.unwrap_or(Command::List) {
+        Command::List => {
+            show(&matching)?;
+            Outcome::listed(&gone)
+        },
+        Command::Gone => {
+            show(&gone)?;
+            Outcome::listed_beyond(&gone, allowed)
+        },
+        Command::Merged { .. } => {
+            let merged: Vec<&BranchLine> = matching.iter().copied().filter(|b| b.merged).collect();
+            show(&merged)?;
+            Outcome::listed_beyond(&merged, allowed)
+        },
+        Command::Stale { .. } => {
+            let stale = stale::classify(&matching, now, older_than.unwrap_or(stale::DEFAULT_STALE_AGE));
+            match exec {
+                Some(_) => show(&stale.iter().map(|s| s.branch).collect::<Vec<_>>())?,
+                None if count => writeln!(io::stdout().lock(), "{}", stale.len())?,
+                None => printer.print_stale(&stale)?
+            }
+            Outcome::listed_beyond(&stale, allowed)
+        },
+        Command::Report { html } => {
+            let age = older_than.unwrap_or(stale::DEFAULT_STALE_AGE);
+            let stale = stale::classify(&matching, now, age);
+            let summary = Summary::of(&matching, &protected_branches(&config, vec![], dir));
+            let name = fs::canonicalize(dir).ok().and_then(|dir| dir.file_name().map(|name| name.to_string_lossy().into_owned()));
+            let title = name.map_or_else(|| "Branches".to_string(), |name| format!("Branches of {}", name));
+
+            let mut out = io::BufWriter::new(fs::File::create(&html)?);
+            brune::report::write_html(&mut out, &title, &matching, &summary, &stale, now, age, &printer.dates)?;
+            out.flush()?;
+            writeln!(io::stdout().lock(), "{}", text("report.wrote", &[("count", &matching.len()), ("path", &html.display())]))?;
+            Outcome::listed(&gone)
+        },
+        Command::Lint { max_violations, include_protected, github } => {
+            let rules = lint_rules(&config)?;
+            let lint_config = config.lint.as_ref();
+            let exempt = exempt_branches(&config, dir, include_protected || lint_config.is_some_and(|lint| lint.include_protected));
+            let max_violations = max_violations.or(lint_config.and_then(|lint| lint.max_violations)).unwrap_or(lint::DEFAULT_MAX_VIOLATIONS);
+
+            let violations = lint::lint(&matching, &rules, &exempt);
+            let fails = lint::fails(&violations, max_violations);
+            paged(printer.pager.as_ref(), |mut out| match (github, format) {
+                (true, _) => lint::write_github(&mut out, &violations, if fails { lint::Level::Error } else { lint::Level::Warning }),
+                (false, OutputFormat::Json) => lint::write_json(&mut out, &violations),
+                (false, _) => lint::write_human(&mut out, &violations)
+            })?;
+            if fails { Outcome::Violations } else { Outcome::Clean }
+        },
+        Command::Policy { command: PolicyCommand::Check { include_protected, github } } => {
+            let rules = policy_rules(&config)?;
+            let exempt = exempt_branches(&config, dir, include_protected || config.policy.as_ref().is_some_and(|policy| policy.include_protected));
+
+            let reports = policy::evaluate(&matching, &rules, &exempt, now);
+            paged(printer.pager.as_ref(), |mut out| match (github, format) {
+                (true, _) => policy::write_github(&mut out, &reports),
+                (false, OutputFormat::Json) => policy::write_json(&mut out, &reports),
+                (false, _) => policy::write_human(&mut out, &reports)
+            })?;
+            if policy::fails(&reports) { Outcome::Violations } else { Outcome::Clean }
+        },
+        Command::Remote { remote_file } => {
+            let remote_input = match remote_file {
+                Some(path) => fs::read_to_string(path)?,
+                None => git::remote_branch_listing(dir)?
+            };
+            let remotes = remote::parse_remote_branch_lines_with(&clean(remote_input, cli.keep_ansi, &cli.strip_prefix), mode)?;
+            let remote_only: Vec<&RemoteBranchLine> =
+                remote::remote_only(&remotes, &branches).into_iter().filter(|r| filter.matches(&r.branch_name)).collect();
+            print_remote_branches(&remote_only, format, &printer.badge, printer.pager.as_ref())?;
+            Outcome::listed(&remote_only)
+        },
+        #[cfg(feature = "tui")]
+        Command::Ui { protect } => {
+            let protected = protected_branches(&config, protect, dir);
+            let show = prune_show(&config, dir, printer.pager.clone());
+            let preview = |b: &BranchLine| show_commits(dir, &b.branch_name, &show, &printer.dates).map_err(io::Error::other);
+            if let brune::tui::Outcome::Delete(names) = brune::tui::run(&matching, &preview)? {
+                let selected: Vec<&BranchLine> = matching.iter().copied().filter(|b| names.contains(&b.branch_name)).collect();
+                let options = PruneOptions { confirmation: Confirmation::Yes, backup: Some(now), push_delete: false, force: false, include_protected: false, dry_run: cli.dry_run };
+                prune(dir, &selected, &protected, options, &printer.dates, &show)?
+            }
+            Outcome::Clean
+        },
+        // Dealt with before reading any branches.
+        Command::Completions { .. } | Command::Complete { .. } | Command::InstallHook { .. } | Command::UninstallHook { .. } | Command::Show { .. } | Command::Undo { .. } | Command::Parse { .. } | Command::Scan { .. } | Command::Reconcile { .. } | Command::Watch { .. } => Outcome::Clean,
+        Command::Prune { yes, interactive, protect, include_protected, push_delete, no_backup, force, .. } => {
+            let protected = protected_branches(&config, protect, dir);
+            let confirmation = match (yes, interactive) {
+                (true, _) => Confirmation::Yes,
+                (false, true) => Confirmation::EachBranch,
+                (false, false) => Confirmation::Ask
+            };
+            let candidates: Vec<&BranchLine> =
+                matching
+                    .iter()
+                    .copied()
+                    .filter(|b| b.status.is_gone() || (merged_prs && b.pr_state == Some(PrState::Merged)) || (push_delete && b.merged))
+                    .collect();
+            let backup = if no_backup { None } else { Some(now) };
+            let options = PruneOptions { confirmation, backup, push_delete, force, include_protected, dry_run: cli.dry_run };
+            prune(dir, &candidates, &protected, options, &printer.dates, &prune_show(&config, dir, printer.pager.clone()))?;
+            Outcome::Clean
+        }
+    };
+
+    Ok(outcome)
+}
+
+/// Logs go to stderr so they never mix with the listing.
+fn init_logging(verbose: u8, quiet: bool) {
+    let level = match (quiet, verbose) {
+        (true, _) => Level::ERROR,
+        (false, 0) => Level::WARN,
+        (false, 1) => Level::INFO,
+        (false, 2) => Level::DEBUG,
+        (false, _) => Level::TRACE
+    };
+    tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_writer(io::stderr)
+        .with_ansi(io::stderr().is_terminal() && env::var_os("NO_COLOR").is_none())
+        .without_time()
+        .with_target(false)
+        .init()
+}
+
+fn main() {
+    let cli = Cli::parse();
+    init_logging(cli.verbose, cli.quiet);
+    MESSAGES.get_or_init(|| Messages::from_env(cli.lang.as_deref(), |name| env::var(name).ok()));
+    // Dates in JSON are written as --date-format says, rather than as the
+    // numbers the library writes by default.
+    let format = cli.date_format.clone().unwrap_or_default();
+    match dates::serializing_as(&format, || run(cli)) {
+        Ok(outcome) => process::exit(outcome.exit_code()),
+        // Reading stopped early, as with `brune | head`, which is no error.
+        Err(BruneError::Io(e)) if e.kind() == io::ErrorKind::BrokenPipe => process::exit(0),
+        Err(e) => {
+            eprintln!("{}", text("error", &[("error", &explain(&e))]));
+            process::exit(ERROR_EXIT_CODE)
+        }
+    }
 }
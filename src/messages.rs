@@ -0,0 +1,327 @@
+//! The words brune says to people, as opposed to the listings and machine
+//! formats it writes, kept in a catalog per language so they can be
+//! translated. Each message has a key and a template with `{name}`
+//! placeholders. English is the fallback for any key a catalog is missing,
+//! and the key itself for one English is missing too, so an incomplete
+//! catalog shows up as untranslated text rather than a panic.
+
+use std::fmt;
+
+
+/// Messages by key, for one language.
+pub type Catalog = &'static [(&'static str, &'static str)];
+
+/// The language everything is written in first, and the fallback.
+pub const ENGLISH: Catalog = &[
+    ("summary", "{total} branches: {active} active, {gone} gone, {ahead} ahead, {behind} behind, {merged} merged, {protected} protected"),
+    ("prompt.choices", "y - yes, n - no, a - this and all remaining, q - quit"),
+    ("prompt.choices_show", "y - yes, n - no, a - this and all remaining, q - quit, s - show its commits"),
+
+    ("error", "brune: {error}"),
+    ("error.git", "git {args} failed: {stderr}"),
+    ("error.config", "invalid config {path}: {reason}"),
+    ("error.exec", "{command} failed for {branch}: {status}"),
+    ("error.input_command", "{command} failed with {status}: {stderr}"),
+    ("error.input_command_timeout", "{command} was stopped after running for {timeout}"),
+    ("error.notify", "couldn't send a notification: {reason}"),
+
+    ("count.stash.one", "1 stash"),
+    ("count.stash.other", "{n} stashes"),
+    ("count.commit.one", "1 commit"),
+    ("count.commit.other", "{n} commits"),
+    ("check.protected", "it is protected"),
+    ("check.stashes", "it has {stashes} made on it"),
+    ("check.unpushed", "{commits} on no remote or other branch would become unreachable"),
+    ("check.and", "{first} and {second}"),
+
+    ("prune.missing", "Skipping {branch}, there is no such branch here"),
+    ("prune.current", "Skipping {branch}, it is checked out"),
+    ("prune.worktree", "Skipping {branch}, it is checked out in another worktree"),
+    ("prune.forcing", "Forcing {branch} although {checks}"),
+    ("prune.skipping", "Skipping {branch}, {checks}, pass --force to delete it anyway"),
+    ("prune.none", "No branches to prune"),
+    ("prune.needs_terminal", "refusing to delete without confirmation, pass --yes"),
+    ("prune.last_commit", "{branch} (last commit {date})"),
+    ("prune.ask_each", "delete {branch}?"),
+    ("prune.ask", "Delete {count} branches?"),
+    ("prune.nothing_deleted", "Nothing deleted"),
+    ("prune.deleted", "Deleted branch {branch}"),
+    ("prune.backed_up", "Backed up under {refs}"),
+    ("dry_run.would_run", "Would run: {action}"),
+
+    ("undo.no_dot", "backups can't be listed as a dot graph"),
+    ("undo.no_shield", "backups can't be listed as a shield badge"),
+    ("undo.no_backup", "no backup of {branch}"),
+    ("undo.none", "No backups to restore"),
+    ("undo.needs_terminal", "refusing to ask without a terminal, name the branches to restore instead"),
+    ("undo.ask_each", "restore {branch} at {commit}?"),
+    ("undo.restored", "Restored branch {branch} at {commit}"),
+
+    ("hook.added", "Added brune to {path}"),
+    ("hook.removed", "Took brune out of {path}"),
+    ("hook.absent", "brune isn't in the {hook} hook"),
+    ("report.wrote", "Wrote {count} branches to {path}"),
+    ("watch.watching", "Watching {count} branches, {gone} gone"),
+    ("show.heading", "{commits} on {branch} that {base} hasn't"),
+    ("show.none", "{branch} has no commits that {base} hasn't"),
+    ("show.capped", "The latest {shown} are shown, and {more} older, which a higher --max-commits shows"),
+    ("show.no_branch", "there is no branch {branch} here"),
+
+    ("abbrev.too_short", "commit {commit} of {branch} is shorter than the {digits} digits asked for"),
+    ("config.invalid_format", "invalid format in config: {reason}"),
+    ("config.invalid_notify_after", "invalid notify_after in config: {reason}"),
+    ("config.invalid_older_than", "invalid older_than in config: {reason}"),
+    ("lint.no_rules", "lint needs naming rules under [[lint.rules]] in the config"),
+    ("lint.invalid_rule", "invalid lint rule {rule} in config: {reason}"),
+    ("lint.types_need_structure", "types only apply to a structure"),
+    ("lint.pattern_or_structure", "it needs either a pattern or a structure"),
+    ("policy.no_rules", "policy check needs rules under [[policy.rules]] in the config"),
+    ("policy.invalid_rule", "invalid policy rule {rule} in config: {reason}"),
+    ("policy.one_check", "it needs exactly one of max_age, tracks_upstream, max_gone and pattern"),
+    ("policy.rule_number", "number {n}"),
+    ("provider.unsupported", "{remote} is not on a supported host: {url}"),
+
+    ("usage.template", "templates only apply to listings of local branches, without --group-by"),
+    ("usage.group_by", "--group-by only applies to human output, not {format}"),
+    ("usage.exec", "--exec only applies to list, gone, merged and stale"),
+    ("usage.count", "--count only applies to gone, merged and stale"),
+    ("usage.git_args", "--git-args only applies to the git branch -vv listing, not --input for-each-ref"),
+    ("usage.summary", "--summary doesn't apply to {format} output"),
+    ("usage.notify", "--notify only applies to list, gone, watch and scan"),
+    ("usage.checks", "{command} can't be used with a template, --summary, --porcelain, --group-by or --current"),
+    ("usage.checks_format", "{command} only prints human or json output, or annotations with --github"),
+    ("usage.scan", "scan can't be used with a template, --summary, --porcelain, --exec, --group-by, --current, --input-file, --input-command or --repo"),
+    ("usage.reconcile", "reconcile can't be used with a template, --summary, --porcelain, --exec, --group-by, --current or --repo"),
+    ("usage.reconcile_format", "reconcile only prints human or json output"),
+    ("usage.report", "report can't be used with a template, --porcelain, --group-by or --summary"),
+    ("usage.watch", "watch can't be used with a template, --summary, --porcelain, --exec, --group-by, --input-file or --repo"),
+    ("usage.watch_format", "watch only prints human or ndjson output"),
+    ("usage.show", "show can't be used with a template, --summary, --porcelain, --exec, --group-by, --current or --repo"),
+    ("usage.show_format", "show only prints human output"),
+    ("usage.repos", "reports on several repositories only work for list, gone and merged, without a template, --summary, --porcelain, --exec, --group-by, --current or --count"),
+];
+
+/// German, with every key English has.
+pub const GERMAN: Catalog = &[
+    ("summary", "{total} Branches: {active} aktiv, {gone} verschwunden, {ahead} voraus, {behind} zurück, {merged} gemergt, {protected} geschützt"),
+    ("prompt.choices", "y - ja, n - nein, a - diesen und alle weiteren, q - beenden"),
+    ("prompt.choices_show", "y - ja, n - nein, a - diesen und alle weiteren, q - beenden, s - seine Commits zeigen"),
+
+    ("error", "brune: {error}"),
+    ("error.git", "git {args} ist fehlgeschlagen: {stderr}"),
+    ("error.config", "ungültige Konfiguration {path}: {reason}"),
+    ("error.exec", "{command} ist für {branch} fehlgeschlagen: {status}"),
+    ("error.input_command", "{command} ist mit {status} fehlgeschlagen: {stderr}"),
+    ("error.input_command_timeout", "{command} wurde nach {timeout} Laufzeit gestoppt"),
+    ("error.notify", "Benachrichtigung konnte nicht gesendet werden: {reason}"),
+
+    ("count.stash.one", "1 Stash"),
+    ("count.stash.other", "{n} Stashes"),
+    ("count.commit.one", "1 Commit"),
+    ("count.commit.other", "{n} Commits"),
+    ("check.protected", "er ist geschützt"),
+    ("check.stashes", "auf ihm wurden {stashes} angelegt"),
+    ("check.unpushed", "{commits} auf keinem Remote oder anderen Branch würden unerreichbar"),
+    ("check.and", "{first} und {second}"),
+
+    ("prune.missing", "Überspringe {branch}, diesen Branch gibt es hier nicht"),
+    ("prune.current", "Überspringe {branch}, er ist ausgecheckt"),
+    ("prune.worktree", "Überspringe {branch}, er ist in einem anderen Worktree ausgecheckt"),
+    ("prune.forcing", "Erzwinge {branch}, obwohl {checks}"),
+    ("prune.skipping", "Überspringe {branch}, {checks}, mit --force wird er trotzdem gelöscht"),
+    ("prune.none", "Keine Branches zum Aufräumen"),
+    ("prune.needs_terminal", "lösche nicht ohne Bestätigung, --yes angeben"),
+    ("prune.last_commit", "{branch} (letzter Commit {date})"),
+    ("prune.ask_each", "{branch} löschen?"),
+    ("prune.ask", "{count} Branches löschen?"),
+    ("prune.nothing_deleted", "Nichts gelöscht"),
+    ("prune.deleted", "Branch {branch} gelöscht"),
+    ("prune.backed_up", "Gesichert unter {refs}"),
+    ("dry_run.would_run", "Würde ausführen: {action}"),
+
+    ("undo.no_dot", "Sicherungen können nicht als dot-Graph aufgelistet werden"),
+    ("undo.no_shield", "Sicherungen können nicht als Shield-Badge aufgelistet werden"),
+    ("undo.no_backup", "keine Sicherung von {branch}"),
+    ("undo.none", "Keine Sicherungen zum Wiederherstellen"),
+    ("undo.needs_terminal", "frage nicht ohne Terminal nach, stattdessen die wiederherzustellenden Branches nennen"),
+    ("undo.ask_each", "{branch} bei {commit} wiederherstellen?"),
+    ("undo.restored", "Branch {branch} bei {commit} wiederhergestellt"),
+
+    ("hook.added", "brune zu {path} hinzugefügt"),
+    ("hook.removed", "brune aus {path} entfernt"),
+    ("hook.absent", "brune ist nicht im {hook}-Hook"),
+    ("report.wrote", "{count} Branches nach {path} geschrieben"),
+    ("watch.watching", "Beobachte {count} Branches, {gone} verschwunden"),
+    ("show.heading", "{commits} auf {branch}, die {base} nicht hat"),
+    ("show.none", "{branch} hat keine Commits, die {base} nicht hat"),
+    ("show.capped", "Die neuesten {shown} werden gezeigt, weitere {more} sind älter; ein höheres --max-commits zeigt sie"),
+    ("show.no_branch", "hier gibt es keinen Branch {branch}"),
+
+    ("abbrev.too_short", "Commit {commit} von {branch} ist kürzer als die verlangten {digits} Stellen"),
+    ("config.invalid_format", "ungültiges format in der Konfiguration: {reason}"),
+    ("config.invalid_notify_after", "ungültiges notify_after in der Konfiguration: {reason}"),
+    ("config.invalid_older_than", "ungültiges older_than in der Konfiguration: {reason}"),
+    ("lint.no_rules", "lint braucht Namensregeln unter [[lint.rules]] in der Konfiguration"),
+    ("lint.invalid_rule", "ungültige lint-Regel {rule} in der Konfiguration: {reason}"),
+    ("lint.types_need_structure", "types gelten nur für eine structure"),
+    ("lint.pattern_or_structure", "sie braucht entweder ein pattern oder eine structure"),
+    ("policy.no_rules", "policy check braucht Regeln unter [[policy.rules]] in der Konfiguration"),
+    ("policy.invalid_rule", "ungültige policy-Regel {rule} in der Konfiguration: {reason}"),
+    ("policy.one_check", "sie braucht genau eines von max_age, tracks_upstream, max_gone und pattern"),
+    ("policy.rule_number", "Nummer {n}"),
+    ("provider.unsupported", "{remote} liegt nicht auf einem unterstützten Host: {url}"),
+
+    ("usage.template", "Templates gelten nur für Listen lokaler Branches, ohne --group-by"),
+    ("usage.group_by", "--group-by gilt nur für die menschenlesbare Ausgabe, nicht für {format}"),
+    ("usage.exec", "--exec gilt nur für list, gone, merged und stale"),
+    ("usage.count", "--count gilt nur für gone, merged und stale"),
+    ("usage.git_args", "--git-args gilt nur für die Liste von git branch -vv, nicht für --input for-each-ref"),
+    ("usage.summary", "--summary gilt nicht für die Ausgabe als {format}"),
+    ("usage.notify", "--notify gilt nur für list, gone, watch und scan"),
+    ("usage.checks", "{command} geht nicht mit einem Template, --summary, --porcelain, --group-by oder --current"),
+    ("usage.checks_format", "{command} gibt nur human oder json aus, oder Annotationen mit --github"),
+    ("usage.scan", "scan geht nicht mit einem Template, --summary, --porcelain, --exec, --group-by, --current, --input-file, --input-command oder --repo"),
+    ("usage.reconcile", "reconcile geht nicht mit einem Template, --summary, --porcelain, --exec, --group-by, --current oder --repo"),
+    ("usage.reconcile_format", "reconcile gibt nur human oder json aus"),
+    ("usage.report", "report geht nicht mit einem Template, --porcelain, --group-by oder --summary"),
+    ("usage.watch", "watch geht nicht mit einem Template, --summary, --porcelain, --exec, --group-by, --input-file oder --repo"),
+    ("usage.watch_format", "watch gibt nur human oder ndjson aus"),
+    ("usage.show", "show geht nicht mit einem Template, --summary, --porcelain, --exec, --group-by, --current oder --repo"),
+    ("usage.show_format", "show gibt nur human aus"),
+    ("usage.repos", "Berichte über mehrere Repositories gehen nur für list, gone und merged, ohne Template, --summary, --porcelain, --exec, --group-by, --current oder --count"),
+];
+
+/// The languages there are catalogs for, by their ISO 639-1 code.
+pub const LANGUAGES: [(&str, Catalog); 2] = [("en", ENGLISH), ("de", GERMAN)];
+
+
+/// The messages of one language.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Messages {
+    catalog: Catalog
+}
+
+impl Default for Messages {
+    fn default() -> Messages {
+        Messages::new(ENGLISH)
+    }
+}
+
+impl Messages {
+    pub const fn new(catalog: Catalog) -> Messages {
+        Messages { catalog }
+    }
+
+    /// The messages for `locale`, such as `de`, `de_AT.UTF-8` or `C`, going
+    /// by its language alone. Languages without a catalog get English.
+    pub fn for_locale(locale: &str) -> Messages {
+        language(locale).map_or_else(Messages::default, Messages::new)
+    }
+
+    /// The messages for the locale of `lang`, as given with --lang, or
+    /// else the first of LC_ALL, LC_MESSAGES and LANG that is set, as
+    /// looked up with `var`.
+    pub fn from_env(lang: Option<&str>, var: impl Fn(&str) -> Option<String>) -> Messages {
+        match lang {
+            Some(lang) => Messages::for_locale(lang),
+            None => ["LC_ALL", "LC_MESSAGES", "LANG"]
+                .iter()
+                .find_map(|name| var(name).filter(|value| !value.is_empty()))
+                .map_or_else(Messages::default, |locale| Messages::for_locale(&locale))
+        }
+    }
+
+    /// The template for `key`.
+    pub fn get<'a>(&self, key: &'a str) -> &'a str {
+        let find = |catalog: Catalog| catalog.iter().find(|(k, _)| *k == key).map(|(_, template)| *template);
+        find(self.catalog).or_else(|| find(ENGLISH)).unwrap_or(key)
+    }
+
+    /// `n` of `thing`, in words, e.g. `1 commit` or `2 commits`, going by
+    /// the `count.<thing>.one` and `count.<thing>.other` messages.
+    pub fn counted(&self, thing: &str, n: usize) -> String {
+        self.render(&format!("count.{}.{}", thing, if n == 1 { "one" } else { "other" }), &[("n", &n)])
+    }
+
+    /// The template for `key` with each `{name}` of `args` filled in.
+    /// Placeholders without an argument are left as they are.
+    pub fn render(&self, key: &str, args: &[(&str, &dyn fmt::Display)]) -> String {
+        let mut rendered = String::new();
+        let mut rest = self.get(key);
+        while let Some(open) = rest.find('{') {
+            rendered.push_str(&rest[..open]);
+            let placeholder = rest[open..].find('}').map(|close| &rest[open + 1..open + close]);
+            match placeholder.and_then(|name| args.iter().find(|(n, _)| *n == name).map(|arg| (name, arg.1))) {
+                Some((name, value)) => {
+                    rendered.push_str(&value.to_string());
+                    rest = &rest[open + name.len() + 2..]
+                },
+                None => {
+                    rendered.push('{');
+                    rest = &rest[open + 1..]
+                }
+            }
+        }
+        rendered.push_str(rest);
+        rendered
+    }
+}
+
+/// The catalog for the language of `locale`, which is what comes before
+/// any `_territory`, `.codeset` or `@modifier`.
+fn language(locale: &str) -> Option<Catalog> {
+    let language = locale.split(['_', '-', '.', '@']).next().unwrap_or_default().to_lowercase();
+    LANGUAGES.iter().find(|(code, _)| *code == language).map(|(_, catalog)| *catalog)
+}
+
+/// Checks `lang` names a language brune has messages in, for --lang.
+pub fn parse_lang(lang: &str) -> Result<String, String> {
+    match language(lang) {
+        Some(_) => Ok(lang.to_string()),
+        None => Err(format!("brune has no messages in {}, only in {}", lang, LANGUAGES.iter().map(|(code, _)| *code).collect::<Vec<_>>().join(", ")))
+    }
+}
+
+
+/// 1. Filled in, with unknown placeholders and braces left alone
+/// 2. Missing keys fall back to English, then to the key itself
+#[test]
+fn messages_render() {
+    let english = Messages::default();
+    assert_eq!(english.render("prune.deleted", &[("branch", &"feature/a")]), "Deleted branch feature/a");
+    assert_eq!(english.render("undo.ask_each", &[("branch", &"x")]), "restore x at {commit}?");
+    assert_eq!(english.render("hook.absent", &[("hook", &"{pre-push}")]), "brune isn't in the {pre-push} hook");
+
+    const INCOMPLETE: Catalog = &[("prune.none", "Keine Branches")];
+    let incomplete = Messages::new(INCOMPLETE);
+    assert_eq!(incomplete.get("prune.none"), "Keine Branches");
+    assert_eq!(incomplete.render("prune.deleted", &[("branch", &"x")]), "Deleted branch x");
+    assert_eq!(incomplete.render("no.such.key", &[("branch", &"x")]), "no.such.key");
+}
+
+/// 1. --lang, then LC_ALL, LC_MESSAGES and LANG
+/// 2. Unknown languages and C get English
+#[test]
+fn messages_locales() {
+    let env = |vars: &'static [(&'static str, &'static str)]| move |name: &str| vars.iter().find(|(n, _)| *n == name).map(|(_, v)| v.to_string());
+    assert_eq!(Messages::from_env(None, env(&[("LANG", "de_DE.UTF-8")])), Messages::new(GERMAN));
+    assert_eq!(Messages::from_env(None, env(&[("LC_MESSAGES", "en_GB"), ("LANG", "de_DE.UTF-8")])), Messages::default());
+    assert_eq!(Messages::from_env(None, env(&[("LC_ALL", "de"), ("LC_MESSAGES", "en_GB")])), Messages::new(GERMAN));
+    assert_eq!(Messages::from_env(None, env(&[("LC_ALL", ""), ("LANG", "de")])), Messages::new(GERMAN));
+    assert_eq!(Messages::from_env(Some("en"), env(&[("LANG", "de_DE.UTF-8")])), Messages::default());
+    assert_eq!(Messages::from_env(None, env(&[("LANG", "C")])), Messages::default());
+    assert_eq!(Messages::from_env(None, env(&[("LANG", "fr_FR@euro")])), Messages::default());
+    assert_eq!(Messages::from_env(None, env(&[])), Messages::default());
+
+    assert_eq!(parse_lang("de-AT"), Ok("de-AT".to_string()));
+    assert_eq!(parse_lang("fr"), Err("brune has no messages in fr, only in en, de".to_string()));
+}
+
+/// 1. Every catalog has the keys English has, and no others
+#[test]
+fn messages_complete() {
+    for (code, catalog) in LANGUAGES {
+        let keys = |catalog: Catalog| catalog.iter().map(|(key, _)| *key).collect::<Vec<_>>();
+        assert_eq!(keys(catalog), keys(ENGLISH), "{}", code);
+    }
+}
@@ -0,0 +1,66 @@
+//! Desktop notifications about branches that can be pruned, so cleaning
+//! them up doesn't get forgotten, and about long runs over several
+//! repositories finishing.
+//!
+//! Only available with the `notify` feature.
+
+use std::time::Duration;
+
+use notify_rust::Notification;
+
+use crate::error::BruneError;
+
+
+/// How long a run over several repositories takes before it is worth a
+/// notification when `notify_after` isn't set.
+pub const DEFAULT_NOTIFY_AFTER: Duration = Duration::from_secs(30);
+
+/// `n` of something, in words, e.g. `1 repo` or `42 repos`.
+fn counted(n: usize, one: &str, many: &str) -> String {
+    format!("{} {}", n, if n == 1 { one } else { many })
+}
+
+/// What to say when a run over `repos` repositories finds `gone` gone
+/// branches, with `failed` more that couldn't be read.
+pub fn repos_message(repos: usize, gone: usize, failed: usize) -> String {
+    let message = format!("{} scanned, {}", counted(repos, "repo", "repos"), counted(gone, "gone branch", "gone branches"));
+    match failed {
+        0 => message,
+        _ => format!("{}, {} couldn't be read", message, counted(failed, "repo", "repos"))
+    }
+}
+
+/// What to say about `count` gone branches, `newly` gone since the last
+/// look or not, or nothing when there are none.
+pub fn prunable_message(count: usize, newly: bool) -> Option<String> {
+    let branches = if count == 1 { "1 branch".to_string() } else { format!("{} branches", count) };
+    match (count, newly) {
+        (0, _) => None,
+        (_, true) => Some(format!("{} just became prunable", branches)),
+        (_, false) => Some(format!("{} can be pruned", branches))
+    }
+}
+
+/// Shows `message` as a desktop notification from brune.
+pub fn send(message: &str) -> Result<(), BruneError> {
+    Notification::new().summary("brune").body(message).show()?;
+    Ok(())
+}
+
+
+/// 1. Repos and branches counted in words
+/// 2. Failures only mentioned when there are some
+#[test]
+fn repos_messages() {
+    assert_eq!(repos_message(42, 117, 0), "42 repos scanned, 117 gone branches");
+    assert_eq!(repos_message(1, 1, 0), "1 repo scanned, 1 gone branch");
+    assert_eq!(repos_message(3, 0, 2), "3 repos scanned, 0 gone branches, 2 repos couldn't be read");
+    assert_eq!(repos_message(0, 0, 1), "0 repos scanned, 0 gone branches, 1 repo couldn't be read");
+}
+
+#[test]
+fn prunable_messages() {
+    assert_eq!(prunable_message(0, true), None);
+    assert_eq!(prunable_message(1, true), Some("1 branch just became prunable".to_string()));
+    assert_eq!(prunable_message(3, false), Some("3 branches can be pruned".to_string()));
+}
@@ -0,0 +1,824 @@
+//! Rendering parsed branches in the supported output formats.
+
+use std::borrow::Cow;
+use std::io::{self, Write};
+
+use serde::Serialize;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+#[cfg(feature = "system")]
+use crate::backup::Backup;
+pub use crate::dates::format_date;
+use crate::dates::{DateFormat, DateStyle};
+use crate::messages::Messages;
+use crate::preprocess;
+use crate::remote::RemoteBranchLine;
+use crate::stale::StaleBranch;
+use crate::summary::Summary;
+use crate::{BranchStatus, BranchLine, Upstream};
+
+
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+const RESET: &str = "\x1b[0m";
+
+
+/// Colours used by the human output. A disabled palette leaves text as is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Palette {
+    enabled: bool
+}
+
+impl Palette {
+    pub fn new(enabled: bool) -> Self {
+        Palette { enabled }
+    }
+
+    /// Branches whose upstream is gone.
+    pub fn gone(&self, text: &str) -> String {
+        self.paint(RED, text)
+    }
+
+    /// The checked out branch.
+    pub fn current(&self, text: &str) -> String {
+        self.paint(GREEN, text)
+    }
+
+    /// Ahead and behind annotations.
+    pub fn tracking(&self, text: &str) -> String {
+        self.paint(YELLOW, text)
+    }
+
+    fn paint(&self, colour: &str, text: &str) -> String {
+        if self.enabled {
+            format!("{}{}{}", colour, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+
+/// The status shown for a branch, e.g. `gone` or `merged (PR merged)`,
+/// without how far ahead or behind it is.
+pub(crate) fn status_text(b: &BranchLine) -> String {
+    let status = if b.merged && !b.status.is_gone() { "merged" } else { b.status.kind() }.to_string();
+    match b.pr_state {
+        Some(pr_state) => format!("{} (PR {})", status, pr_state),
+        None => status
+    }
+}
+
+/// The name and status as the human output shows them, coloured by
+/// `palette`.
+fn human_name_status(b: &BranchLine, palette: &Palette) -> (String, String) {
+    let status = status_text(b);
+    let ahead_behind = b.status.ahead_behind();
+    if b.status.is_gone() {
+        (palette.gone(&b.branch_name), palette.gone(&status))
+    } else {
+        let name = if b.is_current { palette.current(&b.branch_name) } else { b.branch_name.clone() };
+        if ahead_behind.is_in_sync() {
+            (name, status)
+        } else {
+            (name, format!("{} {}", status, palette.tracking(&format!("[{}]", ahead_behind))))
+        }
+    }
+}
+
+fn write_human_row<W: Write>(out: &mut W, b: &BranchLine, palette: &Palette, dates: &DateStyle) -> io::Result<()> {
+    let (name, status) = human_name_status(b, palette);
+    match b.last_commit_date {
+        Some(date) => writeln!(out, "{}\t{}\t{}\t{}\t{}", name, b.commit, dates.render(date), status, b.comment),
+        None => writeln!(out, "{}\t{}\t{}\t{}", name, b.commit, status, b.comment)
+    }
+}
+
+/// One branch per line: name, commit, status and comment separated by tabs,
+/// with the date after the commit when known.
+pub fn write_human<W: Write>(out: &mut W, branches: &[&BranchLine], palette: &Palette, dates: &DateStyle) -> io::Result<()> {
+    for b in branches {
+        write_human_row(out, b, palette, dates)?
+    }
+    Ok(())
+}
+
+/// Spaces between the columns of a table.
+const COLUMN_GAP: usize = 2;
+
+/// The least room left for the last column of a table, however narrow the
+/// terminal.
+const MIN_LAST_COLUMN: usize = 10;
+
+/// How many columns `cell` takes up on a terminal, leaving out colours and
+/// counting wide characters such as CJK and emoji twice.
+fn display_width(cell: &str) -> usize {
+    preprocess::strip_ansi(cell).width()
+}
+
+/// `text` cut to fit in `width` columns, ending in `…` when it had to be
+/// cut.
+fn truncate(text: &str, width: usize) -> Cow<'_, str> {
+    if text.width() <= width {
+        return Cow::Borrowed(text)
+    }
+    let mut used = 0;
+    let kept: String =
+        text.chars()
+            .take_while(|c| {
+                used += c.width().unwrap_or(0);
+                used < width
+            })
+            .collect();
+    Cow::Owned(kept + "…")
+}
+
+/// `rows` under `header`, each column padded to its widest cell, with the
+/// last column, which mustn't be coloured, cut to fit in `width`.
+fn write_table<W: Write>(out: &mut W, header: &[&str], rows: &[Vec<String>], width: usize) -> io::Result<()> {
+    let last = header.len() - 1;
+    let mut widths: Vec<usize> = header[..last].iter().map(|h| h.len()).collect();
+    for row in rows {
+        for (w, cell) in widths.iter_mut().zip(row) {
+            *w = (*w).max(display_width(cell))
+        }
+    }
+    let room = width.saturating_sub(widths.iter().map(|w| w + COLUMN_GAP).sum()).max(MIN_LAST_COLUMN);
+
+    let header: Vec<String> = header.iter().map(|h| h.to_string()).collect();
+    for row in std::iter::once(&header).chain(rows) {
+        for (cell, w) in row.iter().zip(&widths) {
+            write!(out, "{}{}", cell, " ".repeat(w - display_width(cell) + COLUMN_GAP))?
+        }
+        writeln!(out, "{}", truncate(&row[last], room))?
+    }
+    Ok(())
+}
+
+/// Like [`write_human`], but as a table with a header for a terminal
+/// `width` columns wide, cutting comments short to fit. Dates get a column
+/// when any branch has one.
+pub fn write_human_table<W: Write>(out: &mut W, branches: &[&BranchLine], palette: &Palette, dates: &DateStyle, width: usize) -> io::Result<()> {
+    let dated = branches.iter().any(|b| b.last_commit_date.is_some());
+    let header: &[&str] = if dated { &["branch", "commit", "date", "status", "comment"] } else { &["branch", "commit", "status", "comment"] };
+    let rows: Vec<Vec<String>> =
+        branches
+            .iter()
+            .map(|b| {
+                let (name, status) = human_name_status(b, palette);
+                let mut row = vec![name, b.commit.to_string()];
+                if dated {
+                    row.push(b.last_commit_date.map_or(String::new(), |date| dates.render(date)))
+                }
+                row.extend([status, b.comment.clone()]);
+                row
+            })
+            .collect();
+    write_table(out, header, &rows, width)
+}
+
+/// The checked out branch and how it tracks its upstream on one line, for
+/// shell prompts: `main`, `main [ahead 1]` or `main [gone]`.
+pub fn write_current<W: Write>(out: &mut W, b: &BranchLine, palette: &Palette) -> io::Result<()> {
+    match b.status {
+        BranchStatus::Gone => writeln!(out, "{} {}", b.branch_name, palette.gone("[gone]")),
+        BranchStatus::UpToDate | BranchStatus::NoUpstream => writeln!(out, "{}", b.branch_name),
+        status => writeln!(out, "{} {}", b.branch_name, palette.tracking(&format!("[{}]", status.ahead_behind())))
+    }
+}
+
+/// Like [`write_human`], but under a `prefix (count)` heading per group with
+/// the branches indented below it. Branches without a prefix are listed
+/// under `(no prefix)`.
+pub fn write_human_grouped<W: Write>(out: &mut W, groups: &[(&str, Vec<&BranchLine>)], palette: &Palette, dates: &DateStyle) -> io::Result<()> {
+    for (prefix, branches) in groups {
+        let heading = if prefix.is_empty() { "(no prefix)" } else { prefix };
+        writeln!(out, "{} ({})", heading, branches.len())?;
+        for b in branches {
+            write!(out, "  ")?;
+            write_human_row(out, b, palette, dates)?
+        }
+    }
+    Ok(())
+}
+
+/// Stable output for scripts: branch, status, commit and upstream.
+///
+/// Fields are tab separated with one branch per line, or each field is
+/// terminated by NUL when `nul_terminated` is set. The fields and status
+/// words (`gone` or `active`) won't change between versions; new fields
+/// will only ever be added at the end.
+pub fn write_porcelain<W: Write>(out: &mut W, branches: &[&BranchLine], nul_terminated: bool) -> io::Result<()> {
+    for b in branches {
+        let status = b.status.kind();
+        let upstream = b.upstream.as_ref().map_or(String::new(), Upstream::to_string);
+        let fields = [b.branch_name.as_str(), status, b.commit.as_str(), &upstream];
+
+        if nul_terminated {
+            for field in fields {
+                write!(out, "{}\0", field)?
+            }
+        } else {
+            writeln!(out, "{}", fields.join("\t"))?
+        }
+    }
+    Ok(())
+}
+
+/// One remote branch per line: name, commit and comment separated by tabs.
+pub fn write_remote_human<W: Write>(out: &mut W, branches: &[&RemoteBranchLine]) -> io::Result<()> {
+    for b in branches {
+        writeln!(out, "{}\t{}\t{}", b.full_name(), b.commit, b.comment)?
+    }
+    Ok(())
+}
+
+/// A pretty printed JSON array of branches.
+pub fn write_json<W: Write, T: Serialize>(out: &mut W, branches: &[&T]) -> io::Result<()> {
+    serde_json::to_writer_pretty(&mut *out, branches)?;
+    writeln!(out)
+}
+
+/// One compact JSON object per line, as JSON Lines.
+pub fn write_ndjson<W: Write, T: Serialize>(out: &mut W, items: &[&T]) -> io::Result<()> {
+    items.iter().try_for_each(|item| write_json_line(out, item))
+}
+
+/// A compact JSON object and a newline.
+pub fn write_json_line<W: Write, T: Serialize>(out: &mut W, item: &T) -> io::Result<()> {
+    serde_json::to_writer(&mut *out, item)?;
+    writeln!(out)
+}
+
+/// Comma separated values with a header row, with dates in `dates`.
+pub fn write_csv<W: Write>(out: &mut W, branches: &[&BranchLine], dates: &DateFormat) -> io::Result<()> {
+    write_delimited(out, branches, b',', dates)
+}
+
+/// Tab separated values with a header row, with dates in `dates`.
+pub fn write_tsv<W: Write>(out: &mut W, branches: &[&BranchLine], dates: &DateFormat) -> io::Result<()> {
+    write_delimited(out, branches, b'\t', dates)
+}
+
+/// Remote branches as comma or tab separated values with a header row.
+pub fn write_remote_delimited<W: Write>(out: &mut W, branches: &[&RemoteBranchLine], delimiter: u8) -> io::Result<()> {
+    let mut writer = csv::WriterBuilder::new().delimiter(delimiter).from_writer(out);
+
+    writer.write_record(["remote", "name", "commit", "comment"])?;
+    for b in branches {
+        writer.write_record([b.remote.as_str(), &b.branch_name, b.commit.as_str(), &b.comment])?
+    }
+
+    writer.flush()
+}
+
+/// One stale branch per line: name, commit, reasons and comment separated
+/// by tabs.
+pub fn write_stale_human<W: Write>(out: &mut W, stale: &[StaleBranch], palette: &Palette) -> io::Result<()> {
+    stale.iter().try_for_each(|s| write_stale_row(out, s, palette))
+}
+
+fn stale_cells(s: &StaleBranch, palette: &Palette) -> Vec<String> {
+    let b = s.branch;
+    let name = if b.status.is_gone() { palette.gone(&b.branch_name) } else { b.branch_name.clone() };
+    vec![name, b.commit.to_string(), s.reason_list(), b.comment.clone()]
+}
+
+fn write_stale_row<W: Write>(out: &mut W, s: &StaleBranch, palette: &Palette) -> io::Result<()> {
+    writeln!(out, "{}", stale_cells(s, palette).join("\t"))
+}
+
+/// Like [`write_stale_human`], but as a table with a header for a terminal
+/// `width` columns wide, cutting comments short to fit.
+pub fn write_stale_table<W: Write>(out: &mut W, stale: &[StaleBranch], palette: &Palette, width: usize) -> io::Result<()> {
+    let rows: Vec<Vec<String>> = stale.iter().map(|s| stale_cells(s, palette)).collect();
+    write_table(out, &["branch", "commit", "reasons", "comment"], &rows, width)
+}
+
+/// Each repository with stale branches under a heading with their count,
+/// as [`write_human_grouped`] lays out groups.
+pub fn write_repos_stale_human<W: Write>(out: &mut W, repos: &[(&str, Vec<StaleBranch>)], palette: &Palette) -> io::Result<()> {
+    for (repo, stale) in repos.iter().filter(|(_, stale)| !stale.is_empty()) {
+        writeln!(out, "{} ({})", repo, stale.len())?;
+        for s in stale {
+            write!(out, "  ")?;
+            write_stale_row(out, s, palette)?
+        }
+    }
+    Ok(())
+}
+
+/// Each repository's stale branches as comma or tab separated values, with
+/// a `repo` column before the usual ones.
+pub fn write_repos_stale_delimited<W: Write>(out: &mut W, repos: &[(&str, Vec<StaleBranch>)], delimiter: u8) -> io::Result<()> {
+    let mut writer = csv::WriterBuilder::new().delimiter(delimiter).from_writer(out);
+
+    writer.write_record(["repo", "name", "commit", "reasons", "comment"])?;
+    for (repo, stale) in repos {
+        for s in stale {
+            writer.write_record([repo, s.branch.branch_name.as_str(), s.branch.commit.as_str(), &s.reason_list(), &s.branch.comment])?
+        }
+    }
+
+    writer.flush()
+}
+
+/// Stale branches as comma or tab separated values with a header row.
+pub fn write_stale_delimited<W: Write>(out: &mut W, stale: &[StaleBranch], delimiter: u8) -> io::Result<()> {
+    let mut writer = csv::WriterBuilder::new().delimiter(delimiter).from_writer(out);
+
+    writer.write_record(["name", "commit", "reasons", "comment"])?;
+    for s in stale {
+        writer.write_record([s.branch.branch_name.as_str(), s.branch.commit.as_str(), &s.reason_list(), &s.branch.comment])?
+    }
+
+    writer.flush()
+}
+
+/// One backup per line: prune time, its date, branch and commit separated
+/// by tabs.
+#[cfg(feature = "system")]
+pub fn write_backups_human<W: Write>(out: &mut W, backups: &[&Backup]) -> io::Result<()> {
+    for b in backups {
+        writeln!(out, "{}\t{}\t{}\t{}", b.time, format_date(b.time), b.branch, b.commit)?
+    }
+    Ok(())
+}
+
+/// Backups as comma or tab separated values with a header row.
+#[cfg(feature = "system")]
+pub fn write_backups_delimited<W: Write>(out: &mut W, backups: &[&Backup], delimiter: u8) -> io::Result<()> {
+    let mut writer = csv::WriterBuilder::new().delimiter(delimiter).from_writer(out);
+    for b in backups {
+        writer.serialize(b)?
+    }
+    writer.flush()
+}
+
+/// The totals on one line, e.g. `4 branches: 3 active, 1 gone, ...`, in
+/// the language of `messages`.
+pub fn write_summary_human<W: Write>(out: &mut W, summary: &Summary, messages: &Messages) -> io::Result<()> {
+    let totals: [(&str, &dyn std::fmt::Display); 7] = [
+        ("total", &summary.total),
+        ("active", &summary.active),
+        ("gone", &summary.gone),
+        ("ahead", &summary.ahead),
+        ("behind", &summary.behind),
+        ("merged", &summary.merged),
+        ("protected", &summary.protected)
+    ];
+    writeln!(out, "{}", messages.render("summary", &totals))
+}
+
+/// The totals as a pretty printed JSON object.
+pub fn write_summary_json<W: Write>(out: &mut W, summary: &Summary) -> io::Result<()> {
+    serde_json::to_writer_pretty(&mut *out, summary)?;
+    writeln!(out)
+}
+
+/// The totals as comma or tab separated values with a header row.
+pub fn write_summary_delimited<W: Write>(out: &mut W, summary: &Summary, delimiter: u8) -> io::Result<()> {
+    let mut writer = csv::WriterBuilder::new().delimiter(delimiter).from_writer(out);
+    writer.serialize(summary)?;
+    writer.flush()
+}
+
+const DELIMITED_HEADER: [&str; 13] = ["name", "current", "worktree", "type", "commit", "upstream", "ahead", "behind", "comment", "last_commit_date", "merged", "pr_state", "provider"];
+
+fn delimited_record(b: &BranchLine, dates: &DateFormat) -> [String; 13] {
+    [
+        b.branch_name.clone(),
+        b.is_current.to_string(),
+        b.checked_out_in_worktree.to_string(),
+        b.status.kind().to_string(),
+        b.commit.to_string(),
+        b.upstream.as_ref().map_or(String::new(), Upstream::to_string),
+        b.status.ahead_behind().ahead.to_string(),
+        b.status.ahead_behind().behind.to_string(),
+        b.comment.clone(),
+        b.last_commit_date.map_or(String::new(), |d| dates.format(d)),
+        b.merged.to_string(),
+        b.pr_state.map_or(String::new(), |state| state.to_string()),
+        b.provider.map_or(String::new(), |host| host.to_string())
+    ]
+}
+
+fn write_delimited<W: Write>(out: &mut W, branches: &[&BranchLine], delimiter: u8, dates: &DateFormat) -> io::Result<()> {
+    let mut writer = csv::WriterBuilder::new().delimiter(delimiter).from_writer(out);
+
+    writer.write_record(DELIMITED_HEADER)?;
+    for b in branches {
+        writer.write_record(delimited_record(b, dates))?
+    }
+
+    writer.flush()
+}
+
+/// A branch, or a [`StaleBranch`], along with the repository it is in, for
+/// reports covering several. Serialized as the branch with a `repo` field
+/// first.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct RepoBranch<'a, T = BranchLine> {
+    pub repo: &'a str,
+    #[serde(flatten)]
+    pub branch: &'a T
+}
+
+/// Each repository's branches as comma or tab separated values, with a
+/// `repo` column before the usual ones.
+pub fn write_repos_delimited<W: Write>(out: &mut W, repos: &[(&str, Vec<&BranchLine>)], delimiter: u8, dates: &DateFormat) -> io::Result<()> {
+    let mut writer = csv::WriterBuilder::new().delimiter(delimiter).from_writer(out);
+
+    writer.write_record(std::iter::once("repo").chain(DELIMITED_HEADER))?;
+    for (repo, branches) in repos {
+        for b in branches {
+            writer.write_record(std::iter::once(repo.to_string()).chain(delimited_record(b, dates)))?
+        }
+    }
+
+    writer.flush()
+}
+
+
+/// Escapes `|` so it doesn't end a markdown table cell.
+fn markdown_cell(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+/// A GitHub flavored markdown table of `rows` under `header`.
+fn write_markdown_table<W: Write, R: IntoIterator<Item = Vec<String>>>(out: &mut W, header: &[&str], rows: R) -> io::Result<()> {
+    writeln!(out, "| {} |", header.join(" | "))?;
+    writeln!(out, "|{}", " --- |".repeat(header.len()))?;
+    for row in rows {
+        writeln!(out, "| {} |", row.iter().map(|cell| markdown_cell(cell)).collect::<Vec<_>>().join(" | "))?
+    }
+    Ok(())
+}
+
+const MARKDOWN_HEADER: [&str; 4] = ["branch", "status", "last commit", "comment"];
+
+/// The commit, with its date when known.
+fn markdown_last_commit(b: &BranchLine, dates: &DateStyle) -> String {
+    match b.last_commit_date {
+        Some(date) => format!("{} ({})", b.commit, dates.render(date)),
+        None => b.commit.to_string()
+    }
+}
+
+fn markdown_record(b: &BranchLine, dates: &DateStyle) -> Vec<String> {
+    let ahead_behind = b.status.ahead_behind();
+    let status = if b.status.is_gone() || ahead_behind.is_in_sync() { status_text(b) } else { format!("{} [{}]", status_text(b), ahead_behind) };
+    vec![b.branch_name.clone(), status, markdown_last_commit(b, dates), b.comment.clone()]
+}
+
+/// The branches as a markdown table, for pasting into an issue or pull
+/// request. The last commit has its date when the branches have dates.
+pub fn write_markdown<W: Write>(out: &mut W, branches: &[&BranchLine], dates: &DateStyle) -> io::Result<()> {
+    write_markdown_table(out, &MARKDOWN_HEADER, branches.iter().map(|b| markdown_record(b, dates)))
+}
+
+/// Each repository's branches as one markdown table, with a `repo` column
+/// before the usual ones.
+pub fn write_repos_markdown<W: Write>(out: &mut W, repos: &[(&str, Vec<&BranchLine>)], dates: &DateStyle) -> io::Result<()> {
+    let header: Vec<&str> = std::iter::once("repo").chain(MARKDOWN_HEADER).collect();
+    let rows = repos.iter().flat_map(|(repo, branches)| branches.iter().map(move |b| std::iter::once(repo.to_string()).chain(markdown_record(b, dates)).collect()));
+    write_markdown_table(out, &header, rows)
+}
+
+/// Stale branches as a markdown table, with the reasons in place of the
+/// status.
+pub fn write_stale_markdown<W: Write>(out: &mut W, stale: &[StaleBranch], dates: &DateStyle) -> io::Result<()> {
+    let rows = stale.iter().map(|s| vec![s.branch.branch_name.clone(), s.reason_list(), markdown_last_commit(s.branch, dates), s.branch.comment.clone()]);
+    write_markdown_table(out, &["branch", "reasons", "last commit", "comment"], rows)
+}
+
+/// Each repository's stale branches as one markdown table, with a `repo`
+/// column first.
+pub fn write_repos_stale_markdown<W: Write>(out: &mut W, repos: &[(&str, Vec<StaleBranch>)], dates: &DateStyle) -> io::Result<()> {
+    let rows = repos.iter().flat_map(|(repo, stale)| {
+        stale.iter().map(move |s| vec![repo.to_string(), s.branch.branch_name.clone(), s.reason_list(), markdown_last_commit(s.branch, dates), s.branch.comment.clone()])
+    });
+    write_markdown_table(out, &["repo", "branch", "reasons", "last commit", "comment"], rows)
+}
+
+/// Remote branches as a markdown table.
+pub fn write_remote_markdown<W: Write>(out: &mut W, branches: &[&RemoteBranchLine]) -> io::Result<()> {
+    let rows = branches.iter().map(|b| vec![b.full_name(), b.commit.to_string(), b.comment.clone()]);
+    write_markdown_table(out, &["branch", "last commit", "comment"], rows)
+}
+
+/// Backups as a markdown table, with the date of each prune.
+#[cfg(feature = "system")]
+pub fn write_backups_markdown<W: Write>(out: &mut W, backups: &[&Backup]) -> io::Result<()> {
+    let rows = backups.iter().map(|b| vec![b.time.to_string(), format_date(b.time), b.branch.clone(), b.commit.to_string()]);
+    write_markdown_table(out, &["time", "date", "branch", "commit"], rows)
+}
+
+/// The totals as a one row markdown table.
+pub fn write_summary_markdown<W: Write>(out: &mut W, summary: &Summary) -> io::Result<()> {
+    let counts = [summary.total, summary.active, summary.gone, summary.ahead, summary.behind, summary.merged, summary.protected];
+    write_markdown_table(out, &["total", "active", "gone", "ahead", "behind", "merged", "protected"], [counts.iter().map(usize::to_string).collect()])
+}
+
+
+#[cfg(test)]
+fn render(write: fn(&mut Vec<u8>, &[&BranchLine]) -> io::Result<()>, input: &str) -> String {
+    let branches = crate::parse_branch_lines(input);
+    let mut out = Vec::new();
+    write(&mut out, &branches.iter().collect::<Vec<_>>()).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn output_human() {
+    let rendered = render(|out, b| write_human(out, b, &Palette::new(false), &DateStyle::absolute(DateFormat::Iso8601)), "  FeatureC dddd3333 [gone] Random weird comments\n* master 0000bbbb Blah");
+    assert_eq!(rendered, "FeatureC\tdddd3333\tgone\tRandom weird comments\nmaster\t0000bbbb\tactive\tBlah\n");
+}
+
+#[test]
+fn output_human_coloured() {
+    let rendered = render(|out, b| write_human(out, b, &Palette::new(true), &DateStyle::absolute(DateFormat::Iso8601)), "  FeatureC dddd3333 [gone] Random\n* master 0000bbbb [behind 2] Blah");
+    assert_eq!(rendered, "\x1b[31mFeatureC\x1b[0m\tdddd3333\t\x1b[31mgone\x1b[0m\tRandom\n\x1b[32mmaster\x1b[0m\t0000bbbb\tactive \x1b[33m[behind 2]\x1b[0m\tBlah\n");
+}
+
+#[test]
+fn output_human_with_date() {
+    let mut branches = crate::parse_branch_lines("  FeatureC dddd3333 [gone] Random");
+    branches[0].last_commit_date = Some(1_700_000_000);
+    let mut out = Vec::new();
+    write_human(&mut out, &branches.iter().collect::<Vec<_>>(), &Palette::new(false), &DateStyle::absolute(DateFormat::Iso8601)).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), "FeatureC\tdddd3333\t2023-11-14T22:13:20Z\tgone\tRandom\n");
+
+    let mut out = Vec::new();
+    write_human(&mut out, &branches.iter().collect::<Vec<_>>(), &Palette::new(false), &DateStyle::relative(1_700_000_000 + 3 * 7 * 86_400)).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), "FeatureC\tdddd3333\t3 weeks ago\tgone\tRandom\n");
+}
+
+/// 1. Columns are padded to the widest cell, leaving out colours
+/// 2. Comments are cut short to fit the terminal
+/// 3. Wide characters count as two columns when padding and cutting
+/// 4. Dates get a column when any branch has one
+#[test]
+fn output_human_table() {
+    let mut branches = crate::parse_branch_lines("  FeatureC dddd3333 [gone] Random, weird and long\n* master 0000bbbb [behind 2] Blah");
+    let table = |branches: &[BranchLine], palette: &Palette| {
+        let mut out = Vec::new();
+        write_human_table(&mut out, &branches.iter().collect::<Vec<_>>(), palette, &DateStyle::absolute(DateFormat::Iso8601), 40).unwrap();
+        String::from_utf8(out).unwrap()
+    };
+
+    assert_eq!(
+        table(&branches, &Palette::new(false)),
+        "branch    commit    status             comment\n\
+         FeatureC  dddd3333  gone               Random, w…\n\
+         master    0000bbbb  active [behind 2]  Blah\n"
+    );
+    assert!(table(&branches, &Palette::new(true)).contains("\x1b[31mFeatureC\x1b[0m  dddd3333  \x1b[31mgone\x1b[0m               Random"));
+
+    let wide = crate::parse_branch_lines("  功能/登录 dddd3333 修复登录页面的错误和问题\n  🚀rocket eeee4444 Launch");
+    assert_eq!(
+        table(&wide, &Palette::new(false)),
+        "branch     commit    status  comment\n\
+         功能/登录  dddd3333  active  修复登录页…\n\
+         🚀rocket   eeee4444  active  Launch\n"
+    );
+
+    branches[0].last_commit_date = Some(1_700_000_000);
+    assert!(table(&branches, &Palette::new(false)).starts_with("branch    commit    date                  status             comment\nFeatureC  dddd3333  2023-11-14T22:13:20Z  gone"));
+}
+
+#[test]
+fn output_human_merged() {
+    let mut branches = crate::parse_branch_lines("  FeatureC dddd3333 Random");
+    branches[0].merged = true;
+    let mut out = Vec::new();
+    write_human(&mut out, &branches.iter().collect::<Vec<_>>(), &Palette::new(false), &DateStyle::absolute(DateFormat::Iso8601)).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), "FeatureC\tdddd3333\tmerged\tRandom\n");
+}
+
+#[test]
+fn output_human_pr_state() {
+    let mut branches = crate::parse_branch_lines("  FeatureC dddd3333 [origin/FeatureC] Random");
+    branches[0].pr_state = Some(crate::PrState::Merged);
+    let mut out = Vec::new();
+    write_human(&mut out, &branches.iter().collect::<Vec<_>>(), &Palette::new(false), &DateStyle::absolute(DateFormat::Iso8601)).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), "FeatureC\tdddd3333\tactive (PR merged)\tRandom\n");
+}
+
+/// 1. In sync
+/// 2. Ahead and behind
+/// 3. Gone
+#[test]
+fn output_current() {
+    let rendered = render(|out, b| b.iter().try_for_each(|b| write_current(out, b, &Palette::new(false))),
+        "* master 0000bbbb [origin/master] A\n* master 0000bbbb [origin/master: ahead 1, behind 2] B\n* master 0000bbbb [origin/master: gone] C\n");
+    assert_eq!(rendered, "master\nmaster [ahead 1, behind 2]\nmaster [gone]\n");
+}
+
+#[test]
+fn output_human_grouped() {
+    let branches = crate::parse_branch_lines("  bob/b eeee3333 B\n  master dddd3333 [gone] C\n  bob/c aaaa4444 D\n");
+    let branches: Vec<&BranchLine> = branches.iter().collect();
+    let mut out = Vec::new();
+    write_human_grouped(&mut out, &crate::sort::group_by_prefix(&branches), &Palette::new(false), &DateStyle::absolute(DateFormat::Iso8601)).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), "bob (2)\n  bob/b\teeee3333\tactive\tB\n  bob/c\taaaa4444\tactive\tD\n(no prefix) (1)\n  master\tdddd3333\tgone\tC\n");
+}
+
+#[test]
+fn output_porcelain() {
+    let input = "  FeatureC dddd3333 [origin/FeatureC: gone] Random\n* master 0000bbbb Blah";
+    assert_eq!(render(|out, b| write_porcelain(out, b, false), input), "FeatureC\tgone\tdddd3333\torigin/FeatureC\nmaster\tactive\t0000bbbb\t\n");
+    assert_eq!(render(|out, b| write_porcelain(out, b, true), input), "FeatureC\0gone\0dddd3333\0origin/FeatureC\0master\0active\x000000bbbb\0\0");
+}
+
+#[test]
+fn output_json() {
+    let rendered = render(write_json::<_, BranchLine>, "  FeatureC dddd3333 [origin/FeatureC: gone] Random \"weird\" comments");
+    let expected = r#"[
+  {
+    "name": "FeatureC",
+    "current": false,
+    "worktree": false,
+    "type": "gone",
+    "status": "gone",
+    "ahead": 0,
+    "behind": 0,
+    "commit": "dddd3333",
+    "upstream": {
+      "remote": "origin",
+      "branch": "FeatureC"
+    },
+    "comment": "Random \"weird\" comments",
+    "last_commit_date": null,
+    "merged": false,
+    "pr_state": null,
+    "provider": null
+  }
+]
+"#;
+    assert_eq!(rendered, expected);
+}
+
+#[test]
+fn output_ndjson() {
+    let rendered = render(write_ndjson::<_, BranchLine>, "  FeatureC dddd3333 [gone] X\n* master 0000bbbb Blah");
+    let lines: Vec<&str> = rendered.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with(r#"{"name":"FeatureC","current":false,"#), "{}", lines[0]);
+    assert!(lines[1].starts_with(r#"{"name":"master","current":true,"#), "{}", lines[1]);
+}
+
+/// 1. Comments containing the delimiter are quoted
+/// 2. Quotes are doubled
+#[test]
+fn output_csv() {
+    let rendered = render(|out, b| write_csv(out, b, &DateFormat::Iso8601), "  FeatureC dddd3333 [gone] Fix a, b and \"c\"\n* master 0000bbbb Blah");
+    assert_eq!(rendered, "name,current,worktree,type,commit,upstream,ahead,behind,comment,last_commit_date,merged,pr_state,provider\nFeatureC,false,false,gone,dddd3333,,0,0,\"Fix a, b and \"\"c\"\"\",,false,,\nmaster,true,false,active,0000bbbb,,0,0,Blah,,false,,\n");
+}
+
+/// 1. Pipes in comments are escaped
+/// 2. Ahead and behind follow the status
+/// 3. The date follows the commit when known
+#[test]
+fn output_markdown() {
+    let mut branches = crate::parse_branch_lines("  FeatureC dddd3333 [gone] Fix a | b\n* master 0000bbbb [origin/master: ahead 1] Blah");
+    branches[0].last_commit_date = Some(1_700_000_000);
+    let mut out = Vec::new();
+    write_markdown(&mut out, &branches.iter().collect::<Vec<_>>(), &DateStyle::absolute(DateFormat::Iso8601)).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), "\
+        | branch | status | last commit | comment |\n\
+        | --- | --- | --- | --- |\n\
+        | FeatureC | gone | dddd3333 (2023-11-14T22:13:20Z) | Fix a \\| b |\n\
+        | master | active [ahead 1] | 0000bbbb | Blah |\n");
+}
+
+#[test]
+fn output_tsv() {
+    let rendered = render(|out, b| write_tsv(out, b, &DateFormat::Iso8601), "  FeatureC dddd3333 [gone] Fix a, b\tand c\n* master 0000bbbb Blah");
+    assert_eq!(rendered, "name\tcurrent\tworktree\ttype\tcommit\tupstream\tahead\tbehind\tcomment\tlast_commit_date\tmerged\tpr_state\tprovider\nFeatureC\tfalse\tfalse\tgone\tdddd3333\t\t0\t0\t\"Fix a, b\tand c\"\t\tfalse\t\t\nmaster\ttrue\tfalse\tactive\t0000bbbb\t\t0\t0\tBlah\t\tfalse\t\t\n");
+}
+
+/// 1. CSV dates in each format
+/// 2. JSON dates as numbers for unix and strings otherwise
+#[test]
+fn output_date_formats() {
+    let mut branches = crate::parse_branch_lines("  FeatureC dddd3333 [gone] X");
+    branches[0].last_commit_date = Some(1_700_000_000);
+    let refs: Vec<&BranchLine> = branches.iter().collect();
+    let date_cell = |format: DateFormat| {
+        let mut out = Vec::new();
+        write_csv(&mut out, &refs, &format).unwrap();
+        String::from_utf8(out).unwrap().lines().nth(1).unwrap().split(',').nth(9).unwrap().to_string()
+    };
+    assert_eq!(date_cell(DateFormat::Iso8601), "2023-11-14T22:13:20Z");
+    assert_eq!(date_cell(DateFormat::Unix), "1700000000");
+    assert_eq!(date_cell(DateFormat::parse("%d/%m/%Y").unwrap()), "14/11/2023");
+
+    let json = |format: DateFormat| crate::dates::serializing_as(&format, || {
+        let mut out = Vec::new();
+        write_json_line(&mut out, &refs[0]).unwrap();
+        String::from_utf8(out).unwrap()
+    });
+    assert!(json(DateFormat::Unix).contains(r#""last_commit_date":1700000000,"#));
+    assert!(json(DateFormat::Iso8601).contains(r#""last_commit_date":"2023-11-14T22:13:20Z","#));
+    assert!(json(DateFormat::parse("%s").unwrap()).contains(r#""last_commit_date":"1700000000","#));
+}
+
+#[test]
+fn output_remote() {
+    let remotes = crate::remote::parse_remote_branch_lines("  origin/orphan ffff1111 Random, weird\n");
+    let remotes: Vec<&RemoteBranchLine> = remotes.iter().collect();
+
+    let mut human = Vec::new();
+    write_remote_human(&mut human, &remotes).unwrap();
+    assert_eq!(String::from_utf8(human).unwrap(), "origin/orphan\tffff1111\tRandom, weird\n");
+
+    let mut csv = Vec::new();
+    write_remote_delimited(&mut csv, &remotes, b',').unwrap();
+    assert_eq!(String::from_utf8(csv).unwrap(), "remote,name,commit,comment\norigin,orphan,ffff1111,\"Random, weird\"\n");
+}
+
+/// 1. Human totals in English and German, and the machine formats in neither
+#[test]
+fn output_summary() {
+    use crate::messages::GERMAN;
+
+    let summary = Summary { total: 4, active: 3, gone: 1, ahead: 1, behind: 2, merged: 0, protected: 1 };
+
+    let mut human = Vec::new();
+    write_summary_human(&mut human, &summary, &Messages::default()).unwrap();
+    assert_eq!(String::from_utf8(human).unwrap(), "4 branches: 3 active, 1 gone, 1 ahead, 2 behind, 0 merged, 1 protected\n");
+
+    let mut german = Vec::new();
+    write_summary_human(&mut german, &summary, &Messages::new(GERMAN)).unwrap();
+    assert_eq!(String::from_utf8(german).unwrap(), "4 Branches: 3 aktiv, 1 verschwunden, 1 voraus, 2 zurück, 0 gemergt, 1 geschützt\n");
+
+    let mut csv = Vec::new();
+    write_summary_delimited(&mut csv, &summary, b',').unwrap();
+    assert_eq!(String::from_utf8(csv).unwrap(), "total,active,gone,ahead,behind,merged,protected\n4,3,1,1,2,0,1\n");
+}
+
+#[test]
+fn output_stale() {
+    use crate::stale::StaleReason;
+
+    let branches = crate::parse_branch_lines("  FeatureC dddd3333 [gone] Random, weird\n");
+    let stale = [StaleBranch { branch: &branches[0], reasons: vec![StaleReason::Gone, StaleReason::Untouched] }];
+
+    let mut human = Vec::new();
+    write_stale_human(&mut human, &stale, &Palette::new(false)).unwrap();
+    assert_eq!(String::from_utf8(human).unwrap(), "FeatureC\tdddd3333\tgone, untouched\tRandom, weird\n");
+
+    let mut table = Vec::new();
+    write_stale_table(&mut table, &stale, &Palette::new(false), 80).unwrap();
+    assert_eq!(String::from_utf8(table).unwrap(), "branch    commit    reasons          comment\nFeatureC  dddd3333  gone, untouched  Random, weird\n");
+
+    let mut csv = Vec::new();
+    write_stale_delimited(&mut csv, &stale, b',').unwrap();
+    assert_eq!(String::from_utf8(csv).unwrap(), "name,commit,reasons,comment\nFeatureC,dddd3333,\"gone, untouched\",\"Random, weird\"\n");
+}
+
+#[cfg(feature = "system")]
+#[test]
+fn output_backups() {
+    let backup = Backup { time: 1_700_000_000, branch: "feature/x".to_string(), commit: crate::HexValue("dddd3333".to_string()) };
+
+    let mut human = Vec::new();
+    write_backups_human(&mut human, &[&backup]).unwrap();
+    assert_eq!(String::from_utf8(human).unwrap(), "1700000000\t2023-11-14\tfeature/x\tdddd3333\n");
+
+    let mut csv = Vec::new();
+    write_backups_delimited(&mut csv, &[&backup], b',').unwrap();
+    assert_eq!(String::from_utf8(csv).unwrap(), "time,branch,commit\n1700000000,feature/x,dddd3333\n");
+}
+
+#[test]
+fn output_repos_stale() {
+    use crate::stale::StaleReason;
+
+    let branches = crate::parse_branch_lines("  FeatureC dddd3333 [gone] X\n");
+    let repos = [("api", vec![StaleBranch { branch: &branches[0], reasons: vec![StaleReason::Gone] }]), ("web", vec![])];
+
+    let mut human = Vec::new();
+    write_repos_stale_human(&mut human, &repos, &Palette::new(false)).unwrap();
+    assert_eq!(String::from_utf8(human).unwrap(), "api (1)\n  FeatureC\tdddd3333\tgone\tX\n");
+
+    let mut csv = Vec::new();
+    write_repos_stale_delimited(&mut csv, &repos, b',').unwrap();
+    assert_eq!(String::from_utf8(csv).unwrap(), "repo,name,commit,reasons,comment\napi,FeatureC,dddd3333,gone,X\n");
+}
+
+#[test]
+fn output_repos() {
+    let api = crate::parse_branch_lines("  FeatureC dddd3333 [gone] X\n");
+    let web = crate::parse_branch_lines("* master 0000bbbb Blah\n");
+    let repos = [("api", api.iter().collect::<Vec<_>>()), ("web", web.iter().collect())];
+
+    let mut csv = Vec::new();
+    write_repos_delimited(&mut csv, &repos, b',', &DateFormat::Iso8601).unwrap();
+    assert_eq!(String::from_utf8(csv).unwrap(), "repo,name,current,worktree,type,commit,upstream,ahead,behind,comment,last_commit_date,merged,pr_state,provider\napi,FeatureC,false,false,gone,dddd3333,,0,0,X,,false,,\nweb,master,true,false,active,0000bbbb,,0,0,Blah,,false,,\n");
+
+    let json = serde_json::to_string(&RepoBranch { repo: "api", branch: &api[0] }).unwrap();
+    assert!(json.starts_with(r#"{"repo":"api","name":"FeatureC","#), "{}", json);
+}
@@ -0,0 +1,93 @@
+//! Showing output that doesn't fit on the terminal a screen at a time, the
+//! way git does.
+
+use std::env;
+use std::ffi::OsString;
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+
+/// The pager to use when `$PAGER` isn't set.
+pub const DEFAULT_PAGER: &str = "less -R";
+
+/// A pager for a terminal of a given height.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pager {
+    command: String,
+    height: usize
+}
+
+impl Pager {
+    /// `$PAGER`, or [`DEFAULT_PAGER`], for a terminal `height` rows high.
+    /// None when `$PAGER` is empty or `cat`, git's ways of turning paging
+    /// off.
+    pub fn from_env(height: usize) -> Option<Pager> {
+        pager_command(env::var_os("PAGER")).map(|command| Pager { command, height })
+    }
+
+    /// Writes `output` to stdout, through the pager when it won't fit on
+    /// the terminal. It goes straight to stdout when the pager can't be
+    /// run, and stops quietly when the pager is quit before reading it all.
+    pub fn page(&self, output: &[u8]) -> io::Result<()> {
+        if !overflows(output, self.height) {
+            return io::stdout().lock().write_all(output)
+        }
+
+        #[cfg(windows)]
+        let mut command = { let mut c = Command::new("cmd"); c.arg("/C"); c };
+        #[cfg(not(windows))]
+        let mut command = { let mut c = Command::new("sh"); c.arg("-c"); c };
+
+        command.arg(&self.command).stdin(Stdio::piped());
+        if env::var_os("LESS").is_none() {
+            command.env("LESS", "FRX");
+        }
+
+        let mut child = match command.spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                tracing::warn!("couldn't run the pager {}: {}", self.command, e);
+                return io::stdout().lock().write_all(output)
+            }
+        };
+        let written = child.stdin.take().map_or(Ok(()), |mut stdin| stdin.write_all(output));
+        child.wait()?;
+        match written {
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+            written => written
+        }
+    }
+}
+
+fn pager_command(pager: Option<OsString>) -> Option<String> {
+    match pager {
+        None => Some(DEFAULT_PAGER.to_string()),
+        Some(pager) => {
+            let pager = pager.to_string_lossy().trim().to_string();
+            if pager.is_empty() || pager == "cat" { None } else { Some(pager) }
+        }
+    }
+}
+
+/// Whether `output` has more lines than a terminal `height` rows high can
+/// show, leaving a row for the prompt.
+fn overflows(output: &[u8], height: usize) -> bool {
+    memchr::memchr_iter(b'\n', output).count() >= height
+}
+
+
+/// 1. Unset falls back to less
+/// 2. Empty and cat turn paging off
+#[test]
+fn pager_commands() {
+    assert_eq!(pager_command(None), Some(DEFAULT_PAGER.to_string()));
+    assert_eq!(pager_command(Some("most ".into())), Some("most".to_string()));
+    assert_eq!(pager_command(Some("".into())), None);
+    assert_eq!(pager_command(Some("cat".into())), None);
+}
+
+#[test]
+fn pager_overflows() {
+    assert!(!overflows(b"a\nb\n", 3));
+    assert!(overflows(b"a\nb\nc\n", 3));
+}
@@ -0,0 +1,286 @@
+//! Hygiene rules a team keeps its branches to, such as no branch older than
+//! 180 days, each warning or failing when broken.
+
+use std::fmt;
+use std::io::{self, Write};
+use std::time::Duration;
+
+use regex::Regex;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::lint::{self, Level};
+use crate::protect::ProtectedBranches;
+use crate::BranchLine;
+
+
+/// How much breaking a rule matters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Severity {
+    /// Reported, without failing the check.
+    Warn,
+    /// Reported, and the check fails.
+    #[default]
+    Error
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Warn => write!(f, "warn"),
+            Severity::Error => write!(f, "error")
+        }
+    }
+}
+
+
+/// What a rule checks.
+#[derive(Debug, Clone)]
+pub enum Check {
+    /// No branch has gone without commits for longer than this.
+    MaxAge(Duration),
+    /// Every branch tracks an upstream.
+    TracksUpstream,
+    /// There are at most this many gone branches.
+    MaxGone(usize),
+    /// Every name matches this.
+    Pattern(Regex)
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Check::MaxAge(age) => write!(f, "no branch older than {} days", days(*age)),
+            Check::TracksUpstream => write!(f, "every branch tracks an upstream"),
+            Check::MaxGone(count) => write!(f, "at most {} gone branches", count),
+            Check::Pattern(regex) => write!(f, "names match {}", regex)
+        }
+    }
+}
+
+fn days(age: Duration) -> u64 {
+    age.as_secs() / (24 * 60 * 60)
+}
+
+
+/// A rule of the policy.
+#[derive(Debug, Clone)]
+pub struct Rule {
+    pub name: String,
+    pub severity: Severity,
+    pub check: Check
+}
+
+impl Rule {
+    /// A rule named for what it checks.
+    pub fn new(check: Check, severity: Severity) -> Rule {
+        Rule { name: check.to_string(), severity, check }
+    }
+}
+
+
+/// A way a rule was broken: by a branch, or by the branches together.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Violation<'a> {
+    pub branch: Option<&'a str>,
+    pub reason: String
+}
+
+impl fmt::Display for Violation<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.branch {
+            Some(branch) => write!(f, "{} {}", branch, self.reason),
+            None => write!(f, "{}", self.reason)
+        }
+    }
+}
+
+/// A rule and everything breaking it.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct RuleReport<'a> {
+    pub rule: &'a str,
+    pub severity: Severity,
+    pub violations: Vec<Violation<'a>>
+}
+
+
+/// Each rule in turn with the ways it is broken, for a check run at `now`,
+/// in seconds since the Unix epoch. Rules about single branches pass over
+/// those in `exempt`, and over branches without a commit date for the age.
+pub fn evaluate<'a>(branches: &[&'a BranchLine], rules: &'a [Rule], exempt: &ProtectedBranches, now: i64) -> Vec<RuleReport<'a>> {
+    let checked: Vec<&BranchLine> = branches.iter().copied().filter(|b| !exempt.is_protected(&b.branch_name)).collect();
+    rules
+        .iter()
+        .map(|rule| {
+            let violations = match &rule.check {
+                Check::MaxAge(age) => {
+                    let cutoff = now - age.as_secs() as i64;
+                    checked
+                        .iter()
+                        .filter_map(|b| b.last_commit_date.filter(|date| *date < cutoff).map(|date| (b, date)))
+                        .map(|(b, date)| Violation { branch: Some(&b.branch_name), reason: format!("has had no commits for {} days", (now - date) / (24 * 60 * 60)) })
+                        .collect()
+                },
+                Check::TracksUpstream =>
+                    checked
+                        .iter()
+                        .filter(|b| b.upstream.is_none())
+                        .map(|b| Violation { branch: Some(&b.branch_name), reason: "doesn't track an upstream".to_string() })
+                        .collect(),
+                Check::MaxGone(max) => {
+                    let gone = branches.iter().filter(|b| b.status.is_gone()).count();
+                    if gone > *max {
+                        vec![Violation { branch: None, reason: format!("{} gone, more than the {} allowed", if gone == 1 { "1 branch is".to_string() } else { format!("{} branches are", gone) }, max) }]
+                    } else {
+                        Vec::new()
+                    }
+                },
+                Check::Pattern(regex) =>
+                    checked
+                        .iter()
+                        .filter(|b| !regex.is_match(&b.branch_name))
+                        .map(|b| Violation { branch: Some(&b.branch_name), reason: format!("doesn't match {}", regex) })
+                        .collect()
+            };
+            RuleReport { rule: &rule.name, severity: rule.severity, violations }
+        })
+        .collect()
+}
+
+/// Whether a rule of error severity is broken, which makes the check exit
+/// with 1. Broken warnings alone don't.
+pub fn fails(reports: &[RuleReport]) -> bool {
+    reports.iter().any(|r| r.severity == Severity::Error && !r.violations.is_empty())
+}
+
+
+/// Each broken rule with its violations under it, e.g.
+///
+/// ```text
+/// error: at most 5 gone branches
+///   12 branches are gone, more than the 5 allowed
+/// ```
+pub fn write_human<W: Write>(out: &mut W, reports: &[RuleReport]) -> io::Result<()> {
+    for report in reports.iter().filter(|r| !r.violations.is_empty()) {
+        writeln!(out, "{}: {}", report.severity, report.rule)?;
+        report.violations.iter().try_for_each(|v| writeln!(out, "  {}", v))?
+    }
+    Ok(())
+}
+
+/// Every rule, with the ways it is broken, if any.
+#[cfg(feature = "serde")]
+pub fn write_json<W: Write>(out: &mut W, reports: &[RuleReport]) -> io::Result<()> {
+    serde_json::to_writer_pretty(&mut *out, reports)?;
+    writeln!(out)
+}
+
+/// A GitHub Actions annotation for each violation, titled with its rule.
+pub fn write_github<W: Write>(out: &mut W, reports: &[RuleReport]) -> io::Result<()> {
+    reports.iter().try_for_each(|report| {
+        let level = match report.severity {
+            Severity::Warn => Level::Warning,
+            Severity::Error => Level::Error
+        };
+        report.violations.iter().try_for_each(|v| lint::write_annotation(out, level, report.rule, &v.to_string()))
+    })
+}
+
+
+#[cfg(test)]
+fn branches() -> Vec<BranchLine> {
+    let day = 24 * 60 * 60;
+    let mut branches = crate::parse_branch_lines("* main 0000aaaa [origin/main] A\n  feature/old 1111bbbb [origin/feature/old] B\n  spike 2222cccc C\n  fix/gone 3333dddd [origin/fix/gone: gone] D\n  Wip 4444eeee [origin/Wip: gone] E\n");
+    for (b, age) in branches.iter_mut().zip([1, 200, 3, 400, 10]) {
+        b.last_commit_date = Some(NOW - age * day)
+    }
+    branches
+}
+
+#[cfg(test)]
+const NOW: i64 = 1_700_000_000;
+
+#[cfg(test)]
+fn violations(rule: Rule) -> Vec<String> {
+    let branches = branches();
+    let refs: Vec<&BranchLine> = branches.iter().collect();
+    let rules = [rule];
+    evaluate(&refs, &rules, &ProtectedBranches::default(), NOW).remove(0).violations.iter().map(Violation::to_string).collect()
+}
+
+/// 1. Branches older than the age, but not the exempt main
+#[test]
+fn policy_max_age() {
+    let rule = Rule::new(Check::MaxAge(Duration::from_secs(180 * 24 * 60 * 60)), Severity::Error);
+    assert_eq!(rule.name, "no branch older than 180 days");
+    assert_eq!(violations(rule), vec!["feature/old has had no commits for 200 days", "fix/gone has had no commits for 400 days"]);
+}
+
+/// 1. Branches with no upstream at all, where gone ones still have one
+#[test]
+fn policy_tracks_upstream() {
+    assert_eq!(violations(Rule::new(Check::TracksUpstream, Severity::Error)), vec!["spike doesn't track an upstream"]);
+}
+
+/// 1. Up to the limit passes
+/// 2. Past it the branches break the rule together
+#[test]
+fn policy_max_gone() {
+    assert!(violations(Rule::new(Check::MaxGone(2), Severity::Error)).is_empty());
+    assert_eq!(violations(Rule::new(Check::MaxGone(0), Severity::Error)), vec!["2 branches are gone, more than the 0 allowed"]);
+    assert_eq!(violations(Rule::new(Check::MaxGone(1), Severity::Error)), vec!["2 branches are gone, more than the 1 allowed"]);
+}
+
+/// 1. Names not matching, where protected branches are exempt
+#[test]
+fn policy_pattern() {
+    let rule = Rule::new(Check::Pattern(Regex::new("^(feature|fix)/").unwrap()), Severity::Error);
+    assert_eq!(violations(rule), vec!["spike doesn't match ^(feature|fix)/", "Wip doesn't match ^(feature|fix)/"]);
+}
+
+/// 1. Broken warnings don't fail the check
+/// 2. A broken error does
+/// 3. An error nothing breaks doesn't
+#[test]
+fn policy_severity() {
+    let branches = branches();
+    let refs: Vec<&BranchLine> = branches.iter().collect();
+    let exempt = ProtectedBranches::default();
+
+    let warnings = [Rule::new(Check::TracksUpstream, Severity::Warn), Rule::new(Check::MaxGone(0), Severity::Warn)];
+    let reports = evaluate(&refs, &warnings, &exempt, NOW);
+    assert!(reports.iter().all(|r| !r.violations.is_empty()));
+    assert!(!fails(&reports));
+
+    let errors = [Rule::new(Check::TracksUpstream, Severity::Warn), Rule::new(Check::MaxGone(0), Severity::Error)];
+    assert!(fails(&evaluate(&refs, &errors, &exempt, NOW)));
+
+    let passing = [Rule::new(Check::TracksUpstream, Severity::Warn), Rule::new(Check::MaxGone(5), Severity::Error)];
+    assert!(!fails(&evaluate(&refs, &passing, &exempt, NOW)));
+}
+
+/// 1. Only broken rules are listed, grouped with their violations
+/// 2. Annotations are warnings or errors as the rules are
+#[test]
+fn policy_report() {
+    let branches = branches();
+    let refs: Vec<&BranchLine> = branches.iter().collect();
+    let rules = [
+        Rule::new(Check::TracksUpstream, Severity::Warn),
+        Rule { name: "few gone".to_string(), severity: Severity::Error, check: Check::MaxGone(1) },
+        Rule::new(Check::MaxAge(Duration::from_secs(1000 * 24 * 60 * 60)), Severity::Error)
+    ];
+    let reports = evaluate(&refs, &rules, &ProtectedBranches::default(), NOW);
+
+    let mut human = Vec::new();
+    write_human(&mut human, &reports).unwrap();
+    assert_eq!(String::from_utf8(human).unwrap(), "warn: every branch tracks an upstream\n  spike doesn't track an upstream\nerror: few gone\n  2 branches are gone, more than the 1 allowed\n");
+
+    let mut github = Vec::new();
+    write_github(&mut github, &reports).unwrap();
+    assert_eq!(String::from_utf8(github).unwrap(), "::warning title=every branch tracks an upstream::spike doesn't track an upstream\n::error title=few gone::2 branches are gone, more than the 1 allowed\n");
+}
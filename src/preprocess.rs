@@ -0,0 +1,63 @@
+//! Cleaning up a listing before it is parsed.
+
+use std::borrow::Cow;
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+
+/// Removes ANSI escape sequences, such as the colours git adds with
+/// `color.ui = always`, which would otherwise end up in branch names.
+/// Input without any is returned as is.
+pub fn strip_ansi(input: &str) -> Cow<'_, str> {
+    static ANSI: OnceLock<Regex> = OnceLock::new();
+    // CSI sequences like `ESC[31m`, and OSC sequences like hyperlinks ended by BEL or `ESC\`.
+    let ansi = ANSI.get_or_init(|| Regex::new(r"\x1b\[[0-?]*[ -/]*[@-~]|\x1b\][^\x07\x1b]*(?:\x07|\x1b\\)").unwrap());
+    ansi.replace_all(input, "")
+}
+
+
+/// Removes any of `prefixes` from the start of each line, repeatedly, so
+/// that e.g. `[info] ` from sbt or a log level doesn't reach the parser.
+/// Prefixes are matched literally.
+pub fn strip_prefixes<'a, S: AsRef<str>>(input: &'a str, prefixes: &[S]) -> Cow<'a, str> {
+    let prefixes: Vec<&str> = prefixes.iter().map(AsRef::as_ref).filter(|p| !p.is_empty()).collect();
+    if prefixes.is_empty() {
+        return Cow::Borrowed(input)
+    }
+
+    let strip = |mut line: &'a str| {
+        while let Some(rest) = prefixes.iter().find_map(|p| line.strip_prefix(p)) {
+            line = rest
+        }
+        line
+    };
+    Cow::Owned(input.split_inclusive('\n').map(strip).collect())
+}
+
+
+/// 1. Colours around the marker, name and upstream
+/// 2. Plain input is borrowed
+#[test]
+fn strip_ansi_sequences() {
+    let coloured = "* \x1b[32mmaster\x1b[m 0000bbbb [\x1b[34morigin/master\x1b[m: ahead 1] Blah\n  \x1b[1;31mFeatureC\x1b[0m dddd3333 [gone] X\n";
+    assert_eq!(strip_ansi(coloured), "* master 0000bbbb [origin/master: ahead 1] Blah\n  FeatureC dddd3333 [gone] X\n");
+    assert!(matches!(strip_ansi("  FeatureC dddd3333 X"), Cow::Borrowed(_)));
+}
+
+#[test]
+fn strip_ansi_then_parse() {
+    let branches = crate::parse_branch_lines(&strip_ansi("* \x1b[32mmaster\x1b[m 0000bbbb Blah"));
+    assert_eq!(branches[0].branch_name, "master");
+}
+
+/// 1. Prefixes are stripped from every line
+/// 2. More than one prefix on a line
+/// 3. Lines without a prefix are left alone
+#[test]
+fn strip_line_prefixes() {
+    let input = "[info] * master 0000bbbb Blah\n[info] DEBUG   FeatureC dddd3333 [gone] X\n  FeatureD eeee4444 Y";
+    let stripped = strip_prefixes(input, &["[info] ", "DEBUG "]);
+    assert_eq!(stripped, "* master 0000bbbb Blah\n  FeatureC dddd3333 [gone] X\n  FeatureD eeee4444 Y");
+    assert_eq!(crate::parse_branch_lines(&stripped).len(), 3);
+}
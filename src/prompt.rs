@@ -0,0 +1,169 @@
+//! Asking the user for confirmation before doing anything destructive.
+
+use std::io::{self, BufRead, Write};
+
+use crate::messages::Messages;
+
+
+/// Writes `question` to `output` and reads a yes/no answer from `input`.
+///
+/// Anything other than `y` or `yes` (case-insensitive) is a no.
+pub fn confirm<R: BufRead, W: Write>(question: &str, input: &mut R, output: &mut W) -> io::Result<bool> {
+    write!(output, "{} [y/N] ", question)?;
+    output.flush()?;
+
+    let mut answer = String::new();
+    input.read_line(&mut answer)?;
+
+    let answer = answer.trim().to_lowercase();
+    Ok(answer == "y" || answer == "yes")
+}
+
+/// An answer to a per-item question.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Answer {
+    Yes,
+    No,
+    /// Yes to this and everything after it.
+    All,
+    /// No to this and everything after it.
+    Quit,
+    /// Show more about this one, then ask again.
+    Show
+}
+
+/// Asks `question` until it gets one of `y`, `n`, `a` or `q`, or `s` when
+/// `showable`, explaining them in the language of `messages` after any
+/// other answer. The end of the input counts as quitting.
+pub fn ask<R: BufRead, W: Write>(question: &str, showable: bool, messages: &Messages, input: &mut R, output: &mut W) -> io::Result<Answer> {
+    loop {
+        write!(output, "{} [y/n/a/q{}] ", question, if showable { "/s" } else { "" })?;
+        output.flush()?;
+
+        let mut answer = String::new();
+        if input.read_line(&mut answer)? == 0 {
+            writeln!(output)?;
+            return Ok(Answer::Quit)
+        }
+
+        match answer.trim().to_lowercase().as_str() {
+            "y" | "yes" => return Ok(Answer::Yes),
+            "n" | "no" => return Ok(Answer::No),
+            "a" | "all" => return Ok(Answer::All),
+            "q" | "quit" => return Ok(Answer::Quit),
+            "s" | "show" if showable => return Ok(Answer::Show),
+            _ => writeln!(output, "{}", messages.get(if showable { "prompt.choices_show" } else { "prompt.choices" }))?
+        }
+    }
+}
+
+/// Writes more about an item, for the user to look at before answering.
+pub type ShowItem<'s, T> = &'s dyn Fn(&T) -> io::Result<()>;
+
+/// Walks `items`, asking about each one like `git add -p`, and returns the
+/// ones the user said yes to. With `show`, `s` runs it on the item before
+/// asking about it again.
+pub fn choose_each<'a, T, R: BufRead, W: Write>(
+    items: &[&'a T],
+    question: impl Fn(&T) -> String,
+    show: Option<ShowItem<T>>,
+    messages: &Messages,
+    input: &mut R,
+    output: &mut W
+) -> io::Result<Vec<&'a T>> {
+    let mut chosen = Vec::new();
+
+    let mut i = 0;
+    while let Some(item) = items.get(i) {
+        match ask(&question(item), show.is_some(), messages, input, output)? {
+            Answer::Yes => chosen.push(*item),
+            Answer::No => (),
+            Answer::All => {
+                chosen.extend_from_slice(&items[i..]);
+                break
+            },
+            Answer::Quit => break,
+            Answer::Show => {
+                if let Some(show) = show {
+                    show(item)?
+                }
+                continue
+            }
+        }
+        i += 1
+    }
+
+    Ok(chosen)
+}
+
+
+#[test]
+fn confirm_yes() {
+    let mut output = Vec::new();
+    let answer = confirm("Delete 2 branches?", &mut io::Cursor::new("Y\n"), &mut output).unwrap();
+    assert!(answer);
+    assert_eq!(String::from_utf8(output).unwrap(), "Delete 2 branches? [y/N] ");
+}
+
+/// 1. Empty answer defaults to no
+/// 2. Any other answer is a no
+#[test]
+fn confirm_no() {
+    for reply in ["\n", "nope\n", ""] {
+        let answer = confirm("Delete?", &mut io::Cursor::new(reply), &mut Vec::new()).unwrap();
+        assert!(!answer, "{:?} should not confirm", reply);
+    }
+}
+
+/// 1. Invalid answers are asked again
+/// 2. End of input quits
+#[test]
+fn ask_answers() {
+    let mut output = Vec::new();
+    let answer = ask("delete FeatureC?", false, &Messages::default(), &mut io::Cursor::new("maybe\nA\n"), &mut output).unwrap();
+    assert_eq!(answer, Answer::All);
+    assert_eq!(String::from_utf8(output).unwrap(), "delete FeatureC? [y/n/a/q] y - yes, n - no, a - this and all remaining, q - quit\ndelete FeatureC? [y/n/a/q] ");
+
+    assert_eq!(ask("delete?", false, &Messages::default(), &mut io::Cursor::new(""), &mut Vec::new()).unwrap(), Answer::Quit);
+}
+
+/// 1. Yes and no per item
+/// 2. All takes the current item and the rest
+#[test]
+fn choose_each_all() {
+    let items = ["a", "b", "c", "d"];
+    let items: Vec<&&str> = items.iter().collect();
+    let chosen = choose_each(&items, |i| format!("delete {}?", i), None, &Messages::default(), &mut io::Cursor::new("n\ny\na\n"), &mut Vec::new()).unwrap();
+    assert_eq!(chosen, vec![&"b", &"c", &"d"]);
+}
+
+/// 1. Quit stops without taking the current item
+#[test]
+fn choose_each_quit() {
+    let items = ["a", "b", "c"];
+    let items: Vec<&&str> = items.iter().collect();
+    let chosen = choose_each(&items, |i| format!("delete {}?", i), None, &Messages::default(), &mut io::Cursor::new("y\nq\n"), &mut Vec::new()).unwrap();
+    assert_eq!(chosen, vec![&"a"]);
+}
+
+/// 1. Showing an item asks about it again
+/// 2. s is only an answer when there is something to show
+#[test]
+fn choose_each_show() {
+    let items = ["a", "b"];
+    let items: Vec<&&str> = items.iter().collect();
+    let shown = std::cell::RefCell::new(Vec::new());
+    let show = |i: &&str| {
+        shown.borrow_mut().push(i.to_string());
+        Ok(())
+    };
+    let mut output = Vec::new();
+    let chosen = choose_each(&items, |i| format!("delete {}?", i), Some(&show), &Messages::default(), &mut io::Cursor::new("n\ns\ny\n"), &mut output).unwrap();
+    assert_eq!(chosen, vec![&"b"]);
+    assert_eq!(shown.into_inner(), vec!["b"]);
+    assert_eq!(String::from_utf8(output).unwrap(), "delete a? [y/n/a/q/s] delete b? [y/n/a/q/s] delete b? [y/n/a/q/s] ");
+
+    let mut output = Vec::new();
+    assert_eq!(ask("delete?", false, &Messages::default(), &mut io::Cursor::new("s\nq\n"), &mut output).unwrap(), Answer::Quit);
+    assert_eq!(String::from_utf8(output).unwrap(), "delete? [y/n/a/q] y - yes, n - no, a - this and all remaining, q - quit\ndelete? [y/n/a/q] ");
+}
@@ -0,0 +1,62 @@
+//! Branches that must never be pruned.
+
+
+/// Branches protected unless configured otherwise.
+pub const DEFAULT_PROTECTED: [&str; 3] = ["main", "master", "develop"];
+
+
+/// The set of branch names prune refuses to delete.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProtectedBranches {
+    names: Vec<String>
+}
+
+impl ProtectedBranches {
+    pub fn new<I, S>(names: I) -> Self
+    where I: IntoIterator<Item = S>, S: Into<String> {
+        ProtectedBranches { names: names.into_iter().map(Into::into).collect() }
+    }
+
+    /// Adds `name` to the protected set.
+    pub fn protect<S: Into<String>>(&mut self, name: S) {
+        let name = name.into();
+        if !self.is_protected(&name) {
+            self.names.push(name)
+        }
+    }
+
+    pub fn is_protected(&self, name: &str) -> bool {
+        self.names.iter().any(|n| n == name)
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+}
+
+impl Default for ProtectedBranches {
+    fn default() -> Self {
+        ProtectedBranches::new(DEFAULT_PROTECTED)
+    }
+}
+
+
+#[test]
+fn protected_defaults() {
+    let protected = ProtectedBranches::default();
+    assert!(protected.is_protected("main"));
+    assert!(protected.is_protected("master"));
+    assert!(protected.is_protected("develop"));
+    assert!(!protected.is_protected("feature/main"));
+}
+
+/// 1. Extra names are added
+/// 2. Duplicates are ignored
+#[test]
+fn protected_extra_names() {
+    let mut protected = ProtectedBranches::default();
+    protected.protect("release");
+    protected.protect("main");
+    assert!(protected.is_protected("release"));
+    assert_eq!(protected.names(), ["main", "master", "develop", "release"]);
+}
@@ -0,0 +1,287 @@
+//! Lining the local branches up with the branches on a remote, as `git
+//! ls-remote` or `git branch -r -vv` lists them, to show what is only on
+//! one side and what is on both.
+
+use std::fmt;
+use std::io::{self, Write};
+
+use nom::bytes::complete::take_till1;
+use nom::IResult;
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use crate::remote::{self, RemoteListingLine};
+use crate::stream::ParseError;
+use crate::{numbered_lines, report_lines, take_hex, take_whitespace, BranchLine, HexValue, ParseMode, ParseReport};
+
+
+/// A branch on the remote, by its name without any prefix.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct RemoteHead {
+    pub name: String,
+    pub commit: HexValue
+}
+
+/// `name` without the `refs/heads/`, `heads/`, `refs/remotes/<remote>/` or
+/// `remotes/<remote>/` it may be written with, so the names of the two
+/// sides can be compared.
+pub fn normalise<'a>(name: &'a str, remote: &str) -> &'a str {
+    let tracking = |prefix: &str| name.strip_prefix(prefix).and_then(|rest| rest.strip_prefix(remote)).and_then(|rest| rest.strip_prefix('/'));
+    name.strip_prefix("refs/heads/")
+        .or_else(|| name.strip_prefix("heads/"))
+        .or_else(|| tracking("refs/remotes/"))
+        .or_else(|| tracking("remotes/"))
+        .unwrap_or(name)
+}
+
+
+/// Parses a line of `git ls-remote` output, e.g.
+/// "dddd3333dddd3333dddd3333dddd3333dddd3333\trefs/heads/main".
+///
+/// Refs other than branches, such as tags and HEAD, are None.
+pub fn parse_ls_remote_line(input: &str) -> IResult<&str, Option<RemoteHead>> {
+    let (tail2, commit)   = take_hex(input)?;
+    let (tail3, _)        = take_whitespace(tail2)?;
+    let (tail4, name)     = take_till1(char::is_whitespace)(tail3)?;
+
+    let head = name.strip_prefix("refs/heads/").map(|name| RemoteHead { name: name.to_string(), commit });
+    Ok((tail4, head))
+}
+
+fn parse_remote_head<'a>(line: &'a str, remote: &str) -> Result<Option<RemoteHead>, nom::Err<nom::error::Error<&'a str>>> {
+    if let Ok((_, head)) = parse_ls_remote_line(line) {
+        return Ok(head)
+    }
+    match remote::parse_remote_listing_line(line)? {
+        (_, RemoteListingLine::Branch(b)) if b.remote == remote => Ok(Some(RemoteHead { name: normalise(&b.branch_name, remote).to_string(), commit: b.commit })),
+        (_, RemoteListingLine::Branch(_) | RemoteListingLine::SymbolicRef(_)) => Ok(None)
+    }
+}
+
+/// Parses every non-blank line of `git ls-remote` or `git branch -r -vv`
+/// output as the branches on `remote`, reporting the lines that were
+/// skipped. Tags, symbolic refs and the branches of other remotes are
+/// counted as ignored.
+pub fn parse_remote_heads_report(input: &str, remote: &str) -> ParseReport<RemoteHead> {
+    report_lines(numbered_lines(input).into_iter().map(|(n, line)| (n, line, parse_remote_head(line, remote))))
+}
+
+/// Parses the branches on `remote` as [`parse_remote_heads_report`] does,
+/// treating lines that don't parse as `mode` says.
+pub fn parse_remote_heads_with(input: &str, remote: &str, mode: ParseMode) -> Result<Vec<RemoteHead>, ParseError> {
+    parse_remote_heads_report(input, remote).into_result(mode)
+}
+
+
+/// What to do with a branch only found locally.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Suggestion {
+    /// It was never pushed, or not to this remote.
+    Push,
+    /// Its upstream is gone or it is merged, so it is done with.
+    Delete
+}
+
+/// Whether a branch on both sides is at the same commit on each.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Sync {
+    Same,
+    Differs
+}
+
+impl fmt::Display for Suggestion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Suggestion::Push => write!(f, "push"),
+            Suggestion::Delete => write!(f, "delete")
+        }
+    }
+}
+
+/// A branch only found locally.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct LocalOnly<'a> {
+    pub name: &'a str,
+    pub commit: &'a HexValue,
+    pub suggestion: Suggestion
+}
+
+/// A branch on both sides.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct OnBoth<'a> {
+    pub name: &'a str,
+    pub local_commit: &'a HexValue,
+    pub remote_commit: &'a HexValue,
+    pub status: Sync
+}
+
+/// The branches of each side, in three sections.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Reconciliation<'a> {
+    pub local_only: Vec<LocalOnly<'a>>,
+    pub remote_only: Vec<&'a RemoteHead>,
+    pub both: Vec<OnBoth<'a>>
+}
+
+impl Reconciliation<'_> {
+    /// Whether every branch is on both sides, at the same commit.
+    pub fn in_sync(&self) -> bool {
+        self.local_only.is_empty() && self.remote_only.is_empty() && self.both.iter().all(|b| b.status == Sync::Same)
+    }
+}
+
+/// Whether two hashes name the same commit, when one may be abbreviated.
+fn same_commit(a: &HexValue, b: &HexValue) -> bool {
+    a.as_str().starts_with(b.as_str()) || b.as_str().starts_with(a.as_str())
+}
+
+/// Lines `locals` up with `remotes` by their names, normalised for
+/// `remote`. Local only and both sides branches are in the order of
+/// `locals`, and remote only ones in the order of `remotes`.
+pub fn reconcile<'a>(locals: &[&'a BranchLine], remotes: &'a [RemoteHead], remote: &str) -> Reconciliation<'a> {
+    let mut reconciliation = Reconciliation { local_only: Vec::new(), remote_only: Vec::new(), both: Vec::new() };
+    for local in locals {
+        let name = normalise(&local.branch_name, remote);
+        match remotes.iter().find(|r| r.name == name) {
+            Some(r) => {
+                let status = if same_commit(&local.commit, &r.commit) { Sync::Same } else { Sync::Differs };
+                reconciliation.both.push(OnBoth { name, local_commit: &local.commit, remote_commit: &r.commit, status })
+            },
+            None => {
+                let suggestion = if local.status.is_gone() || local.merged { Suggestion::Delete } else { Suggestion::Push };
+                reconciliation.local_only.push(LocalOnly { name, commit: &local.commit, suggestion })
+            }
+        }
+    }
+    reconciliation.remote_only = remotes.iter().filter(|r| !locals.iter().any(|l| normalise(&l.branch_name, remote) == r.name)).collect();
+    reconciliation
+}
+
+
+/// Each section under a heading with its count, and its branches indented
+/// below it with their names, commits and suggestion or status separated
+/// by tabs, e.g. `  main  eeee4444  differs from ffff5555`.
+pub fn write_human<W: Write>(out: &mut W, reconciliation: &Reconciliation) -> io::Result<()> {
+    writeln!(out, "local only ({})", reconciliation.local_only.len())?;
+    for b in &reconciliation.local_only {
+        writeln!(out, "  {}\t{}\t{}", b.name, b.commit, b.suggestion)?
+    }
+    writeln!(out, "remote only ({})", reconciliation.remote_only.len())?;
+    for b in &reconciliation.remote_only {
+        writeln!(out, "  {}\t{}", b.name, b.commit)?
+    }
+    writeln!(out, "on both ({})", reconciliation.both.len())?;
+    for b in &reconciliation.both {
+        match b.status {
+            Sync::Same => writeln!(out, "  {}\t{}\tsame", b.name, b.local_commit)?,
+            Sync::Differs => writeln!(out, "  {}\t{}\tdiffers from {}", b.name, b.local_commit, b.remote_commit)?
+        }
+    }
+    Ok(())
+}
+
+#[cfg(feature = "serde")]
+pub fn write_json<W: Write>(out: &mut W, reconciliation: &Reconciliation) -> io::Result<()> {
+    serde_json::to_writer_pretty(&mut *out, reconciliation)?;
+    writeln!(out)
+}
+
+
+#[cfg(test)]
+const LOCAL: &str = "\
+* main 1111aaaa [origin/main] Release
+  feature/x 2222bbbb [origin/feature/x: ahead 1] Login
+  spike 3333cccc Try things
+  fix/old 4444dddd [origin/fix/old: gone] Old fix
+";
+
+#[cfg(test)]
+const LS_REMOTE: &str = "\
+1111aaaa1111aaaa1111aaaa1111aaaa1111aaaa\tHEAD
+1111aaaa1111aaaa1111aaaa1111aaaa1111aaaa\trefs/heads/main
+5555eeee5555eeee5555eeee5555eeee5555eeee\trefs/heads/feature/x
+6666ffff6666ffff6666ffff6666ffff6666ffff\trefs/heads/release/1.0
+7777aaaa7777aaaa7777aaaa7777aaaa7777aaaa\trefs/tags/v1.0
+";
+
+/// 1. Branches, with any other ref ignored
+/// 2. A line with no ref doesn't parse
+#[test]
+fn reconcile_parse_ls_remote() {
+    let report = parse_remote_heads_report(LS_REMOTE, "origin");
+    let names: Vec<&str> = report.parsed.iter().map(|r| r.name.as_str()).collect();
+    assert_eq!(names, vec!["main", "feature/x", "release/1.0"]);
+    assert_eq!(report.parsed[1].commit, HexValue("5555eeee5555eeee5555eeee5555eeee5555eeee".to_string()));
+    assert_eq!((report.skipped.len(), report.ignored), (0, 2));
+    assert!(parse_ls_remote_line("1111aaaa").is_err());
+}
+
+/// 1. The branches of the remote asked for, without its prefix
+/// 2. Other remotes and symbolic refs are ignored
+#[test]
+fn reconcile_parse_branch_r() {
+    let report = parse_remote_heads_report("  origin/HEAD -> origin/main\n  origin/main 1111aaaa Release\n  upstream/main 9999aaaa Theirs\n", "origin");
+    assert_eq!(report.parsed, vec![RemoteHead { name: "main".to_string(), commit: HexValue("1111aaaa".to_string()) }]);
+    assert_eq!(report.ignored, 2);
+}
+
+/// 1. Full and short ref prefixes
+/// 2. Remote-tracking prefixes only for the remote given
+#[test]
+fn reconcile_normalise() {
+    assert_eq!(normalise("refs/heads/feature/x", "origin"), "feature/x");
+    assert_eq!(normalise("heads/main", "origin"), "main");
+    assert_eq!(normalise("refs/remotes/origin/main", "origin"), "main");
+    assert_eq!(normalise("remotes/origin/main", "origin"), "main");
+    assert_eq!(normalise("remotes/upstream/main", "origin"), "remotes/upstream/main");
+    assert_eq!(normalise("originals/x", "origin"), "originals/x");
+}
+
+/// 1. The same name with different hashes on each side differs
+/// 2. An abbreviated hash is the same as the full one
+/// 3. Local only branches are to push, or to delete when gone
+/// 4. Remote only branches in the remote's order
+#[test]
+fn reconcile_sections() {
+    let locals = crate::parse_branch_lines(LOCAL);
+    let refs: Vec<&BranchLine> = locals.iter().collect();
+    let remotes = parse_remote_heads_report(LS_REMOTE, "origin").parsed;
+    let reconciliation = reconcile(&refs, &remotes, "origin");
+
+    let mut out = Vec::new();
+    write_human(&mut out, &reconciliation).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), "\
+        local only (2)\n  spike\t3333cccc\tpush\n  fix/old\t4444dddd\tdelete\n\
+        remote only (1)\n  release/1.0\t6666ffff6666ffff6666ffff6666ffff6666ffff\n\
+        on both (2)\n  main\t1111aaaa\tsame\n  feature/x\t2222bbbb\tdiffers from 5555eeee5555eeee5555eeee5555eeee5555eeee\n");
+    assert!(!reconciliation.in_sync());
+
+    let main = remotes[..1].to_vec();
+    assert!(reconcile(&refs[..1], &main, "origin").in_sync());
+}
+
+/// 1. Each section under its own key, with the status of those on both
+#[cfg(feature = "serde")]
+#[test]
+fn reconcile_json() {
+    let locals = crate::parse_branch_lines("  feature/x 2222bbbb [origin/feature/x] Login\n  spike 3333cccc Try\n");
+    let refs: Vec<&BranchLine> = locals.iter().collect();
+    let remotes = parse_remote_heads_report("  origin/feature/x 5555eeee Login\n  origin/orphan 6666ffff Orphan\n", "origin").parsed;
+
+    let mut out = Vec::new();
+    write_json(&mut out, &reconcile(&refs, &remotes, "origin")).unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&out).unwrap();
+    assert_eq!(json, serde_json::json!({
+        "local_only": [{ "name": "spike", "commit": "3333cccc", "suggestion": "push" }],
+        "remote_only": [{ "name": "orphan", "commit": "6666ffff" }],
+        "both": [{ "name": "feature/x", "local_commit": "2222bbbb", "remote_commit": "5555eeee", "status": "differs" }]
+    }));
+}
@@ -0,0 +1,196 @@
+//! Remote-tracking branches as listed by `git branch -r -vv`.
+
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::combinator::map;
+use nom::IResult;
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use crate::stream::ParseError;
+use crate::{numbered_lines, report_lines, take_branch_name, take_comment, take_hex, take_whitespace, BranchLine, HexValue, ParseMode, ParseReport};
+
+
+/// A single parsed line of `git branch -r -vv` output.
+#[derive(Debug,PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct RemoteBranchLine {
+    /// The remote, e.g. `origin`.
+    pub remote: String,
+    /// The branch on the remote, without the remote prefix.
+    pub branch_name: String,
+    pub commit: HexValue,
+    pub comment: String
+}
+
+impl RemoteBranchLine {
+    /// The remote-tracking branch name, e.g. `origin/feature`.
+    pub fn full_name(&self) -> String {
+        format!("{}/{}", self.remote, self.branch_name)
+    }
+}
+
+
+/// A line such as `origin/HEAD -> origin/main`, naming the branch a remote
+/// ref points to.
+#[derive(Debug,PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct SymbolicRef {
+    /// The symbolic ref, e.g. `origin/HEAD`.
+    pub name: String,
+    /// The ref it points to, e.g. `origin/main`.
+    pub target: String
+}
+
+/// Any line of `git branch -r -vv` output.
+#[derive(Debug,PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub enum RemoteListingLine {
+    Branch(RemoteBranchLine),
+    SymbolicRef(SymbolicRef)
+}
+
+
+/// Parses a single line of `git branch -r -vv` output.
+///
+/// Possible variations:
+/// "  origin/FeatureA         dddeeee Random weird comments"
+/// "  upstream/PERSON1/FeatureD eeee4444 Random weird comments"
+///
+pub fn parse_remote_branch_line(input: &str) -> IResult<&str, RemoteBranchLine> {
+    let (tail2, _)         = take_whitespace(input)?;
+    let (tail3, full_name) = take_branch_name(tail2)?;
+    let (tail4, _)         = take_whitespace(tail3)?;
+    let (tail5, commit)    = take_hex(tail4)?;
+    let (tail6, _)         = take_whitespace(tail5)?;
+
+    match full_name.split_once('/') {
+        Some((remote, branch_name)) => {
+            let remote_line = RemoteBranchLine { remote: remote.to_string(), branch_name: branch_name.to_string(), commit, comment: take_comment(tail6).to_string() };
+            Ok((tail6, remote_line))
+        },
+        None => Err(nom::Err::Error(nom::error::Error::new(tail2, nom::error::ErrorKind::Verify)))
+    }
+}
+
+/// Parses a symbolic ref line of `git branch -r` output, e.g.
+/// "  origin/HEAD -> origin/main"
+pub fn parse_symbolic_ref_line(input: &str) -> IResult<&str, SymbolicRef> {
+    let (tail2, _)      = take_whitespace(input)?;
+    let (tail3, name)   = take_branch_name(tail2)?;
+    let (tail4, _)      = take_whitespace(tail3)?;
+    let (tail5, _)      = tag("->")(tail4)?;
+    let (tail6, _)      = take_whitespace(tail5)?;
+    let (tail7, target) = take_branch_name(tail6)?;
+
+    Ok((tail7, SymbolicRef { name: name.to_string(), target: target.to_string() }))
+}
+
+/// Parses any line of `git branch -r -vv` output.
+pub fn parse_remote_listing_line(input: &str) -> IResult<&str, RemoteListingLine> {
+    alt((
+        map(parse_symbolic_ref_line, RemoteListingLine::SymbolicRef),
+        map(parse_remote_branch_line, RemoteListingLine::Branch)
+    ))(input)
+}
+
+fn parse_remote_branch_only(line: &str) -> Result<Option<RemoteBranchLine>, nom::Err<nom::error::Error<&str>>> {
+    match parse_remote_listing_line(line)? {
+        (_, RemoteListingLine::Branch(branch_line)) => Ok(Some(branch_line)),
+        (_, RemoteListingLine::SymbolicRef(_)) => Ok(None)
+    }
+}
+
+/// Parses every non-blank line of `git branch -r -vv` output.
+///
+/// Symbolic refs and lines that don't parse are skipped.
+pub fn parse_remote_branch_lines(input: &str) -> Vec<RemoteBranchLine> {
+    parse_remote_branch_lines_with(input, ParseMode::Lenient).unwrap_or_default()
+}
+
+/// Parses every non-blank line of `git branch -r -vv` output, treating lines
+/// that don't parse as `mode` says.
+pub fn parse_remote_branch_lines_with(input: &str, mode: ParseMode) -> Result<Vec<RemoteBranchLine>, ParseError> {
+    parse_remote_branch_lines_report(input).into_result(mode)
+}
+
+/// Parses every non-blank line of `git branch -r -vv` output, reporting the
+/// branches along with the lines that were skipped. Symbolic refs are
+/// counted as ignored.
+pub fn parse_remote_branch_lines_report(input: &str) -> ParseReport<RemoteBranchLine> {
+    report_lines(numbered_lines(input).into_iter().map(|(n, line)| (n, line, parse_remote_branch_only(line))))
+}
+
+/// Parses every non-blank line of `git branch -r -vv` output, keeping
+/// symbolic refs alongside the branches.
+pub fn parse_remote_lines_report(input: &str) -> ParseReport<RemoteListingLine> {
+    report_lines(numbered_lines(input).into_iter().map(|(n, line)| (n, line, parse_remote_listing_line(line).map(|(_, parsed)| Some(parsed)))))
+}
+
+/// The remote branches that no local branch tracks or shares a name with.
+pub fn remote_only<'a>(remotes: &'a [RemoteBranchLine], locals: &[BranchLine]) -> Vec<&'a RemoteBranchLine> {
+    remotes
+        .iter()
+        .filter(|remote| {
+            !locals.iter().any(|local| {
+                local.upstream.as_ref().is_some_and(|upstream| upstream.remote == remote.remote && upstream.branch == remote.branch_name) ||
+                    local.branch_name == remote.branch_name
+            })
+        })
+        .collect()
+}
+
+
+#[test]
+fn parse_remote_line() {
+    let (_, m) = parse_remote_branch_line("  origin/PERSON1/FeatureD eeee4444 Random weird comments").unwrap();
+    let expected = RemoteBranchLine { remote: "origin".to_string(), branch_name: "PERSON1/FeatureD".to_string(), commit: HexValue("eeee4444".to_string()), comment: "Random weird comments".to_string() };
+    assert_eq!(m, expected);
+    assert_eq!(m.full_name(), "origin/PERSON1/FeatureD");
+}
+
+/// 1. CRLF line endings
+/// 2. A single line parsed on its own
+#[test]
+fn parse_remote_lines_crlf() {
+    let remotes = parse_remote_branch_lines("  origin/HEAD -> origin/main\r\n  origin/main dddd3333 X\r\n");
+    assert_eq!(remotes[0].comment, "X");
+    assert_eq!(parse_remote_branch_line("  origin/main dddd3333 X\r").unwrap().1.comment, "X");
+}
+
+/// 1. A remote branch needs a remote prefix
+#[test]
+fn parse_remote_line_without_remote() {
+    assert!(parse_remote_branch_line("  FeatureA dddeeee Random weird comments").is_err());
+}
+
+/// 1. With -vv
+/// 2. Without
+#[test]
+fn parse_symbolic_ref() {
+    let expected = RemoteListingLine::SymbolicRef(SymbolicRef { name: "origin/HEAD".to_string(), target: "origin/main".to_string() });
+    assert_eq!(parse_remote_listing_line("  origin/HEAD -> origin/main").unwrap().1, expected);
+    assert_eq!(parse_remote_listing_line("  origin/HEAD      -> origin/main").unwrap().1, expected);
+}
+
+#[test]
+fn parse_remote_lines_with_symbolic_ref() {
+    let input = "  origin/HEAD -> origin/main\n  origin/main dddd3333 X\n";
+
+    let branches = parse_remote_branch_lines_report(input);
+    assert_eq!((branches.parsed.len(), branches.skipped.len(), branches.ignored), (1, 0, 1));
+
+    let lines = parse_remote_lines_report(input);
+    assert!(matches!(lines.parsed[..], [RemoteListingLine::SymbolicRef(_), RemoteListingLine::Branch(_)]));
+}
+
+/// 1. Tracked by a local branch with another name
+/// 2. Same name as a local branch
+/// 3. No local counterpart
+#[test]
+fn remote_only_branches() {
+    let remotes = parse_remote_branch_lines("  origin/feature/x dddd3333 X\n  origin/FeatureB eeee3333 B\n  origin/orphan ffff1111 Orphan\n");
+    let locals = crate::parse_branch_lines("  my-x dddd3333 [origin/feature/x] X\n  FeatureB eeee3333 B\n");
+    let names: Vec<String> = remote_only(&remotes, &locals).iter().map(|r| r.full_name()).collect();
+    assert_eq!(names, vec!["origin/orphan"]);
+}
@@ -0,0 +1,165 @@
+//! A self-contained HTML report of a repository's branches, to share with
+//! a team: the totals, how old the branches are, and every branch in a
+//! table that sorts by any column.
+
+use std::io::{self, Write};
+use std::time::Duration;
+
+use crate::dates::DateStyle;
+use crate::output::status_text;
+use crate::stale::StaleBranch;
+use crate::summary::Summary;
+use crate::BranchLine;
+
+
+const DAY: i64 = 24 * 60 * 60;
+
+/// The upper bound, in days, and label of each age bucket, youngest first.
+const AGE_BUCKETS: [(i64, &str); 5] = [
+    (7, "under a week"),
+    (30, "under a month"),
+    (90, "under 3 months"),
+    (365, "under a year"),
+    (i64::MAX, "a year or more")
+];
+
+/// How many branches had their last commit within each age bucket of
+/// `now`, youngest first, then how many have no date.
+pub fn age_distribution(branches: &[&BranchLine], now: i64) -> Vec<(&'static str, usize)> {
+    let bucket = |date: i64| AGE_BUCKETS.iter().position(|(days, _)| now.saturating_sub(date) / DAY < *days).unwrap_or(AGE_BUCKETS.len() - 1);
+
+    let mut counts = vec![0; AGE_BUCKETS.len() + 1];
+    for b in branches {
+        counts[b.last_commit_date.map_or(AGE_BUCKETS.len(), bucket)] += 1
+    }
+
+    AGE_BUCKETS.iter().map(|(_, label)| *label).chain(["no date"]).zip(counts).collect()
+}
+
+/// Escapes text for HTML element content and quoted attributes.
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c)
+        }
+    }
+    escaped
+}
+
+const STYLE: &str = "\
+body { font-family: system-ui, sans-serif; margin: 2em; color: #222; }
+table { border-collapse: collapse; margin-bottom: 2em; }
+th, td { padding: 0.3em 0.8em; text-align: left; border-bottom: 1px solid #ddd; }
+#branches th { cursor: pointer; user-select: none; }
+#branches th:after { content: ' \\2195'; color: #aaa; }
+.gone { color: #b00; }
+.bar { background: #4a7; height: 1em; }";
+
+// Sorts by the column clicked, by its data-sort value when it has one, and
+// the other way round when it's clicked again.
+const SCRIPT: &str = "\
+document.querySelectorAll('#branches th').forEach((th, column) => th.addEventListener('click', () => {
+  const body = th.closest('table').tBodies[0];
+  const ascending = th.dataset.order !== 'asc';
+  th.dataset.order = ascending ? 'asc' : 'desc';
+  const key = row => { const cell = row.cells[column]; return cell.dataset.sort ?? cell.textContent; };
+  const compare = (a, b) => { const [x, y] = [key(a), key(b)]; return isNaN(x) || isNaN(y) || x === '' || y === '' ? x.localeCompare(y) : x - y; };
+  [...body.rows].sort((a, b) => ascending ? compare(a, b) : compare(b, a)).forEach(row => body.appendChild(row));
+}));";
+
+/// Writes the report titled `title` on `branches`, with the `summary` of
+/// them and the `stale` ones among them as of `now`, stale after `age`
+/// without commits. Last commits are dated as `dates` says, with the exact
+/// date in their tooltip in the style's format, as the report's own date
+/// is.
+#[allow(clippy::too_many_arguments)]
+pub fn write_html<W: Write>(out: &mut W, title: &str, branches: &[&BranchLine], summary: &Summary, stale: &[StaleBranch], now: i64, age: Duration, dates: &DateStyle) -> io::Result<()> {
+    let title = escape_html(title);
+    writeln!(out, "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n{}\n</style>\n</head>\n<body>", title, STYLE)?;
+    writeln!(out, "<h1>{}</h1>\n<p>As of {}, with branches untouched for {} days counted as stale.</p>", title, dates.format.format(now), age.as_secs() as i64 / DAY)?;
+
+    writeln!(out, "<h2>Totals</h2>\n<table>")?;
+    let totals = [
+        ("branches", summary.total), ("active", summary.active), ("gone", summary.gone), ("stale", stale.len()),
+        ("ahead", summary.ahead), ("behind", summary.behind), ("merged", summary.merged), ("protected", summary.protected)
+    ];
+    for (label, count) in totals {
+        writeln!(out, "<tr><th>{}</th><td>{}</td></tr>", label, count)?
+    }
+    writeln!(out, "</table>")?;
+
+    writeln!(out, "<h2>Age of the last commit</h2>\n<table>")?;
+    let ages = age_distribution(branches, now);
+    let most = ages.iter().map(|(_, count)| *count).max().unwrap_or_default().max(1);
+    for (label, count) in ages {
+        writeln!(out, "<tr><th>{}</th><td>{}</td><td style=\"width: 20em\"><div class=\"bar\" style=\"width: {}%\"></div></td></tr>", label, count, count * 100 / most)?
+    }
+    writeln!(out, "</table>")?;
+
+    writeln!(out, "<h2>Branches</h2>\n<table id=\"branches\">\n<thead><tr><th>branch</th><th>status</th><th>stale</th><th>last commit</th><th>ahead</th><th>behind</th><th>upstream</th><th>comment</th></tr></thead>\n<tbody>")?;
+    for b in branches {
+        let reasons = stale.iter().find(|s| std::ptr::eq(s.branch, *b)).map(StaleBranch::reason_list).unwrap_or_default();
+        let (date, exact, sort_date) = b.last_commit_date.map_or((String::new(), String::new(), String::new()), |date| {
+            (dates.render(date), format!(" title=\"{}\"", dates.format.format(date)), date.to_string())
+        });
+        let ahead_behind = b.status.ahead_behind();
+        let upstream = b.upstream.as_ref().map(ToString::to_string).unwrap_or_default();
+        writeln!(
+            out,
+            "<tr{}><td>{}</td><td>{}</td><td>{}</td><td data-sort=\"{}\"{}>{} {}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            if b.status.is_gone() { " class=\"gone\"" } else { "" },
+            escape_html(&b.branch_name), status_text(b), reasons, sort_date, exact, b.commit, date,
+            ahead_behind.ahead, ahead_behind.behind, escape_html(&upstream), escape_html(&b.comment)
+        )?
+    }
+    writeln!(out, "</tbody>\n</table>\n<script>\n{}\n</script>\n</body>\n</html>", SCRIPT)
+}
+
+
+/// 1. Branches fall in the youngest bucket they fit
+/// 2. Branches without a date are counted apart
+#[test]
+fn report_age_distribution() {
+    let now = 1_000 * DAY;
+    let mut branches = crate::parse_branch_lines("  a dddd3333 X\n  b eeee4444 X\n  c ffff5555 X\n  d aaaa6666 X\n");
+    branches[0].last_commit_date = Some(now - DAY);
+    branches[1].last_commit_date = Some(now - 10 * DAY);
+    branches[2].last_commit_date = Some(now - 400 * DAY);
+    let branches: Vec<&BranchLine> = branches.iter().collect();
+
+    assert_eq!(age_distribution(&branches, now), vec![
+        ("under a week", 1), ("under a month", 1), ("under 3 months", 0), ("under a year", 0), ("a year or more", 1), ("no date", 1)
+    ]);
+}
+
+#[test]
+fn report_escapes_html() {
+    assert_eq!(escape_html("<b>\"fix\" & 'test'</b>"), "&lt;b&gt;&quot;fix&quot; &amp; &#39;test&#39;&lt;/b&gt;");
+}
+
+/// 1. Gone branches are marked
+/// 2. Stale reasons and comments are filled in, escaped
+/// 3. Last commits say how long ago they were, exactly in the tooltip
+#[test]
+fn report_html() {
+    let day = Duration::from_secs(DAY as u64);
+    let mut branches = crate::parse_branch_lines("  feature/x dddd3333 [origin/feature/x: gone] Fix <em>\n* main eeee4444 Blah\n");
+    branches[1].last_commit_date = Some(-3 * DAY);
+    let branches: Vec<&BranchLine> = branches.iter().collect();
+    let summary = Summary::of(&branches, &crate::protect::ProtectedBranches::default());
+    let stale = crate::stale::classify(&branches, 0, day);
+
+    let mut out = Vec::new();
+    write_html(&mut out, "brune", &branches, &summary, &stale, 0, day, &DateStyle::relative(0)).unwrap();
+    let html = String::from_utf8(out).unwrap();
+    assert!(html.contains("<tr class=\"gone\"><td>feature/x</td><td>gone</td><td>gone</td><td data-sort=\"\">dddd3333 </td><td>0</td><td>0</td><td>origin/feature/x</td><td>Fix &lt;em&gt;</td></tr>"));
+    assert!(html.contains("<td data-sort=\"-259200\" title=\"1969-12-29T00:00:00Z\">eeee4444 3 days ago</td>"));
+    assert!(html.contains("<tr><th>stale</th><td>1</td></tr>"));
+    assert!(html.ends_with("</html>\n"));
+}
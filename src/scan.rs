@@ -0,0 +1,155 @@
+//! Picking the branch lines out of other output, such as a CI log with a
+//! `git branch -vv` dump somewhere in it.
+//!
+//! Most lines of such input can be turned down from their first few bytes,
+//! so they are checked before the parser sees them. Finding line and
+//! token ends with memchr keeps that check cheap on huge inputs.
+
+use memchr::{memchr, memchr_iter};
+
+use crate::stream::ParseError;
+use crate::{parse_branch_only, BranchLine, BranchStatus, ParseReport, FULL_HASH_LEN, MIN_HASH_LEN};
+
+
+/// Whether the line has the shape of a `git branch -vv` line: a marker
+/// column, then a name followed by a whole hash token, or a detached HEAD
+/// in brackets. Lines that pass may still not parse, but lines that fail
+/// never would.
+pub fn could_be_branch_line(line: &[u8]) -> bool {
+    let rest = match line {
+        [b' ' | b'*' | b'+', b' ', rest @ ..] => rest,
+        _ => return false
+    };
+
+    match rest.first() {
+        Some(b'(') => return true,
+        Some(b' ') | None => return false,
+        Some(_) => {}
+    }
+
+    let after_name = match memchr(b' ', rest) {
+        Some(end) => &rest[end..],
+        None => return false
+    };
+    let hash = match after_name.iter().position(|b| *b != b' ') {
+        Some(start) => &after_name[start..],
+        None => return false
+    };
+    let hash = &hash[..memchr(b' ', hash).unwrap_or(hash.len())];
+    (MIN_HASH_LEN..=FULL_HASH_LEN).contains(&hash.len()) && hash.iter().all(u8::is_ascii_hexdigit)
+}
+
+/// The input's lines, without their line endings.
+fn lines(input: &str) -> impl Iterator<Item = &str> {
+    let bytes = input.as_bytes();
+    let mut start = 0;
+    memchr_iter(b'\n', bytes)
+        .chain(std::iter::once(bytes.len()))
+        .filter_map(move |end| {
+            // Nothing follows a final line ending.
+            if start == bytes.len() && end == bytes.len() {
+                return None
+            }
+            let line = input.get(start..end)?;
+            start = end + 1;
+            Some(line.strip_suffix('\r').unwrap_or(line))
+        })
+}
+
+/// Whether a parsed line says enough to be taken for a branch rather than
+/// noise that happens to parse, such as `  core 7881f32a`: an annotation
+/// or a subject after the hash.
+pub(crate) fn says_enough(branch: &BranchLine) -> bool {
+    branch.upstream.is_some() || branch.status != BranchStatus::NoUpstream || !branch.comment.trim().is_empty()
+}
+
+/// The branch lines in `input`, with the candidates that passed
+/// [`could_be_branch_line`] but not the parser as skipped, and those that
+/// parsed as a detached HEAD or without saying enough as ignored. Every
+/// other line is left out, so the report's total is the number of
+/// candidates considered.
+pub fn scan_branch_lines_report(input: &str) -> ParseReport<BranchLine> {
+    let mut report = ParseReport { parsed: Vec::new(), skipped: Vec::new(), ignored: 0 };
+    let mut read = 0;
+    for (i, line) in lines(input).enumerate() {
+        read = i + 1;
+        if !could_be_branch_line(line.as_bytes()) {
+            continue
+        }
+        match parse_branch_only(line) {
+            Ok(Some(branch)) if says_enough(&branch) => report.parsed.push(branch),
+            Ok(_) => report.ignored += 1,
+            Err(e) => report.skipped.push(ParseError::invalid_line(i + 1, line, e))
+        }
+    }
+
+    tracing::info!("accepted {} of {} candidate lines, out of {} lines read", report.parsed.len(), report.total(), read);
+    report
+}
+
+/// Every branch line in `input`, ignoring anything else without a
+/// warning. Detached HEADs are left out, as with
+/// [`parse_branch_lines`](crate::parse_branch_lines).
+pub fn scan_branch_lines(input: &str) -> Vec<BranchLine> {
+    scan_branch_lines_report(input).parsed
+}
+
+/// 1. Each marker
+/// 2. Detached HEAD
+/// 3. Log lines, indented text and names without a hash
+/// 4. Short numbers and words that only start with a hex digit
+#[test]
+fn branch_line_shapes() {
+    assert!(could_be_branch_line(b"  FeatureC dddd3333 [gone] X"));
+    assert!(could_be_branch_line(b"* master   0000bbbb Blah"));
+    assert!(could_be_branch_line(b"+ wt1 8ef9921 init"));
+    assert!(could_be_branch_line(b"* (HEAD detached at 8ef9921) 8ef9921 init"));
+
+    assert!(!could_be_branch_line(b"[info] Compiling brune"));
+    assert!(!could_be_branch_line(b"    at Main.run(Main.java:12)"));
+    assert!(!could_be_branch_line(b"  Running tests now"));
+    assert!(!could_be_branch_line(b"  FeatureC"));
+    assert!(!could_be_branch_line(b"*"));
+
+    assert!(!could_be_branch_line(b"  Running 3 tests"));
+    assert!(!could_be_branch_line(b"  Step 1 of 7"));
+    assert!(!could_be_branch_line(b"  Building deadbeefcafe-image"));
+}
+
+/// 1. A listing in the middle of a log
+/// 2. Windows line endings and no final newline
+/// 3. The same branches as a plain parse of the listing
+#[test]
+fn scan_log_for_branches() {
+    let listing = "* master 0000bbbb [origin/master: ahead 1] Blah\r\n  FeatureC dddd3333 [gone] X\r\n";
+    let log = format!("Step 3/7: git branch -vv\n  Running tests now\n  Running 3 tests\n  Added 2 files\n{}+ wt1 8ef9921 init\n* (HEAD detached at 8ef9921) 8ef9921 init\nDone", listing);
+    let names: Vec<String> = scan_branch_lines(&log).into_iter().map(|b| b.branch_name).collect();
+    assert_eq!(names, vec!["master", "FeatureC", "wt1"]);
+    assert_eq!(scan_branch_lines(listing), crate::parse_branch_lines(listing));
+}
+
+/// 1. Lines with a hash and nothing after it are turned down
+/// 2. An annotation or a subject is enough
+#[test]
+fn scan_needs_annotation_or_subject() {
+    let report = scan_branch_lines_report("  core 7881f32a\n  gone dddd3333 [gone]\n  wip 2f3a4b5 WIP\n  bad 12345xyz X\n");
+    let names: Vec<&str> = report.parsed.iter().map(|b| b.branch_name.as_str()).collect();
+    assert_eq!(names, vec!["gone", "wip"]);
+    assert_eq!((report.ignored, report.skipped.len(), report.total()), (1, 0, 3));
+}
+
+/// 1. A listing printed by sbt, amid 500 lines of a Jenkins build
+/// 2. Timed steps that don't parse and module hashes with nothing after
+///    them are counted as candidates, but none of them get through
+#[test]
+fn scan_ci_log_fixture() {
+    let log = crate::preprocess::strip_prefixes(include_str!("../tests/fixtures/ci.log"), &["[info] "]);
+    assert_eq!(log.lines().count(), 500);
+
+    let report = scan_branch_lines_report(&log);
+    let names: Vec<&str> = report.parsed.iter().map(|b| b.branch_name.as_str()).collect();
+    assert_eq!(names, vec!["main", "feature/scan", "feature/shield", "fix/pager", "release/1.4", "spike/dot", "wip", "docs"]);
+    assert_eq!(report.parsed[1].status, BranchStatus::Gone);
+    assert_eq!(report.parsed[7].comment, "Document --scan");
+    assert_eq!((report.ignored, report.skipped.len(), report.total()), (45, 32, 85));
+}
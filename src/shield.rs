@@ -0,0 +1,81 @@
+//! A shields.io endpoint badge counting stale branches, for CI to publish
+//! and a README to show with `https://img.shields.io/endpoint?url=...`.
+
+use std::io::{self, Write};
+
+use serde::Serialize;
+
+
+/// The label shown when `--shield-label` isn't given.
+pub const DEFAULT_LABEL: &str = "stale branches";
+
+/// How many stale branches the badge stays yellow for when `--max-gone`
+/// isn't given.
+pub const DEFAULT_MAX_GONE: usize = 5;
+
+/// The label and threshold of a badge.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Badge {
+    pub label: String,
+    /// The most stale branches shown in yellow, with any more in red.
+    pub max_gone: usize
+}
+
+/// The shields.io endpoint schema, version 1.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Endpoint<'a> {
+    schema_version: u8,
+    label: &'a str,
+    message: String,
+    color: &'static str
+}
+
+impl Badge {
+    /// Green with no stale branches, yellow up to `max_gone` of them and
+    /// red past it.
+    pub fn colour(&self, count: usize) -> &'static str {
+        match count {
+            0 => "green",
+            count if count <= self.max_gone => "yellow",
+            _ => "red"
+        }
+    }
+
+    /// Writes the badge for `count` stale branches as one line of JSON.
+    pub fn write<W: Write>(&self, out: &mut W, count: usize) -> io::Result<()> {
+        let endpoint = Endpoint { schema_version: 1, label: &self.label, message: count.to_string(), color: self.colour(count) };
+        serde_json::to_writer(&mut *out, &endpoint)?;
+        writeln!(out)
+    }
+}
+
+impl Default for Badge {
+    fn default() -> Self {
+        Badge { label: DEFAULT_LABEL.to_string(), max_gone: DEFAULT_MAX_GONE }
+    }
+}
+
+
+/// 1. Green with none, yellow up to the limit, red past it
+#[test]
+fn shield_thresholds() {
+    let badge = Badge { label: DEFAULT_LABEL.to_string(), max_gone: 5 };
+    let written = |count| {
+        let mut out = Vec::new();
+        badge.write(&mut out, count).unwrap();
+        String::from_utf8(out).unwrap()
+    };
+    assert_eq!(written(0), "{\"schemaVersion\":1,\"label\":\"stale branches\",\"message\":\"0\",\"color\":\"green\"}\n");
+    assert_eq!(written(5), "{\"schemaVersion\":1,\"label\":\"stale branches\",\"message\":\"5\",\"color\":\"yellow\"}\n");
+    assert_eq!(written(7), "{\"schemaVersion\":1,\"label\":\"stale branches\",\"message\":\"7\",\"color\":\"red\"}\n");
+}
+
+/// 1. The label is escaped as JSON
+#[test]
+fn shield_label() {
+    let badge = Badge { label: "gone \"branches\"".to_string(), ..Badge::default() };
+    let mut out = Vec::new();
+    badge.write(&mut out, 1).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), "{\"schemaVersion\":1,\"label\":\"gone \\\"branches\\\"\",\"message\":\"1\",\"color\":\"yellow\"}\n");
+}
@@ -0,0 +1,186 @@
+//! The commits a branch has that the default branch hasn't, to see what
+//! deleting it would lose before doing so.
+
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::dates::DateStyle;
+use crate::error::BruneError;
+use crate::git;
+use crate::messages::Messages;
+
+
+/// How many commits are shown when --max-commits isn't given.
+pub const DEFAULT_MAX_COMMITS: usize = 50;
+
+/// Hash, committer date, author and subject, split by the unit separator,
+/// which subjects and names don't have in them.
+const LOG_FORMAT: &str = "--format=%h%x1f%ct%x1f%an%x1f%s";
+
+
+/// One commit, as git log describes it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Commit {
+    pub hash: String,
+    /// When it was committed, in seconds since the epoch.
+    pub date: i64,
+    pub author: String,
+    pub subject: String
+}
+
+/// What a branch has that its base hasn't.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UniqueCommits {
+    pub branch: String,
+    pub base: String,
+    /// The latest of them, newest first, up to the number asked for.
+    pub commits: Vec<Commit>,
+    /// How many there are in all, shown or not.
+    pub total: usize,
+    /// `git diff --stat` of the branch since it left the base, when asked
+    /// for.
+    pub diffstat: Option<String>
+}
+
+/// The commits in `log`, as git log writes them with [`LOG_FORMAT`].
+/// Lines that aren't are skipped.
+pub fn parse_log(log: &str) -> Vec<Commit> {
+    log.lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(4, '\u{1f}');
+            let commit = Commit {
+                hash: fields.next()?.to_string(),
+                date: fields.next()?.parse().ok()?,
+                author: fields.next()?.to_string(),
+                subject: fields.next()?.to_string()
+            };
+            Some(commit)
+        })
+        .collect()
+}
+
+/// The commits of `branch` in the repository at `dir` that `base` hasn't,
+/// the latest `max` of them, with the diffstat when `diffstat` is set.
+pub fn unique_commits(dir: &Path, branch: &str, base: &str, max: usize, diffstat: bool) -> Result<UniqueCommits, BruneError> {
+    let branch_ref = format!("refs/heads/{}", branch);
+    let range = format!("{}..{}", base, branch_ref);
+    let total = git::run_git(dir, &["rev-list", "--count", &range])?;
+    let total = total.trim().parse().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("unexpected commit count {:?}: {}", total.trim(), e)))?;
+    let commits = parse_log(&git::run_git(dir, &["log", "--no-color", LOG_FORMAT, "-n", &max.to_string(), &range])?);
+    let diffstat = match diffstat {
+        true => Some(git::run_git(dir, &["diff", "--no-color", "--stat", &format!("{}...{}", base, branch_ref)])?),
+        false => None
+    };
+    Ok(UniqueCommits { branch: branch.to_string(), base: base.to_string(), commits, total, diffstat })
+}
+
+/// The commits under a heading saying how many there are, one a line in
+/// columns of hash, date, author and subject, then the diffstat. When
+/// there are more than are shown, it says how many weren't.
+pub fn write_human<W: Write>(out: &mut W, unique: &UniqueCommits, dates: &DateStyle, messages: &Messages) -> io::Result<()> {
+    let names: [(&str, &dyn std::fmt::Display); 2] = [("branch", &unique.branch), ("base", &unique.base)];
+    if unique.total == 0 {
+        return writeln!(out, "{}", messages.render("show.none", &names))
+    }
+    let commits = messages.counted("commit", unique.total);
+    writeln!(out, "{}", messages.render("show.heading", &[names[0], names[1], ("commits", &commits)]))?;
+
+    let rendered: Vec<String> = unique.commits.iter().map(|c| dates.render(c.date)).collect();
+    let width = |column: &dyn Fn(usize) -> usize| (0..unique.commits.len()).map(column).max().unwrap_or_default();
+    let date_width = width(&|i| rendered[i].chars().count());
+    let author_width = width(&|i| unique.commits[i].author.chars().count());
+    for (c, date) in unique.commits.iter().zip(&rendered) {
+        let pad = |text: &str, width: usize| format!("{}{}", text, " ".repeat(width - text.chars().count()));
+        writeln!(out, "{}  {}  {}  {}", c.hash, pad(date, date_width), pad(&c.author, author_width), c.subject)?
+    }
+
+    if unique.total > unique.commits.len() {
+        let more = unique.total - unique.commits.len();
+        writeln!(out, "{}", messages.render("show.capped", &[("shown", &unique.commits.len()), ("more", &messages.counted("commit", more))]))?
+    }
+    if let Some(diffstat) = unique.diffstat.as_deref().filter(|diffstat| !diffstat.is_empty()) {
+        writeln!(out)?;
+        write!(out, "{}", diffstat)?
+    }
+    Ok(())
+}
+
+
+#[cfg(test)]
+fn sample(total: usize, diffstat: Option<&str>) -> UniqueCommits {
+    UniqueCommits {
+        branch: "feature/x".to_string(),
+        base: "main".to_string(),
+        commits: parse_log(
+            "1111aaaa\u{1f}1700000000\u{1f}Ada Lovelace\u{1f}Add the engine\n\
+             2222bbbb\u{1f}1699990000\u{1f}Bo\u{1f}Fix: a | b\u{1f}c\n\
+             not a commit\n"
+        ),
+        total,
+        diffstat: diffstat.map(str::to_string)
+    }
+}
+
+/// 1. Separators in subjects are kept, other lines skipped
+#[test]
+fn show_parse_log() {
+    let commits = sample(2, None).commits;
+    assert_eq!(commits.len(), 2);
+    assert_eq!(commits[1], Commit { hash: "2222bbbb".to_string(), date: 1699990000, author: "Bo".to_string(), subject: "Fix: a | b\u{1f}c".to_string() });
+}
+
+/// 1. Columns line up, with the diffstat after
+/// 2. A capped listing says how many more there are
+/// 3. A branch with nothing of its own says so, in German too
+#[test]
+fn show_write_human() {
+    let render = |unique: &UniqueCommits, messages: &Messages| {
+        let mut out = Vec::new();
+        write_human(&mut out, unique, &DateStyle::relative(1700000000 + 3600), messages).unwrap();
+        String::from_utf8(out).unwrap()
+    };
+    let english = Messages::default();
+
+    assert_eq!(
+        render(&sample(2, Some(" src/lib.rs | 2 ++\n 1 file changed, 2 insertions(+)\n")), &english),
+        "2 commits on feature/x that main hasn't\n\
+         1111aaaa  1 hour ago   Ada Lovelace  Add the engine\n\
+         2222bbbb  3 hours ago  Bo            Fix: a | b\u{1f}c\n\
+         \n \
+         src/lib.rs | 2 ++\n \
+         1 file changed, 2 insertions(+)\n"
+    );
+    assert_eq!(
+        render(&sample(241, Some("")), &english),
+        "241 commits on feature/x that main hasn't\n\
+         1111aaaa  1 hour ago   Ada Lovelace  Add the engine\n\
+         2222bbbb  3 hours ago  Bo            Fix: a | b\u{1f}c\n\
+         The latest 2 are shown, and 239 commits older, which a higher --max-commits shows\n"
+    );
+
+    let none = UniqueCommits { commits: Vec::new(), total: 0, ..sample(0, None) };
+    assert_eq!(render(&none, &english), "feature/x has no commits that main hasn't\n");
+    assert_eq!(render(&none, &Messages::new(crate::messages::GERMAN)), "feature/x hat keine Commits, die main nicht hat\n");
+}
+
+/// 1. Only what the branch has, newest first and capped, with how many
+///    there are in all
+#[test]
+fn show_unique_commits() {
+    let dir = git::temp_repo("show");
+    git::run_git(&dir, &["checkout", "-q", "feature/a"]).unwrap();
+    for subject in ["First", "Second", "Third"] {
+        std::fs::write(dir.join("notes.txt"), subject).unwrap();
+        git::run_git(&dir, &["add", "notes.txt"]).unwrap();
+        git::run_git(&dir, &["-c", "user.name=Ada", "-c", "user.email=ada@example.com", "commit", "-q", "-m", subject]).unwrap();
+    }
+
+    let unique = unique_commits(&dir, "feature/a", "main", 2, true).unwrap();
+    assert_eq!(unique.total, 3);
+    assert_eq!(unique.commits.iter().map(|c| c.subject.as_str()).collect::<Vec<_>>(), vec!["Third", "Second"]);
+    assert!(unique.commits.iter().all(|c| c.author == "Ada" && c.date > 0));
+    assert!(unique.diffstat.unwrap().contains("notes.txt"));
+
+    let none = unique_commits(&dir, "main", "feature/a", 2, false).unwrap();
+    assert_eq!((none.total, none.commits.len(), none.diffstat), (0, 0, None));
+}
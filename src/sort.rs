@@ -0,0 +1,124 @@
+//! Ordering and grouping branches for output.
+
+use std::cmp::Ordering;
+
+use crate::{BranchStatus, BranchLine};
+
+
+/// What to order branches by.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortKey {
+    Name,
+    Hash,
+    /// Gone branches first, then the rest.
+    Status,
+    /// Oldest [`last_commit_date`](BranchLine::last_commit_date)
+    /// first, branches without a date last.
+    Age
+}
+
+fn status_rank(status: &BranchStatus) -> u8 {
+    if status.is_gone() { 0 } else { 1 }
+}
+
+fn compare(key: SortKey, a: &BranchLine, b: &BranchLine) -> Ordering {
+    match key {
+        SortKey::Name => a.branch_name.cmp(&b.branch_name),
+        SortKey::Hash => a.commit.as_str().cmp(b.commit.as_str()),
+        SortKey::Status => status_rank(&a.status).cmp(&status_rank(&b.status)),
+        SortKey::Age => match (a.last_commit_date, b.last_commit_date) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal
+        }
+    }
+}
+
+/// Sorts `branches` by `key`. The sort is stable, so branches that compare
+/// equal keep their input order, also when reversed.
+pub fn sort_branches(branches: &mut [&BranchLine], key: SortKey, reverse: bool) {
+    branches.sort_by(|a, b| {
+        let ordering = compare(key, a, b);
+        if reverse { ordering.reverse() } else { ordering }
+    })
+}
+
+/// The part of `name` before the first `/`, or `""` when there is none.
+pub fn prefix(name: &str) -> &str {
+    name.split_once('/').map_or("", |(prefix, _)| prefix)
+}
+
+/// Groups branches by [`prefix`]. Groups are in order of their first branch
+/// and keep the order of their branches, so an earlier sort carries through.
+pub fn group_by_prefix<'a>(branches: &[&'a BranchLine]) -> Vec<(&'a str, Vec<&'a BranchLine>)> {
+    let mut groups: Vec<(&str, Vec<&BranchLine>)> = Vec::new();
+    for b in branches {
+        let p = prefix(&b.branch_name);
+        match groups.iter_mut().find(|(name, _)| *name == p) {
+            Some((_, members)) => members.push(b),
+            None => groups.push((p, vec![b]))
+        }
+    }
+    groups
+}
+
+
+#[cfg(test)]
+fn sorted_names(key: SortKey, reverse: bool) -> Vec<String> {
+    let branches = crate::parse_branch_lines("  FeatureB eeee3333 B\n  FeatureC dddd3333 [gone] C\n  FeatureA ffff1111 A\n  FeatureD aaaa4444 [gone] D\n");
+    let mut branches: Vec<&BranchLine> = branches.iter().collect();
+    sort_branches(&mut branches, key, reverse);
+    branches.iter().map(|b| b.branch_name.clone()).collect()
+}
+
+#[test]
+fn sort_by_name() {
+    assert_eq!(sorted_names(SortKey::Name, false), vec!["FeatureA", "FeatureB", "FeatureC", "FeatureD"]);
+    assert_eq!(sorted_names(SortKey::Name, true), vec!["FeatureD", "FeatureC", "FeatureB", "FeatureA"]);
+}
+
+#[test]
+fn sort_by_hash() {
+    assert_eq!(sorted_names(SortKey::Hash, false), vec!["FeatureD", "FeatureC", "FeatureB", "FeatureA"]);
+}
+
+/// 1. Gone branches first
+/// 2. Ties keep input order, also when reversed
+#[test]
+fn sort_by_status() {
+    assert_eq!(sorted_names(SortKey::Status, false), vec!["FeatureC", "FeatureD", "FeatureB", "FeatureA"]);
+    assert_eq!(sorted_names(SortKey::Status, true), vec!["FeatureB", "FeatureA", "FeatureC", "FeatureD"]);
+}
+
+/// 1. Oldest first
+/// 2. Branches without a date last
+#[test]
+fn sort_by_age() {
+    let mut branches = crate::parse_branch_lines("  FeatureA ffff1111 A\n  FeatureB eeee3333 B\n  FeatureC dddd3333 C\n");
+    branches[0].last_commit_date = Some(200);
+    branches[2].last_commit_date = Some(100);
+    let mut branches: Vec<&BranchLine> = branches.iter().collect();
+    sort_branches(&mut branches, SortKey::Age, false);
+    let names: Vec<&str> = branches.iter().map(|b| b.branch_name.as_str()).collect();
+    assert_eq!(names, vec!["FeatureC", "FeatureA", "FeatureB"]);
+}
+
+#[test]
+fn prefix_is_before_first_slash() {
+    assert_eq!(prefix("PERSON1/FeatureD"), "PERSON1");
+    assert_eq!(prefix("PERSON1/team/FeatureD"), "PERSON1");
+    assert_eq!(prefix("master"), "");
+}
+
+#[test]
+fn groups_keep_first_appearance_order() {
+    let branches = crate::parse_branch_lines("  bob/b eeee3333 B\n  master dddd3333 C\n  alice/a ffff1111 A\n  bob/c aaaa4444 D\n");
+    let branches: Vec<&BranchLine> = branches.iter().collect();
+    let groups: Vec<(&str, Vec<&str>)> =
+        group_by_prefix(&branches)
+            .into_iter()
+            .map(|(p, bs)| (p, bs.iter().map(|b| b.branch_name.as_str()).collect()))
+            .collect();
+    assert_eq!(groups, vec![("bob", vec!["bob/b", "bob/c"]), ("", vec!["master"]), ("alice", vec!["alice/a"])]);
+}
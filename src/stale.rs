@@ -0,0 +1,119 @@
+//! Classifying branches that are probably safe to clean up.
+
+use std::fmt;
+use std::time::Duration;
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use crate::BranchLine;
+
+
+/// How long a branch can go without commits before `stale` lists it, when
+/// neither `--older-than` nor the config say otherwise.
+pub const DEFAULT_STALE_AGE: Duration = Duration::from_secs(90 * 24 * 60 * 60);
+
+
+/// Why a branch is stale, from the strongest reason to the weakest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum StaleReason {
+    /// The upstream is gone.
+    Gone,
+    /// Already merged into the base branch.
+    Merged,
+    /// No commits for longer than the age given to [`classify`].
+    Untouched
+}
+
+impl fmt::Display for StaleReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StaleReason::Gone => write!(f, "gone"),
+            StaleReason::Merged => write!(f, "merged"),
+            StaleReason::Untouched => write!(f, "untouched")
+        }
+    }
+}
+
+
+/// A stale branch and every reason it is stale, strongest first.
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct StaleBranch<'a> {
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub branch: &'a BranchLine,
+    pub reasons: Vec<StaleReason>
+}
+
+impl StaleBranch<'_> {
+    /// The reasons separated by commas, e.g. `gone, untouched`.
+    pub fn reason_list(&self) -> String {
+        self.reasons.iter().map(StaleReason::to_string).collect::<Vec<_>>().join(", ")
+    }
+}
+
+
+/// The stale branches, most clearly stale first: by their strongest
+/// reason, then by how many reasons they have. The sort is stable, so an
+/// earlier ordering carries through otherwise.
+///
+/// Branches count as untouched when their
+/// [`last_commit_date`](BranchLine::last_commit_date) is more than `age`
+/// before `now`, in seconds since the epoch, and as merged when
+/// [`merged`](BranchLine::merged) is set, so both need filling in first.
+/// The checked out branch is never stale.
+pub fn classify<'a>(branches: &[&'a BranchLine], now: i64, age: Duration) -> Vec<StaleBranch<'a>> {
+    let cutoff = now.saturating_sub(age.as_secs() as i64);
+
+    let mut stale: Vec<StaleBranch> =
+        branches
+            .iter()
+            .filter(|b| !b.is_current)
+            .map(|b| {
+                let reasons = [
+                    (b.status.is_gone(), StaleReason::Gone),
+                    (b.merged, StaleReason::Merged),
+                    (b.last_commit_date.is_some_and(|date| date < cutoff), StaleReason::Untouched)
+                ];
+                StaleBranch { branch: b, reasons: reasons.iter().filter(|(applies, _)| *applies).map(|(_, reason)| *reason).collect() }
+            })
+            .filter(|s| !s.reasons.is_empty())
+            .collect();
+
+    stale.sort_by_key(|s| (s.reasons[0], std::cmp::Reverse(s.reasons.len())));
+    stale
+}
+
+
+/// 1. Gone and untouched comes before just gone
+/// 2. Then merged, then untouched
+/// 3. Fresh branches and the current branch aren't stale
+#[test]
+fn classify_stale_branches() {
+    let mut branches = crate::parse_branch_lines(
+        "  old dddd3333 X\n  \
+           merged eeee4444 X\n  \
+           gone ffff5555 [origin/gone: gone] X\n  \
+           fresh aaaa6666 X\n  \
+           gone-old bbbb7777 [origin/gone-old: gone] X\n\
+         * current cccc8888 [origin/current: gone] X\n"
+    );
+    let day = 24 * 60 * 60;
+    let now = 1_000 * day;
+    for b in branches.iter_mut() {
+        b.last_commit_date = Some(if b.branch_name.ends_with("old") { now - 100 * day } else { now - day })
+    }
+    branches[1].merged = true;
+    let branches: Vec<&BranchLine> = branches.iter().collect();
+
+    let stale = classify(&branches, now, Duration::from_secs(30 * day as u64));
+    let listed: Vec<(&str, String)> = stale.iter().map(|s| (s.branch.branch_name.as_str(), s.reason_list())).collect();
+    assert_eq!(listed, vec![
+        ("gone-old", "gone, untouched".to_string()),
+        ("gone", "gone".to_string()),
+        ("merged", "merged".to_string()),
+        ("old", "untouched".to_string()),
+    ]);
+}
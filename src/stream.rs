@@ -0,0 +1,185 @@
+//! Parsing `git branch -vv` output a line at a time.
+
+use std::borrow::Cow;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead};
+
+use crate::{parse_line, scan, BranchLine, ListingLine};
+
+
+/// Why a line of input couldn't be turned into a branch.
+#[derive(Debug)]
+pub enum ParseError {
+    /// Reading the input failed.
+    Io(io::Error),
+    /// The line isn't in the expected format. Lines and columns count from
+    /// 1, and the column is where the parser gave up.
+    InvalidLine { line_number: usize, column: usize, line: String }
+}
+
+impl ParseError {
+    /// Locates the column of a nom `error` from parsing `line`.
+    pub(crate) fn invalid_line(line_number: usize, line: &str, error: nom::Err<nom::error::Error<&str>>) -> ParseError {
+        let rest = match error {
+            nom::Err::Error(e) | nom::Err::Failure(e) => e.input,
+            nom::Err::Incomplete(_) => ""
+        };
+        let offset = line.len().saturating_sub(rest.len());
+        let column = line.get(..offset).map_or(offset, |consumed| consumed.chars().count()) + 1;
+        ParseError::InvalidLine { line_number, column, line: line.to_string() }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::Io(e) => write!(f, "failed to read branches: {}", e),
+            ParseError::InvalidLine { line_number, column, line } => write!(f, "line {}, column {}: can't parse {:?}", line_number, column, line)
+        }
+    }
+}
+
+impl Error for ParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ParseError::Io(e) => Some(e),
+            ParseError::InvalidLine { .. } => None
+        }
+    }
+}
+
+impl From<io::Error> for ParseError {
+    fn from(e: io::Error) -> Self {
+        ParseError::Io(e)
+    }
+}
+
+/// The branches in `git branch -vv` output, read and parsed one line at a
+/// time so memory use doesn't grow with the size of the input.
+///
+/// Blank lines and detached HEAD lines are skipped. After an I/O error the
+/// iterator ends.
+pub struct BranchLines<R> {
+    reader: R,
+    buffer: String,
+    line_number: usize,
+    failed: bool,
+    clean: Option<Box<Clean>>,
+    scan: bool
+}
+
+type Clean = dyn for<'a> Fn(&'a str) -> Cow<'a, str>;
+
+impl<R: BufRead> BranchLines<R> {
+    pub fn new(reader: R) -> Self {
+        BranchLines { reader, buffer: String::new(), line_number: 0, failed: false, clean: None, scan: false }
+    }
+
+    /// Passes each line through `clean` before parsing it, e.g. to strip
+    /// ANSI colours with [`strip_ansi`](crate::preprocess::strip_ansi).
+    pub fn with_clean<F>(mut self, clean: F) -> Self
+    where
+        F: for<'a> Fn(&'a str) -> Cow<'a, str> + 'static
+    {
+        self.clean = Some(Box::new(clean));
+        self
+    }
+
+    /// Picks out the branch lines as [`scan`](crate::scan) does, skipping
+    /// every other line without an error.
+    pub fn scanning(mut self) -> Self {
+        self.scan = true;
+        self
+    }
+}
+
+impl<R: BufRead> Iterator for BranchLines<R> {
+    type Item = Result<BranchLine, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.failed {
+            self.buffer.clear();
+            match self.reader.read_line(&mut self.buffer) {
+                Ok(0) => return None,
+                Ok(_) => self.line_number += 1,
+                Err(e) => {
+                    self.failed = true;
+                    return Some(Err(e.into()))
+                }
+            }
+
+            let line = self.buffer.trim_end_matches(['\n', '\r']);
+            let line = match &self.clean {
+                Some(clean) => clean(line),
+                None => Cow::Borrowed(line)
+            };
+            let line = line.as_ref();
+            if line.trim().is_empty() || (self.scan && !scan::could_be_branch_line(line.as_bytes())) {
+                continue
+            }
+
+            match parse_line(line) {
+                Ok((_, ListingLine::Branch(branch_line))) if !self.scan || scan::says_enough(&branch_line) => return Some(Ok(branch_line)),
+                Ok(_) => continue,
+                Err(_) if self.scan => continue,
+                Err(e) => return Some(Err(ParseError::invalid_line(self.line_number, line, e)))
+            }
+        }
+        None
+    }
+}
+
+
+/// 1. Blank and detached lines are skipped
+/// 2. Windows line endings
+#[test]
+fn stream_branch_lines() {
+    let input = "* (HEAD detached at 8ef9921) 8ef9921 init\r\n\r\n  FeatureC dddd3333 [gone] Random\r\n  master 0000bbbb Blah\r\n";
+    let names: Vec<String> = BranchLines::new(input.as_bytes()).map(|b| b.unwrap().branch_name).collect();
+    assert_eq!(names, vec!["FeatureC", "master"]);
+}
+
+#[test]
+fn stream_cleaned_lines() {
+    let input = "\x1b[32m* master\x1b[m 0000bbbb Blah\n";
+    let mut lines = BranchLines::new(input.as_bytes()).with_clean(crate::preprocess::strip_ansi);
+    assert_eq!(lines.next().unwrap().unwrap().branch_name, "master");
+}
+
+#[test]
+fn stream_reports_invalid_lines() {
+    let mut lines = BranchLines::new("  FeatureC dddd3333 Random\n\n*\n".as_bytes());
+    assert!(lines.next().unwrap().is_ok());
+    let error = lines.next().unwrap().unwrap_err();
+    assert_eq!(error.to_string(), "line 3, column 2: can't parse \"*\"");
+    assert!(lines.next().is_none());
+}
+
+#[test]
+fn stream_stops_after_read_error() {
+    let mut lines = BranchLines::new(io::BufReader::new(&[0xff, b'\n'][..]));
+    assert!(matches!(lines.next(), Some(Err(ParseError::Io(_)))));
+    assert!(lines.next().is_none());
+}
+
+/// 1. Scanning streams the same branches as a scan of the whole input,
+///    without lines that only have a hash or don't parse
+#[test]
+fn stream_scanning() {
+    let input = "Step 1/3\n  just 1234abcd\n  FeatureC dddd3333 [gone] Random\n  bad 12345xyz X\n* master 0000bbbb Blah\n";
+    let streamed: Vec<BranchLine> = BranchLines::new(input.as_bytes()).scanning().map(Result::unwrap).collect();
+    assert_eq!(streamed, scan::scan_branch_lines(input));
+    assert_eq!(streamed.iter().map(|b| b.branch_name.as_str()).collect::<Vec<_>>(), vec!["FeatureC", "master"]);
+}
+
+/// 1. Invalid branch names are reported where the name starts
+/// 2. After the marker
+#[test]
+fn stream_error_columns() {
+    let error = BranchLines::new("  feature.lock dddd3333 X\n".as_bytes()).next().unwrap().unwrap_err();
+    assert!(matches!(error, ParseError::InvalidLine { line_number: 1, column: 3, .. }), "{:?}", error);
+
+    let error = BranchLines::new("\n+ ~wip dddd3333 X\n".as_bytes()).next().unwrap().unwrap_err();
+    assert!(matches!(error, ParseError::InvalidLine { line_number: 2, column: 3, .. }), "{:?}", error);
+}
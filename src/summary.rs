@@ -0,0 +1,55 @@
+//! Totals over a branch listing, for a quick health check of a repository.
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use crate::protect::ProtectedBranches;
+use crate::BranchLine;
+
+
+/// How many of the branches fall into each state. A branch can be counted
+/// in several, e.g. active, ahead and protected.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Summary {
+    pub total: usize,
+    pub active: usize,
+    pub gone: usize,
+    pub ahead: usize,
+    pub behind: usize,
+    pub merged: usize,
+    pub protected: usize
+}
+
+impl Summary {
+    pub fn of(branches: &[&BranchLine], protected: &ProtectedBranches) -> Summary {
+        let count = |f: &dyn Fn(&BranchLine) -> bool| branches.iter().filter(|b| f(b)).count();
+        Summary {
+            total: branches.len(),
+            active: count(&|b| !b.status.is_gone()),
+            gone: count(&|b| b.status.is_gone()),
+            ahead: count(&|b| b.status.ahead_behind().ahead > 0),
+            behind: count(&|b| b.status.ahead_behind().behind > 0),
+            merged: count(&|b| b.merged),
+            protected: count(&|b| b.is_current || protected.is_protected(&b.branch_name))
+        }
+    }
+}
+
+
+/// 1. Ahead and behind are counted separately
+/// 2. The current branch is protected even when not configured
+#[test]
+fn summary_counts() {
+    let mut branches = crate::parse_branch_lines(
+        "* master 0000bbbb [origin/master: ahead 1, behind 2] A\n  \
+           FeatureC dddd3333 [gone] B\n  \
+           develop eeee4444 [origin/develop: behind 1] C\n  \
+           current aaaa1111 D\n"
+    );
+    branches[3].merged = true;
+    let branches: Vec<&BranchLine> = branches.iter().collect();
+
+    let summary = Summary::of(&branches, &ProtectedBranches::new(["develop"]));
+    assert_eq!(summary, Summary { total: 4, active: 3, gone: 1, ahead: 1, behind: 2, merged: 1, protected: 2 });
+}
@@ -0,0 +1,182 @@
+//! Output shaped by a template of the user's, such as
+//! `{name}\t{hash}\t{status}`, for scripts that need a layout none of the
+//! built in formats give.
+//!
+//! A template is text with placeholders in braces. `\t`, `\n` and `\\`
+//! stand for a tab, a newline and a backslash, so templates can be given
+//! on the command line without the shell's help, and `{{` and `}}` for
+//! literal braces. Each branch is rendered on its own line.
+
+use std::fmt::Write as _;
+use std::io::{self, Write};
+
+use crate::output::format_date;
+use crate::BranchLine;
+
+
+/// Something a placeholder can stand for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Name,
+    Hash,
+    /// The tracking status, e.g. `diverged`.
+    Status,
+    /// `gone` or `active`.
+    Type,
+    Ahead,
+    Behind,
+    Upstream,
+    Remote,
+    Comment,
+    /// The last commit date as `YYYY-MM-DD`.
+    Date,
+    /// The last commit date in seconds since the epoch.
+    LastCommitDate,
+    Current,
+    Worktree,
+    Merged,
+    PrState,
+    Provider
+}
+
+/// Every placeholder, with the field it stands for.
+const FIELDS: &[(&str, Field)] = &[
+    ("name", Field::Name),
+    ("hash", Field::Hash),
+    ("commit", Field::Hash),
+    ("status", Field::Status),
+    ("type", Field::Type),
+    ("ahead", Field::Ahead),
+    ("behind", Field::Behind),
+    ("upstream", Field::Upstream),
+    ("remote", Field::Remote),
+    ("comment", Field::Comment),
+    ("date", Field::Date),
+    ("last_commit_date", Field::LastCommitDate),
+    ("current", Field::Current),
+    ("worktree", Field::Worktree),
+    ("merged", Field::Merged),
+    ("pr_state", Field::PrState),
+    ("provider", Field::Provider),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Text(String),
+    Field(Field)
+}
+
+/// A parsed template.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template(Vec<Segment>);
+
+impl Template {
+    pub fn parse(input: &str) -> Result<Template, String> {
+        let mut segments = Vec::new();
+        let mut text = String::new();
+        let mut chars = input.chars();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some('t') => text.push('\t'),
+                    Some('n') => text.push('\n'),
+                    Some('\\') => text.push('\\'),
+                    Some(other) => { text.push('\\'); text.push(other) },
+                    None => text.push('\\')
+                },
+                '{' if chars.as_str().starts_with('{') => { chars.next(); text.push('{') },
+                '}' if chars.as_str().starts_with('}') => { chars.next(); text.push('}') },
+                '{' => {
+                    let (name, rest) = chars.as_str().split_once('}').ok_or_else(|| format!("unclosed placeholder in template {:?}", input))?;
+                    let field = FIELDS.iter().find(|(n, _)| *n == name).map(|(_, f)| *f).ok_or_else(|| {
+                        let names: Vec<&str> = FIELDS.iter().map(|(n, _)| *n).collect();
+                        format!("unknown placeholder {{{}}}, expected one of {}", name, names.join(", "))
+                    })?;
+                    if !text.is_empty() {
+                        segments.push(Segment::Text(std::mem::take(&mut text)))
+                    }
+                    segments.push(Segment::Field(field));
+                    chars = rest.chars()
+                },
+                '}' => return Err(format!("unmatched }} in template {:?}, write }}}} for a literal one", input)),
+                c => text.push(c)
+            }
+        }
+
+        if !text.is_empty() {
+            segments.push(Segment::Text(text))
+        }
+        Ok(Template(segments))
+    }
+
+    /// The branch as the template lays it out, without a trailing newline.
+    /// Fields a branch doesn't have, like the upstream of a local only
+    /// branch, render as nothing.
+    pub fn render(&self, b: &BranchLine) -> String {
+        let mut rendered = String::new();
+        for segment in &self.0 {
+            let field = match segment {
+                Segment::Text(text) => { rendered.push_str(text); continue },
+                Segment::Field(field) => field
+            };
+            // Writing to a String can't fail.
+            let _ = match field {
+                Field::Name => write!(rendered, "{}", b.branch_name),
+                Field::Hash => write!(rendered, "{}", b.commit),
+                Field::Status => write!(rendered, "{}", b.status.name()),
+                Field::Type => write!(rendered, "{}", b.status.kind()),
+                Field::Ahead => write!(rendered, "{}", b.status.ahead_behind().ahead),
+                Field::Behind => write!(rendered, "{}", b.status.ahead_behind().behind),
+                Field::Upstream => b.upstream.iter().try_for_each(|u| write!(rendered, "{}", u)),
+                Field::Remote => b.upstream.iter().try_for_each(|u| write!(rendered, "{}", u.remote)),
+                Field::Comment => write!(rendered, "{}", b.comment),
+                Field::Date => b.last_commit_date.iter().try_for_each(|d| write!(rendered, "{}", format_date(*d))),
+                Field::LastCommitDate => b.last_commit_date.iter().try_for_each(|d| write!(rendered, "{}", d)),
+                Field::Current => write!(rendered, "{}", b.is_current),
+                Field::Worktree => write!(rendered, "{}", b.checked_out_in_worktree),
+                Field::Merged => write!(rendered, "{}", b.merged),
+                Field::PrState => b.pr_state.iter().try_for_each(|s| write!(rendered, "{}", s)),
+                Field::Provider => b.provider.iter().try_for_each(|p| write!(rendered, "{}", p))
+            };
+        }
+        rendered
+    }
+
+    /// Whether rendering needs [`BranchLine::last_commit_date`] filled in.
+    pub fn uses_dates(&self) -> bool {
+        self.0.iter().any(|s| matches!(s, Segment::Field(Field::Date | Field::LastCommitDate)))
+    }
+}
+
+/// Each branch rendered by `template` on its own line.
+pub fn write_templated<W: Write>(out: &mut W, branches: &[&BranchLine], template: &Template) -> io::Result<()> {
+    for b in branches {
+        writeln!(out, "{}", template.render(b))?
+    }
+    Ok(())
+}
+
+
+/// 1. Escapes and placeholders
+/// 2. Missing fields render as nothing
+/// 3. Literal braces
+#[test]
+fn template_rendering() {
+    let branches = crate::parse_branch_lines("  FeatureC dddd3333 [origin/FeatureC: ahead 1, behind 2] X\n  local eeee4444 Y\n");
+    let template = Template::parse(r"{name}\t{hash}\t{status}\t{upstream}").unwrap();
+    assert_eq!(template.render(&branches[0]), "FeatureC\tdddd3333\tdiverged\torigin/FeatureC");
+    assert_eq!(template.render(&branches[1]), "local\teeee4444\tno_upstream\t");
+    assert_eq!(Template::parse("{{{remote}}} {ahead}/{behind}").unwrap().render(&branches[0]), "{origin} 1/2");
+    assert!(!template.uses_dates());
+}
+
+/// 1. Unknown placeholder
+/// 2. Unclosed placeholder
+/// 3. Unmatched closing brace
+#[test]
+fn template_errors() {
+    assert!(Template::parse("{nmae}").unwrap_err().starts_with("unknown placeholder {nmae}"));
+    assert!(Template::parse("{name").is_err());
+    assert!(Template::parse("name}").is_err());
+}
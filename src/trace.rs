@@ -0,0 +1,156 @@
+//! Following the branch line parser one combinator at a time, to show why
+//! a line it rejects goes wrong.
+
+use std::fmt;
+
+use nom::error::ErrorKind;
+
+use crate::{detached_head_parser, observed_git_line_ref_parser};
+
+
+/// What a combinator made of its input.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Taken<'a> {
+    /// It consumed this much.
+    Consumed(&'a str),
+    /// It is optional and didn't match, so the line carries on without it.
+    Nothing,
+    /// It failed with `kind` at `rest`, and parsing stopped there.
+    Failed { kind: ErrorKind, rest: &'a str }
+}
+
+/// A combinator run on the input left by the steps before it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step<'a> {
+    pub combinator: &'static str,
+    /// How far into the line it started.
+    pub offset: usize,
+    pub taken: Taken<'a>
+}
+
+/// The steps taken parsing one line of `git branch -vv` output.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trace<'a> {
+    pub line: &'a str,
+    /// Whether it is a detached HEAD line, which the branch parser rejects.
+    pub detached_head: bool,
+    pub steps: Vec<Step<'a>>,
+    /// The comment left at the end, when every step went through.
+    pub comment: Option<&'a str>
+}
+
+impl Trace<'_> {
+    /// The column, counting characters from 1, where `rest` starts.
+    fn column(&self, rest: &str) -> usize {
+        let offset = self.line.len().saturating_sub(rest.len());
+        self.line.get(..offset).map_or(offset, |consumed| consumed.chars().count()) + 1
+    }
+}
+
+/// Parses `line` with the parser behind [`parse_branch_line`](crate::parse_branch_line),
+/// noting what each combinator consumed, up to the first that fails.
+pub fn trace(line: &str) -> Trace<'_> {
+    let mut steps = Vec::new();
+    let parsed = observed_git_line_ref_parser(line, &mut |combinator, input, result| {
+        let taken = match result {
+            Ok(rest) => Taken::Consumed(&input[..input.len() - rest.len()]),
+            Err(nom::Err::Error(e) | nom::Err::Failure(e)) => Taken::Failed { kind: e.code, rest: e.input },
+            Err(nom::Err::Incomplete(_)) => Taken::Failed { kind: ErrorKind::Eof, rest: "" }
+        };
+        steps.push(Step { combinator, offset: line.len() - input.len(), taken })
+    });
+
+    // The parser goes on from optional steps that fail, so only the last
+    // step of a line that doesn't parse really failed.
+    let last = if parsed.is_ok() { steps.len() } else { steps.len().saturating_sub(1) };
+    for step in &mut steps[..last] {
+        if let Taken::Failed { .. } = step.taken {
+            step.taken = Taken::Nothing
+        }
+    }
+
+    let detached_head = detached_head_parser(line).is_ok();
+    let comment = parsed.ok().filter(|_| !detached_head).map(|(_, branch)| branch.comment);
+    Trace { line, detached_head, steps, comment }
+}
+
+impl fmt::Display for Trace<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.detached_head {
+            writeln!(f, "a detached HEAD line, which is listed apart from the branches")?
+        }
+        for step in &self.steps {
+            write!(f, "{:<20} ", step.combinator)?;
+            match step.taken {
+                Taken::Consumed(consumed) => writeln!(f, "consumed {:?}", consumed)?,
+                Taken::Nothing => writeln!(f, "found nothing to take, which is fine")?,
+                Taken::Failed { kind, rest } => writeln!(f, "failed ({}) at column {}: {:?}", kind.description(), self.column(rest), rest)?
+            }
+        }
+        match (self.comment, self.steps.last()) {
+            (Some(comment), _) => write!(f, "parsed, leaving the comment {:?}", comment),
+            (None, Some(Step { taken: Taken::Failed { rest, .. }, .. })) => write!(f, "stopped at column {}", self.column(rest)),
+            (None, _) => write!(f, "stopped")
+        }
+    }
+}
+
+
+/// 1. Every step of a full line, ending with its comment
+#[test]
+fn trace_parsed_line() {
+    let traced = trace("* main 0000bbbb [origin/main: behind 2] Blah");
+    let consumed: Vec<(&str, &Taken)> = traced.steps.iter().map(|s| (s.combinator, &s.taken)).collect();
+    assert_eq!(consumed, vec![
+        ("take_marker", &Taken::Consumed("* ")),
+        ("take_branch_name", &Taken::Consumed("main")),
+        ("take_whitespace", &Taken::Consumed(" ")),
+        ("take_hex", &Taken::Consumed("0000bbbb")),
+        ("take_whitespace", &Taken::Consumed(" ")),
+        ("take_annotation", &Taken::Consumed("[origin/main: behind 2]")),
+        ("take_whitespace", &Taken::Consumed(" ")),
+    ]);
+    assert_eq!(traced.comment, Some("Blah"));
+    assert!(!traced.detached_head);
+}
+
+/// 1. A missing annotation is fine
+/// 2. The worktree path is only looked for after `+`
+#[test]
+fn trace_optional_steps() {
+    let traced = trace("  FeatureA dddeeee Random");
+    assert_eq!(traced.steps[5], Step { combinator: "take_annotation", offset: 19, taken: Taken::Nothing });
+    assert_eq!(traced.comment, Some("Random"));
+
+    let traced = trace("+ wt 1234abc (/tmp/wt) Random");
+    assert_eq!(traced.steps[5].combinator, "take_worktree_path");
+    assert_eq!(traced.steps[5].taken, Taken::Consumed("(/tmp/wt) "));
+}
+
+/// 1. Parsing stops at the first failure, which is shown where it happened
+#[test]
+fn trace_failed_line() {
+    let traced = trace("  FeatureA abc123xyz Random");
+    assert_eq!(traced.steps.len(), 4);
+    assert_eq!(traced.comment, None);
+    assert!(traced.to_string().starts_with("take_marker          consumed \"  \"\n"));
+    assert!(traced.to_string().ends_with("take_hex             failed (Negation) at column 18: \"xyz Random\"\nstopped at column 18"));
+
+    assert!(trace("* (HEAD detached at abc1234) abc1234 X").detached_head);
+}
+
+/// 1. The trace agrees with the parser on every line of a listing
+#[test]
+fn trace_matches_parser() {
+    let listing = "* master 0000bbbb [origin/master: ahead 1, behind 2] Blah\n  \
+                     FeatureC dddd3333 [gone] Random\n  \
+                     feature abc1 [WIP] subject\n\
+                   + wt1 8ef9921 (/tmp/wt1) [origin/wt1] init\n  \
+                     FeatureA abc123xyz Random\n  \
+                     deadbeef a b\n\
+                   * (HEAD detached at abc1234) abc1234 X";
+    for line in listing.lines() {
+        let parsed = crate::parse_branch_line(line).ok().map(|(_, b)| b.comment);
+        assert_eq!(trace(line).comment.map(str::to_string), parsed, "{}", line);
+    }
+}
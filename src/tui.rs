@@ -0,0 +1,228 @@
+//! An interactive list for choosing which branches to delete.
+//!
+//! Only available with the `tui` feature.
+
+use std::io;
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::crossterm::terminal;
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Paragraph, Row, Table, TableState};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::BranchLine;
+
+
+/// What the user decided when leaving the list.
+#[derive(Debug, PartialEq)]
+pub enum Outcome {
+    /// Delete these branches.
+    Delete(Vec<String>),
+    /// Leave without deleting anything.
+    Quit
+}
+
+/// The state of the branch list: which row has the cursor and which
+/// branches are selected. Gone branches start out selected.
+#[derive(Debug)]
+pub struct App<'a> {
+    branches: &'a [&'a BranchLine],
+    selected: Vec<bool>,
+    table: TableState
+}
+
+impl<'a> App<'a> {
+    pub fn new(branches: &'a [&'a BranchLine]) -> Self {
+        let selected = branches.iter().map(|b| b.status.is_gone()).collect();
+        let table = TableState::default().with_selected(if branches.is_empty() { None } else { Some(0) });
+        App { branches, selected, table }
+    }
+
+    /// The names of the selected branches, in listing order.
+    pub fn selected_names(&self) -> Vec<String> {
+        self.branches
+            .iter()
+            .zip(&self.selected)
+            .filter(|(_, selected)| **selected)
+            .map(|(b, _)| b.branch_name.clone())
+            .collect()
+    }
+
+    /// The branch the cursor is on.
+    pub fn highlighted(&self) -> Option<&'a BranchLine> {
+        self.table.selected().map(|row| self.branches[row])
+    }
+
+    /// Applies a key press, returning the outcome once the user is done.
+    pub fn handle_key(&mut self, key: KeyCode) -> Option<Outcome> {
+        match key {
+            KeyCode::Char('q') | KeyCode::Esc => return Some(Outcome::Quit),
+            KeyCode::Char('d') => return Some(Outcome::Delete(self.selected_names())),
+            KeyCode::Char(' ') => {
+                if let Some(row) = self.table.selected() {
+                    self.selected[row] = !self.selected[row]
+                }
+            },
+            KeyCode::Down | KeyCode::Char('j') => self.move_cursor(1),
+            KeyCode::Up | KeyCode::Char('k') => self.move_cursor(-1),
+            _ => ()
+        }
+        None
+    }
+
+    fn move_cursor(&mut self, delta: isize) {
+        if let Some(row) = self.table.selected() {
+            let last = self.branches.len() - 1;
+            let row = row.saturating_add_signed(delta).min(last);
+            self.table.select(Some(row))
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [list, help] = Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+
+        let rows = self.branches.iter().zip(&self.selected).map(|(b, selected)| {
+            let style =
+                if b.status.is_gone() {
+                    Style::default().fg(Color::Red)
+                } else if b.is_current {
+                    Style::default().fg(Color::Green)
+                } else {
+                    Style::default()
+                };
+            let tracking = b.status.ahead_behind().to_string();
+            Row::new(vec![
+                if *selected { "[x]".to_string() } else { "[ ]".to_string() },
+                b.branch_name.clone(),
+                b.commit.to_string(),
+                b.status.kind().to_string(),
+                tracking,
+                b.comment.clone()
+            ]).style(style)
+        });
+
+        let widths = [Constraint::Length(3), Constraint::Fill(2), Constraint::Length(8), Constraint::Length(6), Constraint::Length(18), Constraint::Fill(3)];
+        let table =
+            Table::new(rows, widths)
+                .header(Row::new(vec!["", "branch", "commit", "status", "tracking", "comment"]).style(Style::default().add_modifier(Modifier::BOLD)))
+                .block(Block::bordered().title(" brune "))
+                .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+        frame.render_stateful_widget(table, list, &mut self.table);
+        frame.render_widget(Paragraph::new(Line::from("↑/↓ move  space toggle  s show commits  d delete selected  q quit")), help);
+    }
+}
+
+
+/// Shows the branch list until the user deletes or quits. `s` leaves it
+/// for `show` to write about the highlighted branch, such as the commits
+/// only it has, to the terminal as usual, and comes back on a key press.
+pub fn run(branches: &[&BranchLine], show: &dyn Fn(&BranchLine) -> io::Result<()>) -> io::Result<Outcome> {
+    let mut terminal = ratatui::init();
+    let outcome = event_loop(&mut terminal, &mut App::new(branches), show);
+    ratatui::restore();
+    outcome
+}
+
+fn event_loop(terminal: &mut DefaultTerminal, app: &mut App, show: &dyn Fn(&BranchLine) -> io::Result<()>) -> io::Result<Outcome> {
+    loop {
+        terminal.draw(|frame| app.draw(frame))?;
+        let key = match event::read()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => key.code,
+            _ => continue
+        };
+        match (key, app.highlighted()) {
+            (KeyCode::Char('s'), Some(b)) => {
+                ratatui::restore();
+                show(b)?;
+                println!("Press a key to go back to the list");
+                terminal::enable_raw_mode()?;
+                while !matches!(event::read()?, Event::Key(key) if key.kind == KeyEventKind::Press) {}
+                *terminal = ratatui::init()
+            },
+            _ => if let Some(outcome) = app.handle_key(key) {
+                return Ok(outcome)
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+fn test_branches() -> Vec<BranchLine> {
+    crate::parse_branch_lines("* master 0000bbbb [behind 2] Blah\n  FeatureC dddd3333 [gone] Gone\n  FeatureA dddeeee Active\n  FeatureD eeee4444 [gone] Also gone\n")
+}
+
+#[test]
+fn tui_gone_branches_preselected() {
+    let branches = test_branches();
+    let branches: Vec<&BranchLine> = branches.iter().collect();
+    let app = App::new(&branches);
+    assert_eq!(app.selected_names(), vec!["FeatureC", "FeatureD"]);
+}
+
+/// 1. Move down and toggle a branch on
+/// 2. Move down and toggle a preselected branch off
+/// 3. Moving past the end stays on the last row
+#[test]
+fn tui_toggle_and_delete() {
+    let branches = test_branches();
+    let branches: Vec<&BranchLine> = branches.iter().collect();
+    let mut app = App::new(&branches);
+
+    for key in [KeyCode::Down, KeyCode::Down, KeyCode::Char(' '), KeyCode::Char('j'), KeyCode::Down, KeyCode::Char(' ')] {
+        assert_eq!(app.handle_key(key), None);
+    }
+
+    assert_eq!(app.handle_key(KeyCode::Char('d')), Some(Outcome::Delete(vec!["FeatureC".to_string(), "FeatureA".to_string()])));
+}
+
+/// 1. Moving up from the first row stays there
+/// 2. Quitting deletes nothing
+#[test]
+fn tui_quit() {
+    let branches = test_branches();
+    let branches: Vec<&BranchLine> = branches.iter().collect();
+    let mut app = App::new(&branches);
+    assert_eq!(app.handle_key(KeyCode::Up), None);
+    assert_eq!(app.handle_key(KeyCode::Char(' ')), None);
+    assert_eq!(app.selected_names(), vec!["master", "FeatureC", "FeatureD"]);
+    assert_eq!(app.handle_key(KeyCode::Char('q')), Some(Outcome::Quit));
+}
+
+#[test]
+fn tui_empty_list() {
+    let branches = Vec::new();
+    let mut app = App::new(&branches);
+    assert_eq!(app.handle_key(KeyCode::Down), None);
+    assert_eq!(app.handle_key(KeyCode::Char(' ')), None);
+    assert_eq!(app.handle_key(KeyCode::Char('d')), Some(Outcome::Delete(vec![])));
+}
+
+#[test]
+fn tui_draw() {
+    let branches = test_branches();
+    let branches: Vec<&BranchLine> = branches.iter().collect();
+    let mut app = App::new(&branches);
+    let mut terminal = ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 8)).unwrap();
+    terminal.draw(|frame| app.draw(frame)).unwrap();
+
+    let screen: String = terminal.backend().buffer().content().iter().map(|cell| cell.symbol()).collect();
+    assert!(screen.contains("[x] FeatureC"));
+    assert!(screen.contains("[ ] FeatureA"));
+    assert!(screen.contains("space toggle"));
+}
+
+/// 1. The cursor's branch, following it as it moves
+#[test]
+fn tui_highlighted() {
+    let branches = test_branches();
+    let branches: Vec<&BranchLine> = branches.iter().collect();
+    let mut app = App::new(&branches);
+    assert_eq!(app.highlighted().map(|b| b.branch_name.as_str()), Some("master"));
+    app.handle_key(KeyCode::Down);
+    assert_eq!(app.highlighted().map(|b| b.branch_name.as_str()), Some("FeatureC"));
+    assert_eq!(App::new(&[]).highlighted(), None);
+}
@@ -0,0 +1,111 @@
+//! Following how the branches change between one look and the next, for
+//! keeping an eye on a repository while others merge and delete upstreams.
+
+use std::fmt;
+use std::time::Duration;
+
+use nom::character::complete::{digit1, one_of};
+use nom::combinator::{all_consuming, map_res};
+use nom::sequence::pair;
+use nom::IResult;
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use crate::BranchLine;
+
+
+/// How long to wait between looks when not told otherwise.
+pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(60);
+
+
+fn take_interval(input: &str) -> IResult<&str, (u64, char)> {
+    all_consuming(pair(map_res(digit1, str::parse), one_of("smh")))(input)
+}
+
+/// Parses an interval like `30s`, `5m` or `1h`, which must not be zero.
+pub fn parse_interval(input: &str) -> Result<Duration, String> {
+    let (_, (count, unit)) = take_interval(input).map_err(|_| format!("invalid interval {:?}, expected a number followed by s, m or h", input))?;
+    let seconds = match unit {
+        's' => 1,
+        'm' => 60,
+        _ => 60 * 60
+    };
+    match count.checked_mul(seconds) {
+        Some(0) => Err("the interval can't be zero".to_string()),
+        Some(secs) => Ok(Duration::from_secs(secs)),
+        None => Err(format!("interval {:?} is too long", input))
+    }
+}
+
+
+/// A change to the branches since the last look.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(tag = "change", rename_all = "lowercase"))]
+pub enum Change {
+    /// The upstream of the branch has gone, or it is new and already gone.
+    Gone { name: String },
+    /// The branch no longer exists.
+    Deleted { name: String }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Change::Gone { name } => write!(f, "{} is gone", name),
+            Change::Deleted { name } => write!(f, "{} was deleted", name)
+        }
+    }
+}
+
+
+/// What changed from `before` to `after`: the newly gone branches in the
+/// order of `after`, then the deleted ones in the order of `before`.
+pub fn changes(before: &[&BranchLine], after: &[&BranchLine]) -> Vec<Change> {
+    let find = |branches: &[&BranchLine], name: &str| branches.iter().any(|b| b.branch_name == name);
+    let was_gone = |name: &str| before.iter().any(|b| b.branch_name == name && b.status.is_gone());
+
+    let gone =
+        after
+            .iter()
+            .filter(|b| b.status.is_gone() && !was_gone(&b.branch_name))
+            .map(|b| Change::Gone { name: b.branch_name.clone() });
+
+    let deleted =
+        before
+            .iter()
+            .filter(|b| !find(after, &b.branch_name))
+            .map(|b| Change::Deleted { name: b.branch_name.clone() });
+
+    gone.chain(deleted).collect()
+}
+
+
+/// 1. Seconds, minutes and hours
+/// 2. Zero, missing or unknown units, and overflow
+#[test]
+fn parse_intervals() {
+    assert_eq!(parse_interval("30s"), Ok(Duration::from_secs(30)));
+    assert_eq!(parse_interval("5m"), Ok(Duration::from_secs(5 * 60)));
+    assert_eq!(parse_interval("1h"), Ok(Duration::from_secs(60 * 60)));
+
+    assert!(parse_interval("0s").is_err());
+    assert!(parse_interval("60").is_err());
+    assert!(parse_interval("1d").is_err());
+    assert!(parse_interval("99999999999999999h").is_err());
+}
+
+/// 1. A branch whose upstream went
+/// 2. A new branch that is already gone
+/// 3. A branch that was already gone isn't reported again
+/// 4. A deleted branch
+#[test]
+fn watch_changes() {
+    let before = crate::parse_branch_lines("  going dddd3333 [origin/going] X\n  old eeee4444 [origin/old: gone] X\n  deleted ffff5555 X\n");
+    let after = crate::parse_branch_lines("  going dddd3333 [origin/going: gone] X\n  old eeee4444 [origin/old: gone] X\n  new aaaa6666 [origin/new: gone] X\n");
+    let before: Vec<&BranchLine> = before.iter().collect();
+    let after: Vec<&BranchLine> = after.iter().collect();
+
+    let listed: Vec<String> = changes(&before, &after).iter().map(Change::to_string).collect();
+    assert_eq!(listed, vec!["going is gone", "new is gone", "deleted was deleted"]);
+}